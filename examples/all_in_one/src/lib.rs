@@ -1,17 +1,21 @@
 use b3_utils::{
-    hex_string_with_0x_to_u128,
+    caller_is_controller, hex_string_with_0x_to_u128,
     http::{HttpRequest, HttpResponse, HttpResponseBuilder},
     log_cycle,
     logs::{export_log, LogEntry},
     memory::{
         init_stable_mem_refcell,
-        timer::{DefaultTaskTimer, TaskTimerEntry},
+        timer::{
+            DeadLetter, DeadLetterQueue, DefaultTaskTimer, RetryPolicy, TaskTimerEntry,
+            TaskTimerExecutor,
+        },
         types::{Bound, DefaultStableBTreeMap, PartitionDetail, Storable},
         with_stable_mem,
     },
+    nonce::Nonce,
     outcall::{HttpOutcall, HttpOutcallResponse},
-    owner::{caller_is_owner, get_owner, set_owner},
-    report_log, NanoTimeStamp,
+    owner::{caller_is_owner, get_owner, init_owner_with, set_owner},
+    report_log,
 };
 use candid::{CandidType, Principal};
 use ic_cdk::{init, post_upgrade, query, update};
@@ -37,6 +41,10 @@ thread_local! {
     static TRANSACTIONS: RefCell<DefaultStableBTreeMap<TransactionHash, TranasactionValue>> = init_stable_mem_refcell("trasnactions", 2).unwrap();
     static RECEIPTS: RefCell<DefaultStableBTreeMap<TransactionHash, ReceiptFrom>> = init_stable_mem_refcell("receipts", 3).unwrap();
     static EXTERNAL_TRANSFERS: RefCell<DefaultStableBTreeMap<TransactionHash, String>> = init_stable_mem_refcell("external_transfers", 4).unwrap();
+    static DEAD_LETTERS: RefCell<DeadLetterQueue<Task>> = init_stable_mem_refcell("dead_letters", 5).unwrap();
+    static EXECUTOR: TaskTimerExecutor<Task> = TaskTimerExecutor::new(&TASK_TIMER, execute_task)
+        .retry_policy(RetryPolicy::new(5))
+        .dead_letters(&DEAD_LETTERS);
 }
 
 #[derive(Debug, Clone, CandidType, Serialize, Deserialize)]
@@ -66,13 +74,16 @@ impl Storable for Task {
 fn init() {
     log_cycle!("Init");
 
+    init_owner_with(ic_cdk::caller()).unwrap();
+
     schedule_task(10, Task::GetLatestExternalTransfer("0x43d20e".to_string()));
 }
 
 #[post_upgrade]
 fn post_upgrade() {
     log_cycle!("Post upgrade");
-    reschedule();
+
+    EXECUTOR.with(|executor| executor.start());
 }
 
 #[query]
@@ -91,6 +102,8 @@ fn change_owner(new_owner: Principal) {
 fn stop_timer() {
     log_cycle!("Stop Timer");
 
+    EXECUTOR.with(|executor| executor.stop());
+
     TASK_TIMER.with(|tt| {
         let mut tt = tt.borrow_mut();
 
@@ -315,6 +328,18 @@ fn get_timers() -> Vec<TaskTimerEntry<Task>> {
     })
 }
 
+#[query(guard = "caller_is_controller")]
+fn get_dead_letters() -> Vec<(Nonce, DeadLetter<Task>)> {
+    DEAD_LETTERS.with(|dl| dl.borrow().list())
+}
+
+#[update(guard = "caller_is_controller")]
+fn requeue_dead_letter(id: Nonce) -> Option<Task> {
+    EXECUTOR
+        .with(|executor| executor.requeue_dead_letter(&id))
+        .unwrap()
+}
+
 #[query]
 fn get_partition_details() -> Vec<PartitionDetail> {
     with_stable_mem(|pm| pm.partition_details())
@@ -327,46 +352,14 @@ fn print_log_entries() -> Vec<LogEntry> {
 
 #[update]
 fn schedule_task(after_sec: u64, task: Task) {
-    let time = NanoTimeStamp::now().add_secs(after_sec);
-
-    let timer = TaskTimerEntry { task, time };
-
-    TASK_TIMER
-        .with(|tt| {
-            let mut tt = tt.borrow_mut();
+    log_cycle!("Task scheduled: {:?}", task);
 
-            tt.push_timer(&timer)
-        })
+    EXECUTOR
+        .with(|executor| executor.schedule_after_secs(after_sec, task))
         .unwrap();
-
-    log_cycle!("Task scheduled: {:?}", timer);
-
-    reschedule();
 }
 
-#[export_name = "canister_global_timer"]
-fn global_timer() {
-    while let Some(task_time) = TASK_TIMER.with(|tt| {
-        let tt = tt.borrow();
-
-        tt.peek_timer()
-    }) {
-        if task_time.time.in_future() {
-            reschedule();
-            return;
-        }
-        TASK_TIMER.with(|tt| {
-            let mut tt = tt.borrow_mut();
-
-            tt.pop_timer()
-        });
-
-        ic_cdk::spawn(execute_task(task_time.task));
-        reschedule();
-    }
-}
-
-async fn execute_task(task: Task) {
+async fn execute_task(task: Task) -> Result<(), String> {
     match task {
         Task::GetLatestExternalTransfer(block_number) => {
             let next_block_number = get_latest_external_transfer(block_number).await;
@@ -374,20 +367,10 @@ async fn execute_task(task: Task) {
             log_cycle!("Task executed: {}", next_block_number);
 
             schedule_task(60, Task::GetLatestExternalTransfer(next_block_number));
-        }
-        _ => panic!("Wrong task"),
-    };
-}
-
-fn reschedule() {
-    if let Some(task_time) = TASK_TIMER.with(|tt| {
-        let tt = tt.borrow();
 
-        tt.peek_timer()
-    }) {
-        unsafe {
-            ic0::global_timer_set(task_time.time.into());
+            Ok(())
         }
+        _ => Err("Wrong task".to_string()),
     }
 }
 
@@ -347,7 +347,7 @@ fn post_upgrade() {
 fn schedule_task(after_sec: u64, task: Task) {
     let time = NanoTimeStamp::now().add_secs(after_sec);
 
-    let timer = TaskTimerEntry { task, time };
+    let timer = TaskTimerEntry::new(time, task);
 
     TASK_TIMER
         .with(|tt| {
@@ -1,13 +1,13 @@
-use candid::CandidType;
-use ic_cdk::api::{
-    call::{call, call_with_payment, call_with_payment128},
-    management_canister::{
-        main::{
-            CanisterInfoRequest, CanisterInfoResponse, CanisterStatusResponse,
-            CreateCanisterArgument, InstallCodeArgument, UpdateSettingsArgument,
-        },
-        provisional::CanisterIdRecord,
+use candid::{
+    utils::{ArgumentDecoder, ArgumentEncoder},
+    CandidType,
+};
+use ic_cdk::api::management_canister::{
+    main::{
+        CanisterInfoRequest, CanisterInfoResponse, CanisterStatusResponse, CreateCanisterArgument,
+        InstallCodeArgument, UpdateSettingsArgument,
     },
+    provisional::CanisterIdRecord,
 };
 use serde::de::DeserializeOwned;
 
@@ -26,31 +26,68 @@ pub use types::*;
 mod cycles;
 pub use cycles::*;
 
+mod accounting;
+pub use accounting::*;
+
+mod transport;
+pub use transport::*;
+
+mod management;
+pub use management::*;
+
 mod app;
 pub use app::*;
 
 pub struct Management;
 
 impl Management {
+    /// Calls `method` with a single argument and decodes a single return value.
     pub async fn call<A, R>(method: &str, args: A, cycles: CallCycles) -> Result<R, ManagementError>
     where
         A: CandidType,
         R: CandidType + DeserializeOwned,
     {
-        let res: Result<(R,), _> = match cycles {
-            CallCycles::Pay128(cycles) => {
-                call_with_payment128(MANAGMENT_CANISTER_ID, method, (args,), cycles).await
-            }
-            CallCycles::Pay(cycles) => {
-                call_with_payment(MANAGMENT_CANISTER_ID, method, (args,), cycles).await
-            }
-            CallCycles::NoPay => call(MANAGMENT_CANISTER_ID, method, (args,)).await,
-        };
+        let (res,) = Management::call_with_args(method, (args,), cycles).await?;
+
+        Ok(res)
+    }
 
-        match res {
-            Ok((res,)) => Ok(res),
-            Err(e) => Err(ManagementError::CallError(method.to_string(), e.1)),
-        }
+    /// Calls `method` with a tuple of arguments and decodes a tuple of return values.
+    pub async fn call_with_args<A, R>(
+        method: &str,
+        args: A,
+        cycles: CallCycles,
+    ) -> Result<R, ManagementError>
+    where
+        A: ArgumentEncoder,
+        R: for<'a> ArgumentDecoder<'a>,
+    {
+        InterCall(MANAGMENT_CANISTER_ID)
+            .call_with_args(method, args, cycles)
+            .await
+            .map_err(ManagementError::from)
+    }
+
+    /// Calls `method` with Candid encoded arguments and returns the encoded reply.
+    pub async fn call_raw(
+        method: &str,
+        args: &[u8],
+        cycles: CallCycles,
+    ) -> Result<Vec<u8>, ManagementError> {
+        InterCall(MANAGMENT_CANISTER_ID)
+            .call_raw(method, args, cycles)
+            .await
+            .map_err(ManagementError::from)
+    }
+
+    /// Calls `method` without waiting for the reply, e.g. `deposit_cycles`.
+    pub fn notify<A>(method: &str, args: A, cycles: CallCycles) -> Result<(), ManagementError>
+    where
+        A: ArgumentEncoder,
+    {
+        InterCall(MANAGMENT_CANISTER_ID)
+            .notify(method, args, cycles)
+            .map_err(ManagementError::from)
     }
 
     pub async fn create_canister(
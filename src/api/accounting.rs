@@ -0,0 +1,212 @@
+use candid::{CandidType, Decode, Encode};
+use ic_cdk::api::call::msg_cycles_refunded128;
+use serde::{Deserialize, Serialize};
+use std::{borrow::Cow, cell::RefCell};
+
+use crate::{
+    memory::{
+        error::StableMemoryError,
+        types::{Bound, DefaultStableBTreeMap, Storable},
+        with_stable_mem_mut,
+    },
+    types::CanisterId,
+    NanoTimeStamp,
+};
+
+mod test;
+
+/// Cycles of the outgoing calls to a method, over a day.
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug, Default, PartialEq)]
+pub struct CyclesUsage {
+    pub calls: u64,
+    pub attached: u128,
+    pub refunded: u128,
+}
+
+impl CyclesUsage {
+    pub fn consumed(&self) -> u128 {
+        self.attached.saturating_sub(self.refunded)
+    }
+
+    fn add(&mut self, other: &CyclesUsage) {
+        self.calls += other.calls;
+        self.attached += other.attached;
+        self.refunded += other.refunded;
+    }
+}
+
+impl Storable for CyclesUsage {
+    const BOUND: Bound = Bound::Unbounded;
+
+    fn from_bytes(bytes: Cow<'_, [u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).unwrap()
+    }
+
+    fn to_bytes(&self) -> Cow<'_, [u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
+}
+
+/// Days since the epoch come first, so the entries of a day are next to each other.
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct CyclesUsageKey {
+    pub day: u64,
+    pub canister_id: CanisterId,
+    pub method: String,
+}
+
+impl Storable for CyclesUsageKey {
+    const BOUND: Bound = Bound::Unbounded;
+
+    fn from_bytes(bytes: Cow<'_, [u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).unwrap()
+    }
+
+    fn to_bytes(&self) -> Cow<'_, [u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
+}
+
+/// Cycles of all outgoing calls over a day.
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct DailyCyclesSpend {
+    pub day: u64,
+    pub usage: CyclesUsage,
+    pub consumed: u128,
+}
+
+thread_local! {
+    static CYCLES_LEDGER: RefCell<Option<DefaultStableBTreeMap<CyclesUsageKey, CyclesUsage>>> = const { RefCell::new(None) };
+}
+
+/// Starts recording the cycles attached to outgoing calls in the partition `name`.
+///
+/// Must be called in `#[init]` and `#[post_upgrade]`, until then nothing is recorded.
+pub fn init_cycles_ledger(name: &str, id: u8) -> Result<(), StableMemoryError> {
+    let ledger = with_stable_mem_mut(|pm| pm.init_memory(name, id))?;
+
+    CYCLES_LEDGER.with(|cycles_ledger| *cycles_ledger.borrow_mut() = Some(ledger));
+
+    Ok(())
+}
+
+pub fn is_cycles_ledger_enabled() -> bool {
+    CYCLES_LEDGER.with(|cycles_ledger| cycles_ledger.borrow().is_some())
+}
+
+/// Adds a call to `method` of `canister_id` to the usage of the current day.
+/// Calls without cycles attached are not recorded.
+pub fn record_cycles(canister_id: CanisterId, method: &str, attached: u128, refunded: u128) {
+    if attached == 0 {
+        return;
+    }
+
+    CYCLES_LEDGER.with(|cycles_ledger| {
+        let mut cycles_ledger = cycles_ledger.borrow_mut();
+
+        let cycles_ledger = match cycles_ledger.as_mut() {
+            Some(cycles_ledger) => cycles_ledger,
+            None => return,
+        };
+
+        let key = CyclesUsageKey {
+            day: NanoTimeStamp::now().get_days(),
+            canister_id,
+            method: method.to_string(),
+        };
+
+        let mut usage = cycles_ledger.get(&key).unwrap_or_default();
+
+        usage.add(&CyclesUsage {
+            calls: 1,
+            attached,
+            refunded,
+        });
+
+        cycles_ledger.insert(key, usage);
+    })
+}
+
+/// Records a call that just returned, with the cycles the callee refunded.
+pub(crate) fn record_call(canister_id: CanisterId, method: &str, attached: u128) {
+    if attached > 0 {
+        record_cycles(canister_id, method, attached, msg_cycles_refunded128());
+    }
+}
+
+/// Returns the usage of every target and method called on `day`.
+pub fn cycles_usage(day: u64) -> Vec<(CyclesUsageKey, CyclesUsage)> {
+    CYCLES_LEDGER.with(|cycles_ledger| match cycles_ledger.borrow().as_ref() {
+        Some(cycles_ledger) => cycles_ledger
+            .range(day_start(day)..)
+            .take_while(|(key, _)| key.day == day)
+            .collect(),
+        None => vec![],
+    })
+}
+
+/// Returns the spend of each day from `from_day` to `to_day` included, skipping days
+/// without calls.
+pub fn cycles_spend_per_day(from_day: u64, to_day: u64) -> Vec<DailyCyclesSpend> {
+    CYCLES_LEDGER.with(|cycles_ledger| {
+        let cycles_ledger = cycles_ledger.borrow();
+
+        let cycles_ledger = match cycles_ledger.as_ref() {
+            Some(cycles_ledger) => cycles_ledger,
+            None => return vec![],
+        };
+
+        let mut spend: Vec<DailyCyclesSpend> = vec![];
+
+        for (key, usage) in cycles_ledger
+            .range(day_start(from_day)..)
+            .take_while(|(key, _)| key.day <= to_day)
+        {
+            match spend.last_mut() {
+                Some(daily) if daily.day == key.day => daily.usage.add(&usage),
+                _ => spend.push(DailyCyclesSpend {
+                    day: key.day,
+                    usage,
+                    consumed: 0,
+                }),
+            }
+        }
+
+        for daily in spend.iter_mut() {
+            daily.consumed = daily.usage.consumed();
+        }
+
+        spend
+    })
+}
+
+/// Removes the usage of the days before `day`, returns the number of entries removed.
+pub fn prune_cycles_ledger(day: u64) -> usize {
+    CYCLES_LEDGER.with(|cycles_ledger| {
+        let mut cycles_ledger = cycles_ledger.borrow_mut();
+
+        let cycles_ledger = match cycles_ledger.as_mut() {
+            Some(cycles_ledger) => cycles_ledger,
+            None => return 0,
+        };
+
+        let keys: Vec<CyclesUsageKey> = cycles_ledger
+            .range(..day_start(day))
+            .map(|(key, _)| key)
+            .collect();
+
+        for key in keys.iter() {
+            cycles_ledger.remove(key);
+        }
+
+        keys.len()
+    })
+}
+
+fn day_start(day: u64) -> CyclesUsageKey {
+    CyclesUsageKey {
+        day,
+        canister_id: CanisterId::from_slice(&[]),
+        method: String::new(),
+    }
+}
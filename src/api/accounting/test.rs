@@ -0,0 +1,71 @@
+#[cfg(test)]
+mod tests {
+    use candid::Principal;
+
+    use crate::{
+        api::{
+            cycles_spend_per_day, cycles_usage, init_cycles_ledger, is_cycles_ledger_enabled,
+            prune_cycles_ledger, record_cycles, CyclesUsage,
+        },
+        NanoTimeStamp,
+    };
+
+    fn canister(id: u8) -> Principal {
+        Principal::from_slice(&[id; 10])
+    }
+
+    #[test]
+    fn test_record_cycles() {
+        let today = NanoTimeStamp::now().get_days();
+
+        // Nothing is recorded until the ledger is initialized
+        record_cycles(canister(1), "transfer", 1_000, 0);
+
+        assert!(!is_cycles_ledger_enabled());
+        assert!(cycles_usage(today).is_empty());
+
+        init_cycles_ledger("cycles_ledger", 10).unwrap();
+
+        record_cycles(canister(1), "transfer", 1_000, 400);
+        record_cycles(canister(1), "transfer", 1_000, 0);
+        record_cycles(canister(1), "balance", 0, 0);
+        record_cycles(canister(2), "http_request", 5_000, 1_000);
+
+        let usage = cycles_usage(today);
+
+        assert_eq!(usage.len(), 2);
+        assert_eq!(usage[0].0.canister_id, canister(1));
+        assert_eq!(
+            usage[0].1,
+            CyclesUsage {
+                calls: 2,
+                attached: 2_000,
+                refunded: 400,
+            }
+        );
+        assert_eq!(usage[0].1.consumed(), 1_600);
+        assert_eq!(usage[1].0.method, "http_request");
+
+        let spend = cycles_spend_per_day(0, today);
+
+        assert_eq!(spend.len(), 1);
+        assert_eq!(spend[0].day, today);
+        assert_eq!(spend[0].usage.calls, 3);
+        assert_eq!(spend[0].consumed, 5_600);
+
+        assert!(cycles_spend_per_day(today + 1, today + 10).is_empty());
+    }
+
+    #[test]
+    fn test_prune_cycles_ledger() {
+        init_cycles_ledger("cycles_ledger", 10).unwrap();
+
+        let today = NanoTimeStamp::now().get_days();
+
+        record_cycles(canister(1), "transfer", 1_000, 0);
+
+        assert_eq!(prune_cycles_ledger(today), 0);
+        assert_eq!(prune_cycles_ledger(today + 1), 1);
+        assert!(cycles_spend_per_day(0, today).is_empty());
+    }
+}
@@ -1,5 +1,8 @@
 use ic_cdk::api::management_canister::ecdsa::EcdsaKeyId;
 
+use super::SchnorrKeyId;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum CallCycles {
     NoPay,
     Pay(u64),
@@ -17,6 +20,16 @@ impl From<&EcdsaKeyId> for CallCycles {
     }
 }
 
+impl From<&SchnorrKeyId> for CallCycles {
+    fn from(schnorr_key_id: &SchnorrKeyId) -> Self {
+        match schnorr_key_id.name.as_str() {
+            "key_1" => CallCycles::Pay(26_153_846_153),
+            "test_key_1" => CallCycles::Pay(10_000_000_000),
+            _ => CallCycles::NoPay,
+        }
+    }
+}
+
 impl Default for CallCycles {
     fn default() -> Self {
         CallCycles::NoPay
@@ -1,16 +1,190 @@
 use candid::CandidType;
+use ic_cdk::api::call::RejectionCode;
 use serde::Deserialize;
 
+use crate::{api::InterCallError, types::CanisterId};
+
+/// A call rejected by the system or by the callee.
+#[derive(CandidType, Clone, Deserialize, Debug, PartialEq)]
+pub struct CallRejection {
+    pub canister_id: CanisterId,
+    pub method: String,
+    pub code: RejectionCode,
+    pub message: String,
+}
+
+impl CallRejection {
+    pub fn new(
+        canister_id: CanisterId,
+        method: &str,
+        (code, message): (RejectionCode, String),
+    ) -> Self {
+        Self {
+            canister_id,
+            method: method.to_string(),
+            code,
+            message,
+        }
+    }
+
+    /// A one-way call the system refused to send.
+    pub fn notify(canister_id: CanisterId, method: &str, code: RejectionCode) -> Self {
+        Self::new(
+            canister_id,
+            method,
+            (code, "Unable to send the notification".to_string()),
+        )
+    }
+
+    /// A reply that didn't match the expected return types.
+    pub fn decode(canister_id: CanisterId, method: &str, error: candid::Error) -> Self {
+        Self::new(
+            canister_id,
+            method,
+            (
+                RejectionCode::CanisterError,
+                format!("failed to decode canister response: {}", error),
+            ),
+        )
+    }
+
+    /// The call may succeed if retried later, e.g. when a queue was full.
+    pub fn is_transient(&self) -> bool {
+        self.code == RejectionCode::SysTransient
+    }
+
+    /// The caller couldn't pay for the call, or the callee ran out of cycles.
+    pub fn is_out_of_cycles(&self) -> bool {
+        let message = self.message.to_lowercase();
+
+        self.code != RejectionCode::CanisterReject
+            && (message.contains("out of cycles")
+                || message.contains("insufficient cycles")
+                || message.contains("not enough cycles"))
+    }
+
+    /// The callee is stopped or stopping.
+    pub fn is_canister_stopped(&self) -> bool {
+        let message = self.message.to_lowercase();
+
+        self.code != RejectionCode::CanisterReject
+            && (message.contains("is stopped") || message.contains("is stopping"))
+    }
+}
+
+#[rustfmt::skip]
+impl std::fmt::Display for CallRejection {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{} on {} rejected with {:?}: {}", self.method, self.canister_id, self.code, self.message)
+    }
+}
+
 #[derive(CandidType, Clone, Deserialize, Debug, PartialEq)]
 pub enum ManagementError {
-    CallError(String, String),
+    SerializationError(String, String),
+    /// Rejected before calling, e.g. a message hash that is not 32 bytes.
+    InvalidArgument(String, String),
+    CallError(CallRejection),
+}
+
+impl ManagementError {
+    pub fn rejection(&self) -> Option<&CallRejection> {
+        match self {
+            ManagementError::CallError(rejection) => Some(rejection),
+            ManagementError::SerializationError(_, _) => None,
+            ManagementError::InvalidArgument(_, _) => None,
+        }
+    }
+
+    pub fn is_transient(&self) -> bool {
+        self.rejection().is_some_and(CallRejection::is_transient)
+    }
+
+    pub fn is_out_of_cycles(&self) -> bool {
+        self.rejection()
+            .is_some_and(CallRejection::is_out_of_cycles)
+    }
+
+    pub fn is_canister_stopped(&self) -> bool {
+        self.rejection()
+            .is_some_and(CallRejection::is_canister_stopped)
+    }
+}
+
+impl From<InterCallError> for ManagementError {
+    fn from(error: InterCallError) -> Self {
+        match error {
+            InterCallError::SerializationError(method, msg) => {
+                ManagementError::SerializationError(method, msg)
+            }
+            InterCallError::CallError(rejection) => ManagementError::CallError(rejection),
+        }
+    }
 }
 
 #[rustfmt::skip]
 impl std::fmt::Display for ManagementError {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         match self {
-            ManagementError::CallError(method, msg) => write!(f, "Error calling method {}: {}", method, msg),
+            ManagementError::SerializationError(method, msg) => write!(f, "Error serializing arguments for method {}: {}", method, msg),
+            ManagementError::InvalidArgument(method, msg) => write!(f, "Invalid argument for method {}: {}", method, msg),
+            ManagementError::CallError(rejection) => write!(f, "Error calling method {}", rejection),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use candid::Principal;
+
+    fn rejection(code: RejectionCode, message: &str) -> CallRejection {
+        CallRejection::new(
+            Principal::anonymous(),
+            "transfer",
+            (code, message.to_string()),
+        )
+    }
+
+    #[test]
+    fn test_call_rejection_classification() {
+        let transient = rejection(RejectionCode::SysTransient, "Couldn't send message");
+
+        assert!(transient.is_transient());
+        assert!(!transient.is_out_of_cycles());
+
+        let out_of_cycles = rejection(
+            RejectionCode::SysTransient,
+            "Canister aaaaa-aa is out of cycles",
+        );
+
+        assert!(out_of_cycles.is_out_of_cycles());
+        assert!(!out_of_cycles.is_canister_stopped());
+
+        let stopped = rejection(RejectionCode::CanisterError, "Canister aaaaa-aa is stopped");
+
+        assert!(stopped.is_canister_stopped());
+        assert!(!stopped.is_transient());
+
+        // Messages from the callee are not classified
+        let rejected = rejection(RejectionCode::CanisterReject, "Canister is stopped");
+
+        assert!(!rejected.is_canister_stopped());
+    }
+
+    #[test]
+    fn test_management_error() {
+        let error = ManagementError::CallError(rejection(RejectionCode::SysTransient, "Busy"));
+
+        assert!(error.is_transient());
+        assert_eq!(
+            error.to_string(),
+            "Error calling method transfer on 2vxsx-fae rejected with SysTransient: Busy"
+        );
+
+        let error = ManagementError::SerializationError("transfer".to_string(), "".to_string());
+
+        assert!(error.rejection().is_none());
+        assert!(!error.is_transient());
+    }
+}
@@ -1,15 +1,26 @@
+mod batch;
+mod client;
 mod error;
+mod retry;
 mod traits;
 
-use crate::{api::CallCycles, types::CanisterId};
-use candid::CandidType;
+use crate::{
+    api::{call_transport, error::CallRejection, record_cycles, CallCycles},
+    types::CanisterId,
+};
+pub use batch::*;
+use candid::{
+    utils::{ArgumentDecoder, ArgumentEncoder},
+    CandidType,
+};
 pub use error::*;
-use ic_cdk::api::call::{call, call_with_payment, call_with_payment128, CallResult};
+pub use retry::*;
 use serde::de::DeserializeOwned;
 
 pub struct InterCall(pub CanisterId);
 
 impl InterCall {
+    /// Calls `method` with a single argument and decodes a single return value.
     pub async fn call<A, R>(
         &self,
         method: &str,
@@ -20,17 +31,83 @@ impl InterCall {
         A: CandidType,
         R: CandidType + DeserializeOwned,
     {
-        let res: CallResult<(R,)> = match cycles {
-            CallCycles::Pay128(cycles) => {
-                call_with_payment128(self.0, method, (args,), cycles).await
-            }
-            CallCycles::Pay(cycles) => call_with_payment(self.0, method, (args,), cycles).await,
-            CallCycles::NoPay => call(self.0, method, (args,)).await,
-        };
-
-        match res {
-            Ok((res,)) => Ok(res),
-            Err(e) => Err(InterCallError::CallError(method.to_string(), e.1)),
+        let (res,) = self.call_with_args(method, (args,), cycles).await?;
+
+        Ok(res)
+    }
+
+    /// Calls `method` with a tuple of arguments and decodes a tuple of return values,
+    /// e.g. `(from, amount)` for a method taking `(principal, nat)`.
+    pub async fn call_with_args<A, R>(
+        &self,
+        method: &str,
+        args: A,
+        cycles: CallCycles,
+    ) -> Result<R, InterCallError>
+    where
+        A: ArgumentEncoder,
+        R: for<'a> ArgumentDecoder<'a>,
+    {
+        let args = candid::encode_args(args)
+            .map_err(|e| InterCallError::SerializationError(method.to_string(), e.to_string()))?;
+
+        let reply = self.call_raw(method, &args, cycles).await?;
+
+        candid::decode_args(&reply)
+            .map_err(|e| InterCallError::CallError(CallRejection::decode(self.0, method, e)))
+    }
+
+    /// Calls `method` with Candid encoded arguments and returns the encoded reply.
+    pub async fn call_raw(
+        &self,
+        method: &str,
+        args: &[u8],
+        cycles: CallCycles,
+    ) -> Result<Vec<u8>, InterCallError> {
+        let attached: u128 = cycles.into();
+
+        let transport = call_transport();
+
+        let res = transport
+            .call_raw(self.0, method, args.to_vec(), attached)
+            .await;
+
+        if attached > 0 {
+            record_cycles(self.0, method, attached, transport.cycles_refunded());
         }
+
+        res.map_err(|e| InterCallError::CallError(CallRejection::new(self.0, method, e)))
+    }
+
+    /// Calls `method` without waiting for, or being able to read, the reply.
+    pub fn notify<A>(&self, method: &str, args: A, cycles: CallCycles) -> Result<(), InterCallError>
+    where
+        A: ArgumentEncoder,
+    {
+        let args = candid::encode_args(args)
+            .map_err(|e| InterCallError::SerializationError(method.to_string(), e.to_string()))?;
+
+        self.notify_raw(method, &args, cycles)
+    }
+
+    /// Like [`notify`](Self::notify), with Candid encoded arguments.
+    pub fn notify_raw(
+        &self,
+        method: &str,
+        args: &[u8],
+        cycles: CallCycles,
+    ) -> Result<(), InterCallError> {
+        let attached: u128 = cycles.into();
+
+        call_transport()
+            .notify_raw(self.0, method, args, attached)
+            .map_err(|code| {
+                InterCallError::CallError(CallRejection::notify(self.0, method, code))
+            })?;
+
+        // Refunds of one-way calls are lost, all attached cycles count as consumed
+        record_cycles(self.0, method, attached, 0);
+
+        Ok(())
     }
 }
@@ -0,0 +1,221 @@
+use candid::CandidType;
+use serde::de::DeserializeOwned;
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use crate::{api::CallCycles, types::CanisterId};
+
+use super::{InterCall, InterCallError};
+
+/// Results of a batch, in the order the calls were given.
+#[derive(Debug)]
+pub struct BatchResult<T, E = InterCallError> {
+    pub results: Vec<Result<T, E>>,
+}
+
+impl<T, E> BatchResult<T, E> {
+    pub fn len(&self) -> usize {
+        self.results.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.results.is_empty()
+    }
+
+    pub fn is_all_ok(&self) -> bool {
+        self.results.iter().all(Result::is_ok)
+    }
+
+    /// Returns the successful results with the index of their call.
+    pub fn successes(&self) -> Vec<(usize, &T)> {
+        self.results
+            .iter()
+            .enumerate()
+            .filter_map(|(index, result)| result.as_ref().ok().map(|value| (index, value)))
+            .collect()
+    }
+
+    /// Returns the errors with the index of their call.
+    pub fn failures(&self) -> Vec<(usize, &E)> {
+        self.results
+            .iter()
+            .enumerate()
+            .filter_map(|(index, result)| result.as_ref().err().map(|error| (index, error)))
+            .collect()
+    }
+
+    /// Returns all the values, or the errors with the index of their call if any failed.
+    pub fn into_result(self) -> Result<Vec<T>, Vec<(usize, E)>> {
+        let mut values = Vec::with_capacity(self.results.len());
+        let mut errors = vec![];
+
+        for (index, result) in self.results.into_iter().enumerate() {
+            match result {
+                Ok(value) => values.push(value),
+                Err(error) => errors.push((index, error)),
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(values)
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+/// Runs calls concurrently, at most `max_concurrent` at a time.
+///
+/// A call only starts when polled, the next one starts as soon as one returns.
+///
+/// # Example
+/// ```
+/// use b3_utils::api::{BatchCall, CallCycles, InterCall};
+/// use candid::{CandidType, Nat, Principal};
+///
+/// #[derive(CandidType, Clone)]
+/// struct Account {
+///     owner: Principal,
+///     subaccount: Option<Vec<u8>>,
+/// }
+///
+/// async fn balances(ledgers: Vec<Principal>, owner: Principal) -> Vec<Nat> {
+///     let account = Account {
+///         owner,
+///         subaccount: None,
+///     };
+///
+///     let batch = BatchCall::new(5)
+///         .run(ledgers.into_iter().map(|ledger| {
+///             let account = account.clone();
+///
+///             async move {
+///                 InterCall(ledger)
+///                     .call::<_, Nat>("icrc1_balance_of", account, CallCycles::NoPay)
+///                     .await
+///             }
+///         }))
+///         .await;
+///
+///     for (index, error) in batch.failures() {
+///         ic_cdk::println!("Ledger {} failed: {}", index, error);
+///     }
+///
+///     batch.results.into_iter().filter_map(Result::ok).collect()
+/// }
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BatchCall {
+    max_concurrent: usize,
+}
+
+impl Default for BatchCall {
+    fn default() -> Self {
+        Self::new(10)
+    }
+}
+
+impl BatchCall {
+    pub fn new(max_concurrent: usize) -> Self {
+        Self {
+            max_concurrent: max_concurrent.max(1),
+        }
+    }
+
+    pub fn max_concurrent(&self) -> usize {
+        self.max_concurrent
+    }
+
+    pub async fn run<I, F, T, E>(&self, calls: I) -> BatchResult<T, E>
+    where
+        I: IntoIterator<Item = F>,
+        F: Future<Output = Result<T, E>>,
+    {
+        let queued: Vec<F> = calls.into_iter().collect();
+
+        let results = Batch {
+            results: queued.iter().map(|_| None).collect(),
+            queued: queued.into_iter().enumerate(),
+            running: vec![],
+            max_concurrent: self.max_concurrent,
+        }
+        .await;
+
+        BatchResult { results }
+    }
+
+    /// Calls the same method with the same argument on every canister.
+    pub async fn call_all<A, R>(
+        &self,
+        canister_ids: &[CanisterId],
+        method: &str,
+        args: A,
+        cycles: CallCycles,
+    ) -> BatchResult<R>
+    where
+        A: CandidType + Clone,
+        R: CandidType + DeserializeOwned,
+    {
+        self.run(canister_ids.iter().map(|canister_id| {
+            let args = args.clone();
+
+            async move { InterCall(*canister_id).call(method, args, cycles).await }
+        }))
+        .await
+    }
+}
+
+struct Batch<F: Future> {
+    queued: std::iter::Enumerate<std::vec::IntoIter<F>>,
+    running: Vec<(usize, Pin<Box<F>>)>,
+    results: Vec<Option<F::Output>>,
+    max_concurrent: usize,
+}
+
+impl<F: Future> Unpin for Batch<F> {}
+
+impl<F: Future> Future for Batch<F> {
+    type Output = Vec<F::Output>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let batch = &mut *self;
+
+        loop {
+            while batch.running.len() < batch.max_concurrent {
+                match batch.queued.next() {
+                    Some((index, call)) => batch.running.push((index, Box::pin(call))),
+                    None => break,
+                }
+            }
+
+            let mut done = false;
+
+            batch
+                .running
+                .retain_mut(|(index, call)| match call.as_mut().poll(cx) {
+                    Poll::Ready(output) => {
+                        batch.results[*index] = Some(output);
+                        done = true;
+                        false
+                    }
+                    Poll::Pending => true,
+                });
+
+            if batch.running.is_empty() && batch.queued.len() == 0 {
+                let results = batch.results.drain(..).map(Option::unwrap).collect();
+
+                return Poll::Ready(results);
+            }
+
+            // Start the next calls in place of the ones that returned
+            if !done {
+                return Poll::Pending;
+            }
+        }
+    }
+}
+
+mod test;
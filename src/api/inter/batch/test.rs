@@ -0,0 +1,110 @@
+#[cfg(test)]
+mod tests {
+    use candid::Principal;
+    use ic_cdk::api::call::RejectionCode;
+    use std::{
+        cell::Cell,
+        future::Future,
+        pin::Pin,
+        rc::Rc,
+        task::{Context, Poll},
+    };
+
+    use crate::{
+        api::{BatchCall, BatchResult, CallCycles, MockTransport},
+        mocks::block_on_mock,
+    };
+
+    fn canister(id: u8) -> Principal {
+        Principal::from_slice(&[id; 10])
+    }
+
+    /// Returns `value` after being polled twice.
+    struct Pending {
+        value: Result<usize, String>,
+        polled: bool,
+        running: Rc<Cell<usize>>,
+        max_running: Rc<Cell<usize>>,
+    }
+
+    impl Future for Pending {
+        type Output = Result<usize, String>;
+
+        fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+            if self.polled {
+                self.running.set(self.running.get() - 1);
+
+                return Poll::Ready(self.value.clone());
+            }
+
+            self.polled = true;
+            self.running.set(self.running.get() + 1);
+            self.max_running
+                .set(self.max_running.get().max(self.running.get()));
+
+            cx.waker().wake_by_ref();
+
+            Poll::Pending
+        }
+    }
+
+    #[test]
+    fn test_batch_concurrency_and_order() {
+        let running = Rc::new(Cell::new(0));
+        let max_running = Rc::new(Cell::new(0));
+
+        let calls = (0..5).map(|i| Pending {
+            value: if i == 3 {
+                Err("failed".to_string())
+            } else {
+                Ok(i)
+            },
+            polled: false,
+            running: running.clone(),
+            max_running: max_running.clone(),
+        });
+
+        let batch = block_on_mock(BatchCall::new(2).run(calls));
+
+        assert_eq!(max_running.get(), 2);
+        assert_eq!(batch.len(), 5);
+        assert!(!batch.is_all_ok());
+        assert_eq!(batch.successes().len(), 4);
+        assert_eq!(batch.failures(), vec![(3, &"failed".to_string())]);
+        assert_eq!(batch.into_result(), Err(vec![(3, "failed".to_string())]));
+
+        let empty = block_on_mock(BatchCall::default().run(Vec::<Pending>::new()));
+
+        assert!(empty.is_empty());
+        assert_eq!(empty.into_result(), Ok(vec![]));
+    }
+
+    #[test]
+    fn test_batch_call_all() {
+        let mock = MockTransport::default()
+            .reply(canister(1), "version", "1.0.0".to_string())
+            .reject(
+                canister(2),
+                "version",
+                RejectionCode::CanisterError,
+                "Canister is stopped",
+            )
+            .reply(canister(3), "version", "1.1.0".to_string());
+
+        mock.install();
+
+        let batch: BatchResult<String> = block_on_mock(BatchCall::new(2).call_all(
+            &[canister(1), canister(2), canister(3)],
+            "version",
+            (),
+            CallCycles::NoPay,
+        ));
+
+        assert_eq!(mock.calls().len(), 3);
+        assert_eq!(
+            batch.successes(),
+            vec![(0, &"1.0.0".to_string()), (2, &"1.1.0".to_string())]
+        );
+        assert!(batch.failures()[0].1.is_canister_stopped());
+    }
+}
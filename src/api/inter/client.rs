@@ -0,0 +1,140 @@
+/// Declares a typed client for a remote canister, calling it through [`InterCall`](crate::api::InterCall).
+///
+/// Each `fn` becomes an async method returning `Result<R, InterCallError>`, calling the
+/// Candid method of the same name, or the one given after `=`.
+/// The client also gets `canister_id` and `From` conversions from a `Principal` or its text.
+///
+/// # Example
+/// ```
+/// use b3_utils::inter_call_client;
+/// use candid::{CandidType, Nat, Principal};
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(CandidType, Deserialize, Serialize)]
+/// pub struct Account {
+///     pub owner: Principal,
+///     pub subaccount: Option<Vec<u8>>,
+/// }
+///
+/// inter_call_client! {
+///     /// A token ledger.
+///     pub struct Ledger {
+///         fn name() -> String = "icrc1_name";
+///         fn balance_of(account: Account) -> Nat = "icrc1_balance_of";
+///         fn allowance(owner: Account, spender: Account) -> Nat;
+///         /// Calls `get_version`.
+///         fn get_version() -> String;
+///     }
+/// }
+///
+/// let ledger = Ledger::from("ryjl3-tyaaa-aaaaa-aaaba-cai");
+///
+/// assert_eq!(ledger.canister_id().to_text(), "ryjl3-tyaaa-aaaaa-aaaba-cai");
+///
+/// // In an async canister method:
+/// // let balance: Nat = ledger.balance_of(account).await?;
+/// ```
+#[macro_export]
+macro_rules! inter_call_client {
+    (@method $fn:ident) => {
+        stringify!($fn)
+    };
+    (@method $fn:ident $method:literal) => {
+        $method
+    };
+    (
+        $(#[$meta:meta])*
+        $vis:vis struct $name:ident {
+            $(
+                $(#[$fn_meta:meta])*
+                fn $fn:ident($($arg:ident: $arg_ty:ty),* $(,)?) -> $ret:ty $(= $method:literal)?;
+            )*
+        }
+    ) => {
+        $(#[$meta])*
+        #[derive(Clone, PartialEq, Debug)]
+        $vis struct $name(pub $crate::types::CanisterId);
+
+        // Implemented by hand rather than derived, so the caller doesn't need candid and
+        // serde as dependencies. Encoded as the canister id, like a derived newtype.
+        impl $crate::candid::CandidType for $name {
+            fn _ty() -> $crate::candid::types::Type {
+                <$crate::types::CanisterId as $crate::candid::CandidType>::_ty()
+            }
+
+            fn idl_serialize<S>(&self, serializer: S) -> Result<(), S::Error>
+            where
+                S: $crate::candid::types::Serializer,
+            {
+                $crate::candid::CandidType::idl_serialize(&self.0, serializer)
+            }
+        }
+
+        impl $crate::serde::Serialize for $name {
+            fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+            where
+                S: $crate::serde::Serializer,
+            {
+                serializer.serialize_newtype_struct(stringify!($name), &self.0)
+            }
+        }
+
+        impl<'de> $crate::serde::Deserialize<'de> for $name {
+            fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+            where
+                D: $crate::serde::Deserializer<'de>,
+            {
+                <$crate::types::CanisterId as $crate::serde::Deserialize>::deserialize(deserializer)
+                    .map(Self)
+            }
+        }
+
+        impl $name {
+            pub fn canister_id(&self) -> $crate::types::CanisterId {
+                self.0
+            }
+
+            $(
+                $(#[$fn_meta])*
+                pub async fn $fn(
+                    &self,
+                    $($arg: $arg_ty),*
+                ) -> Result<$ret, $crate::api::InterCallError> {
+                    let (res,) = $crate::api::InterCall(self.0)
+                        .call_with_args(
+                            $crate::inter_call_client!(@method $fn $($method)?),
+                            ($($arg,)*),
+                            $crate::api::CallCycles::NoPay,
+                        )
+                        .await?;
+
+                    Ok(res)
+                }
+            )*
+        }
+
+        impl From<$crate::candid::Principal> for $name {
+            fn from(principal: $crate::candid::Principal) -> Self {
+                Self(principal)
+            }
+        }
+
+        impl From<&$crate::candid::Principal> for $name {
+            fn from(principal: &$crate::candid::Principal) -> Self {
+                Self(*principal)
+            }
+        }
+
+        impl From<&str> for $name {
+            fn from(principal: &str) -> Self {
+                let principal = $crate::candid::Principal::from_text(principal)
+                    .map_err(|_| concat!(stringify!($name), ": Invalid principal").to_string())
+                    .unwrap();
+
+                Self(principal)
+            }
+        }
+    };
+}
+
+mod test;
@@ -0,0 +1,41 @@
+#[cfg(test)]
+mod tests {
+    use crate::{api::MockTransport, mocks::block_on_mock};
+    use candid::{Nat, Principal};
+
+    inter_call_client! {
+        struct Counter {
+            fn add(amount: Nat, times: u8) -> Nat = "counter_add";
+        }
+    }
+
+    #[test]
+    fn test_client_call_and_encoding() {
+        let canister_id = Principal::from_slice(&[1; 10]);
+        let counter = Counter::from(canister_id);
+
+        assert_eq!(counter.canister_id(), canister_id);
+
+        let mock = MockTransport::default().reply(canister_id, "counter_add", Nat::from(6u8));
+
+        mock.install();
+
+        let total = block_on_mock(counter.add(Nat::from(2u8), 3)).unwrap();
+
+        assert_eq!(total, Nat::from(6u8));
+        assert_eq!(
+            mock.calls()[0].decode_args::<(Nat, u8)>().unwrap(),
+            (Nat::from(2u8), 3)
+        );
+
+        // Encoded as the canister id
+        assert_eq!(
+            candid::encode_one(&counter).unwrap(),
+            candid::encode_one(canister_id).unwrap()
+        );
+        assert_eq!(
+            candid::decode_one::<Counter>(&candid::encode_one(canister_id).unwrap()).unwrap(),
+            counter
+        );
+    }
+}
@@ -1,7 +1,32 @@
-#[derive(Debug)]
+use crate::api::error::CallRejection;
+
+#[derive(Debug, Clone, PartialEq)]
 pub enum InterCallError {
     SerializationError(String, String),
-    CallError(String, String),
+    CallError(CallRejection),
+}
+
+impl InterCallError {
+    pub fn rejection(&self) -> Option<&CallRejection> {
+        match self {
+            InterCallError::CallError(rejection) => Some(rejection),
+            InterCallError::SerializationError(_, _) => None,
+        }
+    }
+
+    pub fn is_transient(&self) -> bool {
+        self.rejection().is_some_and(CallRejection::is_transient)
+    }
+
+    pub fn is_out_of_cycles(&self) -> bool {
+        self.rejection()
+            .is_some_and(CallRejection::is_out_of_cycles)
+    }
+
+    pub fn is_canister_stopped(&self) -> bool {
+        self.rejection()
+            .is_some_and(CallRejection::is_canister_stopped)
+    }
 }
 
 #[rustfmt::skip]
@@ -10,8 +35,8 @@ impl std::fmt::Display for InterCallError {
         match self {
             InterCallError::SerializationError(method, msg) => 
                 write!(f, "Error serializing arguments for method {}: {}", method, msg),
-            InterCallError::CallError(method, msg) => 
-                write!(f, "Error calling method {} : {}", method, msg),
+            InterCallError::CallError(rejection) => 
+                write!(f, "Error calling method {}", rejection),
         }
     }
 }
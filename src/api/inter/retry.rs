@@ -0,0 +1,262 @@
+use candid::{
+    utils::{ArgumentDecoder, ArgumentEncoder},
+    CandidType,
+};
+use ic_cdk::api::call::RejectionCode;
+use serde::de::DeserializeOwned;
+use std::time::Duration;
+
+use crate::{
+    api::{error::CallRejection, CallCycles, Management},
+    types::CanisterId,
+    NanoTimeStamp,
+};
+
+use super::{InterCall, InterCallError};
+
+/// How long to wait before retrying a call.
+///
+/// The wait is spent in `raw_rand` calls, which answer in a later round, so the
+/// caller keeps its call context and an update method can still reply after a retry.
+/// Each round adds a second or so, the actual wait is rounded up to it.
+///
+/// # Example
+/// ```
+/// use b3_utils::api::{Backoff, CallRetryPolicy};
+/// use std::time::Duration;
+///
+/// let policy = CallRetryPolicy::new(3).backoff(Backoff::Exponential {
+///     initial: Duration::from_secs(1),
+///     max: Duration::from_secs(10),
+/// });
+///
+/// assert_eq!(policy.backoff.delay(3), Duration::from_secs(4));
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Backoff {
+    None,
+    Fixed(Duration),
+    /// Doubles the wait after each retry, up to `max`.
+    Exponential {
+        initial: Duration,
+        max: Duration,
+    },
+}
+
+impl Backoff {
+    /// Returns the wait before the `retry`-th retry, starting at 1.
+    pub fn delay(&self, retry: u32) -> Duration {
+        match self {
+            Backoff::None => Duration::ZERO,
+            Backoff::Fixed(delay) => *delay,
+            Backoff::Exponential { initial, max } => {
+                let factor = 2u32.saturating_pow(retry.saturating_sub(1));
+
+                initial.saturating_mul(factor).min(*max)
+            }
+        }
+    }
+}
+
+/// Which rejected calls to retry, and how often.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CallRetryPolicy {
+    /// Attempts including the first one.
+    pub max_attempts: u32,
+    pub backoff: Backoff,
+    pub retryable: Vec<RejectionCode>,
+}
+
+impl Default for CallRetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 1,
+            backoff: Backoff::None,
+            retryable: vec![RejectionCode::SysTransient],
+        }
+    }
+}
+
+impl CallRetryPolicy {
+    /// Retries `SysTransient` rejections until `max_attempts` calls were made.
+    pub fn new(max_attempts: u32) -> Self {
+        Self {
+            max_attempts: max_attempts.max(1),
+            ..Default::default()
+        }
+    }
+
+    pub fn backoff(mut self, backoff: Backoff) -> Self {
+        self.backoff = backoff;
+        self
+    }
+
+    pub fn retry_on(mut self, code: RejectionCode) -> Self {
+        if !self.retryable.contains(&code) {
+            self.retryable.push(code);
+        }
+        self
+    }
+
+    pub fn should_retry(&self, error: &InterCallError) -> bool {
+        error
+            .rejection()
+            .is_some_and(|rejection| self.retryable.contains(&rejection.code))
+    }
+}
+
+/// An [`InterCall`] with a retry policy and an overall timeout.
+///
+/// The timeout only bounds the time spent retrying: no retry is started after it.
+/// Bounded-wait calls are not supported, a single attempt still waits for its reply
+/// however long that takes.
+///
+/// # Example
+/// ```
+/// use b3_utils::api::{CallRetryPolicy, InterCall};
+/// use candid::Principal;
+/// use std::time::Duration;
+///
+/// let ledger = InterCall(Principal::anonymous())
+///     .builder()
+///     .retry(CallRetryPolicy::new(3))
+///     .timeout(Duration::from_secs(60));
+///
+/// // In an async canister method:
+/// // let fee: Nat = ledger.call("icrc1_fee", (), CallCycles::NoPay).await?;
+/// ```
+#[derive(Clone, Debug)]
+pub struct InterCallBuilder {
+    canister_id: CanisterId,
+    retry: CallRetryPolicy,
+    timeout: Option<Duration>,
+}
+
+impl InterCallBuilder {
+    pub fn new(canister_id: CanisterId) -> Self {
+        Self {
+            canister_id,
+            retry: CallRetryPolicy::default(),
+            timeout: None,
+        }
+    }
+
+    pub fn retry(mut self, policy: CallRetryPolicy) -> Self {
+        self.retry = policy;
+        self
+    }
+
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    pub fn canister_id(&self) -> CanisterId {
+        self.canister_id
+    }
+
+    pub fn retry_policy(&self) -> &CallRetryPolicy {
+        &self.retry
+    }
+
+    /// Like [`InterCall::call`], retrying according to the policy.
+    pub async fn call<A, R>(
+        &self,
+        method: &str,
+        args: A,
+        cycles: CallCycles,
+    ) -> Result<R, InterCallError>
+    where
+        A: CandidType,
+        R: CandidType + DeserializeOwned,
+    {
+        let (res,) = self.call_with_args(method, (args,), cycles).await?;
+
+        Ok(res)
+    }
+
+    /// Like [`InterCall::call_with_args`], retrying according to the policy.
+    pub async fn call_with_args<A, R>(
+        &self,
+        method: &str,
+        args: A,
+        cycles: CallCycles,
+    ) -> Result<R, InterCallError>
+    where
+        A: ArgumentEncoder,
+        R: for<'a> ArgumentDecoder<'a>,
+    {
+        let args = candid::encode_args(args)
+            .map_err(|e| InterCallError::SerializationError(method.to_string(), e.to_string()))?;
+
+        let reply = self.call_raw(method, &args, cycles).await?;
+
+        candid::decode_args(&reply).map_err(|e| {
+            InterCallError::CallError(CallRejection::decode(self.canister_id, method, e))
+        })
+    }
+
+    /// Like [`InterCall::call_raw`], retrying according to the policy.
+    /// The cycles are attached to every attempt.
+    pub async fn call_raw(
+        &self,
+        method: &str,
+        args: &[u8],
+        cycles: CallCycles,
+    ) -> Result<Vec<u8>, InterCallError> {
+        let cycles: u128 = cycles.into();
+
+        let deadline = self.timeout.map(|timeout| {
+            NanoTimeStamp::now()
+                .0
+                .saturating_add(timeout.as_nanos() as u64)
+        });
+
+        let mut attempt = 1;
+
+        loop {
+            let result = InterCall(self.canister_id)
+                .call_raw(method, args, CallCycles::Pay128(cycles))
+                .await;
+
+            match result {
+                Err(error)
+                    if attempt < self.retry.max_attempts && self.retry.should_retry(&error) =>
+                {
+                    let delay = self.retry.backoff.delay(attempt);
+
+                    let retry_at = NanoTimeStamp::now()
+                        .0
+                        .saturating_add(delay.as_nanos() as u64);
+
+                    if deadline.is_some_and(|deadline| retry_at >= deadline) {
+                        return Err(error);
+                    }
+
+                    wait_until(retry_at).await;
+
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+impl InterCall {
+    pub fn builder(&self) -> InterCallBuilder {
+        InterCallBuilder::new(self.0)
+    }
+}
+
+/// Waits until `until` without leaving the call context, each `raw_rand` call
+/// is answered in a later round. Stops early if `raw_rand` is rejected.
+async fn wait_until(until: u64) {
+    while NanoTimeStamp::now().0 < until {
+        if Management::raw_rand().await.is_err() {
+            break;
+        }
+    }
+}
+
+mod test;
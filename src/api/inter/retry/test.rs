@@ -0,0 +1,57 @@
+#[cfg(test)]
+mod tests {
+    use candid::Principal;
+    use ic_cdk::api::call::RejectionCode;
+    use std::time::Duration;
+
+    use crate::api::{error::CallRejection, Backoff, CallRetryPolicy, InterCallError};
+
+    fn rejected(code: RejectionCode) -> InterCallError {
+        InterCallError::CallError(CallRejection::new(
+            Principal::anonymous(),
+            "icrc1_transfer",
+            (code, "rejected".to_string()),
+        ))
+    }
+
+    #[test]
+    fn test_backoff_delay() {
+        assert_eq!(Backoff::None.delay(3), Duration::ZERO);
+        assert_eq!(
+            Backoff::Fixed(Duration::from_secs(2)).delay(3),
+            Duration::from_secs(2)
+        );
+
+        let exponential = Backoff::Exponential {
+            initial: Duration::from_secs(1),
+            max: Duration::from_secs(5),
+        };
+
+        assert_eq!(exponential.delay(1), Duration::from_secs(1));
+        assert_eq!(exponential.delay(2), Duration::from_secs(2));
+        assert_eq!(exponential.delay(3), Duration::from_secs(4));
+        assert_eq!(exponential.delay(4), Duration::from_secs(5));
+        assert_eq!(exponential.delay(100), Duration::from_secs(5));
+    }
+
+    #[test]
+    fn test_retry_policy() {
+        assert_eq!(CallRetryPolicy::new(0).max_attempts, 1);
+
+        let policy = CallRetryPolicy::new(3);
+
+        assert!(policy.should_retry(&rejected(RejectionCode::SysTransient)));
+        assert!(!policy.should_retry(&rejected(RejectionCode::CanisterError)));
+        assert!(!policy.should_retry(&InterCallError::SerializationError(
+            "icrc1_transfer".to_string(),
+            "".to_string()
+        )));
+
+        let policy = policy
+            .retry_on(RejectionCode::CanisterError)
+            .retry_on(RejectionCode::CanisterError);
+
+        assert_eq!(policy.retryable.len(), 2);
+        assert!(policy.should_retry(&rejected(RejectionCode::CanisterError)));
+    }
+}
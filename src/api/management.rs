@@ -0,0 +1,234 @@
+use ic_cdk::api::management_canister::{
+    bitcoin::{
+        BitcoinNetwork, GetBalanceRequest, GetCurrentFeePercentilesRequest, GetUtxosRequest,
+        GetUtxosResponse, MillisatoshiPerByte, Satoshi, SendTransactionRequest,
+    },
+    ecdsa::{
+        EcdsaPublicKeyArgument, EcdsaPublicKeyResponse, SignWithEcdsaArgument,
+        SignWithEcdsaResponse,
+    },
+    main::{
+        CanisterSettings, ChunkHash, ClearChunkStoreArgument, DeleteCanisterSnapshotArgs,
+        InstallChunkedCodeArgument, LoadCanisterSnapshotArgs, LogVisibility, Snapshot, SnapshotId,
+        StoredChunksArgument, TakeCanisterSnapshotArgs, UpdateSettingsArgument,
+        UploadChunkArgument,
+    },
+    provisional::CanisterIdRecord,
+};
+
+use crate::types::CanisterId;
+
+use super::{error::ManagementError, CallCycles, Management};
+
+mod test;
+
+mod types;
+pub use types::*;
+
+/// Largest chunk accepted by `upload_chunk`.
+pub const MAX_CHUNK_SIZE: usize = 1024 * 1024;
+
+// Bitcoin fees as (mainnet, testnet), regtest is free.
+const BITCOIN_GET_BALANCE_FEE: (u128, u128) = (100_000_000, 40_000_000);
+const BITCOIN_GET_UTXOS_FEE: (u128, u128) = (10_000_000_000, 4_000_000_000);
+const BITCOIN_GET_CURRENT_FEE_PERCENTILES_FEE: (u128, u128) = (100_000_000, 40_000_000);
+const BITCOIN_SEND_TRANSACTION_FEE: (u128, u128) = (5_000_000_000, 2_000_000_000);
+const BITCOIN_SEND_TRANSACTION_BYTE_FEE: (u128, u128) = (20_000_000, 8_000_000);
+
+fn bitcoin_fee(network: BitcoinNetwork, (mainnet, testnet): (u128, u128)) -> u128 {
+    match network {
+        BitcoinNetwork::Mainnet => mainnet,
+        BitcoinNetwork::Testnet => testnet,
+        BitcoinNetwork::Regtest => 0,
+    }
+}
+
+impl Management {
+    pub async fn ecdsa_public_key(
+        arg: EcdsaPublicKeyArgument,
+    ) -> Result<EcdsaPublicKeyResponse, ManagementError> {
+        Management::call("ecdsa_public_key", arg, CallCycles::NoPay).await
+    }
+
+    /// Signs a 32 bytes message hash, paying the fee of the key.
+    pub async fn sign_with_ecdsa(
+        arg: SignWithEcdsaArgument,
+    ) -> Result<SignWithEcdsaResponse, ManagementError> {
+        if arg.message_hash.len() != 32 {
+            return Err(ManagementError::InvalidArgument(
+                "sign_with_ecdsa".to_string(),
+                format!(
+                    "message hash is {} bytes, expected 32",
+                    arg.message_hash.len()
+                ),
+            ));
+        }
+
+        let cycles = CallCycles::from(&arg.key_id);
+
+        Management::call("sign_with_ecdsa", arg, cycles).await
+    }
+
+    pub async fn schnorr_public_key(
+        arg: SchnorrPublicKeyArgument,
+    ) -> Result<SchnorrPublicKeyResponse, ManagementError> {
+        Management::call("schnorr_public_key", arg, CallCycles::NoPay).await
+    }
+
+    /// Signs a message, paying the fee of the key.
+    pub async fn sign_with_schnorr(
+        arg: SignWithSchnorrArgument,
+    ) -> Result<SignWithSchnorrResponse, ManagementError> {
+        let cycles = CallCycles::from(&arg.key_id);
+
+        Management::call("sign_with_schnorr", arg, cycles).await
+    }
+
+    /// Uploads a chunk of at most [`MAX_CHUNK_SIZE`] bytes to the chunk store of `canister_id`.
+    pub async fn upload_chunk(
+        canister_id: CanisterId,
+        chunk: Vec<u8>,
+    ) -> Result<ChunkHash, ManagementError> {
+        if chunk.len() > MAX_CHUNK_SIZE {
+            return Err(ManagementError::InvalidArgument(
+                "upload_chunk".to_string(),
+                format!("chunk is {} bytes, at most {}", chunk.len(), MAX_CHUNK_SIZE),
+            ));
+        }
+
+        let arg = UploadChunkArgument { canister_id, chunk };
+
+        Management::call("upload_chunk", arg, CallCycles::NoPay).await
+    }
+
+    pub async fn clear_chunk_store(canister_id: CanisterId) -> Result<(), ManagementError> {
+        let arg = ClearChunkStoreArgument { canister_id };
+
+        Management::call("clear_chunk_store", arg, CallCycles::NoPay).await
+    }
+
+    pub async fn stored_chunks(canister_id: CanisterId) -> Result<Vec<ChunkHash>, ManagementError> {
+        let arg = StoredChunksArgument { canister_id };
+
+        Management::call("stored_chunks", arg, CallCycles::NoPay).await
+    }
+
+    pub async fn install_chunked_code(
+        arg: InstallChunkedCodeArgument,
+    ) -> Result<(), ManagementError> {
+        if arg.chunk_hashes_list.is_empty() {
+            return Err(ManagementError::InvalidArgument(
+                "install_chunked_code".to_string(),
+                "no chunks to install".to_string(),
+            ));
+        }
+
+        Management::call("install_chunked_code", arg, CallCycles::NoPay).await
+    }
+
+    /// Takes a snapshot of a stopped canister, replacing `replace_snapshot` if given.
+    pub async fn take_canister_snapshot(
+        canister_id: CanisterId,
+        replace_snapshot: Option<SnapshotId>,
+    ) -> Result<Snapshot, ManagementError> {
+        let arg = TakeCanisterSnapshotArgs {
+            canister_id,
+            replace_snapshot,
+        };
+
+        Management::call("take_canister_snapshot", arg, CallCycles::NoPay).await
+    }
+
+    pub async fn load_canister_snapshot(
+        canister_id: CanisterId,
+        snapshot_id: SnapshotId,
+    ) -> Result<(), ManagementError> {
+        let arg = LoadCanisterSnapshotArgs {
+            canister_id,
+            snapshot_id,
+            sender_canister_version: None,
+        };
+
+        Management::call("load_canister_snapshot", arg, CallCycles::NoPay).await
+    }
+
+    pub async fn list_canister_snapshots(
+        canister_id: CanisterId,
+    ) -> Result<Vec<Snapshot>, ManagementError> {
+        let arg = CanisterIdRecord { canister_id };
+
+        Management::call("list_canister_snapshots", arg, CallCycles::NoPay).await
+    }
+
+    pub async fn delete_canister_snapshot(
+        canister_id: CanisterId,
+        snapshot_id: SnapshotId,
+    ) -> Result<(), ManagementError> {
+        let arg = DeleteCanisterSnapshotArgs {
+            canister_id,
+            snapshot_id,
+        };
+
+        Management::call("delete_canister_snapshot", arg, CallCycles::NoPay).await
+    }
+
+    /// Sets who can fetch the logs of `canister_id`, leaving the other settings unchanged.
+    pub async fn set_log_visibility(
+        canister_id: CanisterId,
+        log_visibility: LogVisibility,
+    ) -> Result<(), ManagementError> {
+        Management::update_settings(UpdateSettingsArgument {
+            canister_id,
+            settings: CanisterSettings {
+                log_visibility: Some(log_visibility),
+                ..Default::default()
+            },
+        })
+        .await
+    }
+
+    pub async fn bitcoin_get_balance(arg: GetBalanceRequest) -> Result<Satoshi, ManagementError> {
+        let cycles = bitcoin_fee(arg.network, BITCOIN_GET_BALANCE_FEE);
+
+        Management::call("bitcoin_get_balance", arg, CallCycles::Pay128(cycles)).await
+    }
+
+    pub async fn bitcoin_get_utxos(
+        arg: GetUtxosRequest,
+    ) -> Result<GetUtxosResponse, ManagementError> {
+        let cycles = bitcoin_fee(arg.network, BITCOIN_GET_UTXOS_FEE);
+
+        Management::call("bitcoin_get_utxos", arg, CallCycles::Pay128(cycles)).await
+    }
+
+    pub async fn bitcoin_get_current_fee_percentiles(
+        arg: GetCurrentFeePercentilesRequest,
+    ) -> Result<Vec<MillisatoshiPerByte>, ManagementError> {
+        let cycles = bitcoin_fee(arg.network, BITCOIN_GET_CURRENT_FEE_PERCENTILES_FEE);
+
+        Management::call(
+            "bitcoin_get_current_fee_percentiles",
+            arg,
+            CallCycles::Pay128(cycles),
+        )
+        .await
+    }
+
+    /// Sends a transaction, paying a fee per byte on top of the submission fee.
+    pub async fn bitcoin_send_transaction(
+        arg: SendTransactionRequest,
+    ) -> Result<(), ManagementError> {
+        if arg.transaction.is_empty() {
+            return Err(ManagementError::InvalidArgument(
+                "bitcoin_send_transaction".to_string(),
+                "empty transaction".to_string(),
+            ));
+        }
+
+        let cycles = bitcoin_fee(arg.network, BITCOIN_SEND_TRANSACTION_FEE)
+            + bitcoin_fee(arg.network, BITCOIN_SEND_TRANSACTION_BYTE_FEE)
+                * arg.transaction.len() as u128;
+
+        Management::call("bitcoin_send_transaction", arg, CallCycles::Pay128(cycles)).await
+    }
+}
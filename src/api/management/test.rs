@@ -0,0 +1,236 @@
+#[cfg(test)]
+mod tests {
+    use candid::Principal;
+    use ic_cdk::api::management_canister::{
+        bitcoin::{BitcoinNetwork, GetBalanceRequest, SendTransactionRequest},
+        ecdsa::{EcdsaCurve, EcdsaKeyId, SignWithEcdsaArgument, SignWithEcdsaResponse},
+        main::{ChunkHash, LogVisibility, Snapshot},
+    };
+
+    use crate::{
+        api::{
+            error::ManagementError, Management, MockTransport, SchnorrAlgorithm, SchnorrKeyId,
+            SignWithSchnorrArgument, SignWithSchnorrResponse, MAX_CHUNK_SIZE,
+        },
+        constants::MANAGMENT_CANISTER_ID,
+        mocks::block_on_mock,
+    };
+
+    fn canister(id: u8) -> Principal {
+        Principal::from_slice(&[id; 10])
+    }
+
+    #[test]
+    fn test_sign_with_ecdsa() {
+        let mock = MockTransport::default().reply(
+            MANAGMENT_CANISTER_ID,
+            "sign_with_ecdsa",
+            SignWithEcdsaResponse {
+                signature: vec![1; 64],
+            },
+        );
+
+        mock.install();
+
+        let key_id = EcdsaKeyId {
+            curve: EcdsaCurve::Secp256k1,
+            name: "key_1".to_string(),
+        };
+
+        let error = block_on_mock(Management::sign_with_ecdsa(SignWithEcdsaArgument {
+            message_hash: vec![0; 31],
+            derivation_path: vec![],
+            key_id: key_id.clone(),
+        }))
+        .unwrap_err();
+
+        assert!(matches!(error, ManagementError::InvalidArgument(_, _)));
+        assert!(mock.calls().is_empty());
+
+        let response = block_on_mock(Management::sign_with_ecdsa(SignWithEcdsaArgument {
+            message_hash: vec![0; 32],
+            derivation_path: vec![],
+            key_id,
+        }))
+        .unwrap();
+
+        assert_eq!(response.signature, vec![1; 64]);
+        assert_eq!(mock.calls()[0].cycles, 26_153_846_153);
+    }
+
+    #[test]
+    fn test_sign_with_schnorr() {
+        let mock = MockTransport::default().reply(
+            MANAGMENT_CANISTER_ID,
+            "sign_with_schnorr",
+            SignWithSchnorrResponse {
+                signature: vec![2; 64],
+            },
+        );
+
+        mock.install();
+
+        let arg = SignWithSchnorrArgument {
+            message: b"hello".to_vec(),
+            derivation_path: vec![],
+            key_id: SchnorrKeyId {
+                algorithm: SchnorrAlgorithm::Bip340Secp256k1,
+                name: "test_key_1".to_string(),
+            },
+        };
+
+        let response = block_on_mock(Management::sign_with_schnorr(arg.clone())).unwrap();
+
+        assert_eq!(response.signature, vec![2; 64]);
+
+        let calls = mock.calls_to(MANAGMENT_CANISTER_ID, "sign_with_schnorr");
+
+        assert_eq!(calls[0].cycles, 10_000_000_000);
+        assert_eq!(
+            calls[0]
+                .decode_args::<(SignWithSchnorrArgument,)>()
+                .unwrap(),
+            (arg,)
+        );
+    }
+
+    #[test]
+    fn test_schnorr_algorithm_candid() {
+        let encoded = candid::encode_one(SchnorrAlgorithm::Ed25519).unwrap();
+
+        #[derive(candid::CandidType)]
+        #[allow(non_camel_case_types, dead_code)]
+        enum Expected {
+            bip340secp256k1,
+            ed25519,
+        }
+
+        assert_eq!(encoded, candid::encode_one(Expected::ed25519).unwrap());
+    }
+
+    #[test]
+    fn test_chunked_code() {
+        let mock = MockTransport::default()
+            .reply(
+                MANAGMENT_CANISTER_ID,
+                "upload_chunk",
+                ChunkHash { hash: vec![3; 32] },
+            )
+            .reply(
+                MANAGMENT_CANISTER_ID,
+                "stored_chunks",
+                vec![ChunkHash { hash: vec![3; 32] }],
+            );
+
+        mock.install();
+
+        let error = block_on_mock(Management::upload_chunk(
+            canister(1),
+            vec![0; MAX_CHUNK_SIZE + 1],
+        ))
+        .unwrap_err();
+
+        assert!(matches!(error, ManagementError::InvalidArgument(_, _)));
+        assert!(error.rejection().is_none());
+
+        let hash = block_on_mock(Management::upload_chunk(canister(1), vec![0; 10])).unwrap();
+        let stored = block_on_mock(Management::stored_chunks(canister(1))).unwrap();
+
+        assert_eq!(stored, vec![hash]);
+        assert_eq!(mock.calls().len(), 2);
+    }
+
+    #[test]
+    fn test_canister_snapshots() {
+        let snapshot = Snapshot {
+            id: vec![4; 8],
+            taken_at_timestamp: 1_000,
+            total_size: 2_000,
+        };
+
+        let mock = MockTransport::default()
+            .reply(
+                MANAGMENT_CANISTER_ID,
+                "list_canister_snapshots",
+                vec![snapshot.clone()],
+            )
+            .reply(MANAGMENT_CANISTER_ID, "delete_canister_snapshot", ());
+
+        mock.install();
+
+        let snapshots = block_on_mock(Management::list_canister_snapshots(canister(1))).unwrap();
+
+        assert_eq!(snapshots, vec![snapshot.clone()]);
+
+        block_on_mock(Management::delete_canister_snapshot(
+            canister(1),
+            snapshot.id,
+        ))
+        .unwrap();
+
+        let error = block_on_mock(Management::take_canister_snapshot(canister(1), None));
+
+        assert!(error.unwrap_err().rejection().is_some());
+    }
+
+    #[test]
+    fn test_log_visibility() {
+        let mock = MockTransport::default().reply(MANAGMENT_CANISTER_ID, "update_settings", ());
+
+        mock.install();
+
+        block_on_mock(Management::set_log_visibility(
+            canister(1),
+            LogVisibility::Public,
+        ))
+        .unwrap();
+
+        assert_eq!(
+            mock.calls_to(MANAGMENT_CANISTER_ID, "update_settings")
+                .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_bitcoin_fees() {
+        let mock = MockTransport::default()
+            .reply(MANAGMENT_CANISTER_ID, "bitcoin_get_balance", 1_000u64)
+            .reply(MANAGMENT_CANISTER_ID, "bitcoin_send_transaction", ());
+
+        mock.install();
+
+        let balance = block_on_mock(Management::bitcoin_get_balance(GetBalanceRequest {
+            address: "bc1q".to_string(),
+            network: BitcoinNetwork::Mainnet,
+            min_confirmations: None,
+        }))
+        .unwrap();
+
+        assert_eq!(balance, 1_000);
+
+        block_on_mock(Management::bitcoin_send_transaction(
+            SendTransactionRequest {
+                transaction: vec![0; 10],
+                network: BitcoinNetwork::Testnet,
+            },
+        ))
+        .unwrap();
+
+        let error = block_on_mock(Management::bitcoin_send_transaction(
+            SendTransactionRequest {
+                transaction: vec![],
+                network: BitcoinNetwork::Regtest,
+            },
+        ))
+        .unwrap_err();
+
+        assert!(matches!(error, ManagementError::InvalidArgument(_, _)));
+
+        let calls = mock.calls();
+
+        assert_eq!(calls.len(), 2);
+        assert_eq!(calls[0].cycles, 100_000_000);
+        assert_eq!(calls[1].cycles, 2_000_000_000 + 10 * 8_000_000);
+    }
+}
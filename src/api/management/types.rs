@@ -0,0 +1,63 @@
+use candid::CandidType;
+use serde::{Deserialize, Serialize};
+
+use crate::types::CanisterId;
+
+#[derive(CandidType, Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum SchnorrAlgorithm {
+    #[serde(rename = "bip340secp256k1")]
+    Bip340Secp256k1,
+    #[serde(rename = "ed25519")]
+    Ed25519,
+}
+
+#[derive(CandidType, Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone)]
+pub struct SchnorrKeyId {
+    pub algorithm: SchnorrAlgorithm,
+    pub name: String,
+}
+
+#[derive(CandidType, Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct SchnorrPublicKeyArgument {
+    /// Defaults to the caller if `None`.
+    pub canister_id: Option<CanisterId>,
+    pub derivation_path: Vec<Vec<u8>>,
+    pub key_id: SchnorrKeyId,
+}
+
+#[derive(CandidType, Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct SchnorrPublicKeyResponse {
+    pub public_key: Vec<u8>,
+    pub chain_code: Vec<u8>,
+}
+
+#[derive(CandidType, Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct SignWithSchnorrArgument {
+    pub message: Vec<u8>,
+    pub derivation_path: Vec<Vec<u8>>,
+    pub key_id: SchnorrKeyId,
+}
+
+#[derive(CandidType, Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct SignWithSchnorrResponse {
+    pub signature: Vec<u8>,
+}
+
+/// Arguments of `fetch_canister_logs`, the system only answers it as a query from users,
+/// so there is no call wrapper: canisters are rejected.
+#[derive(CandidType, Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct FetchCanisterLogsRequest {
+    pub canister_id: CanisterId,
+}
+
+#[derive(CandidType, Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct CanisterLogRecord {
+    pub idx: u64,
+    pub timestamp_nanos: u64,
+    pub content: Vec<u8>,
+}
+
+#[derive(CandidType, Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct FetchCanisterLogsResponse {
+    pub canister_log_records: Vec<CanisterLogRecord>,
+}
@@ -0,0 +1,86 @@
+use ic_cdk::api::call::{
+    call_raw128, msg_cycles_refunded128, notify_raw, CallResult, RejectionCode,
+};
+use std::{cell::RefCell, future::Future, pin::Pin, rc::Rc};
+
+use crate::types::CanisterId;
+
+mod mock;
+pub use mock::*;
+
+mod test;
+
+pub type CallFuture = Pin<Box<dyn Future<Output = CallResult<Vec<u8>>>>>;
+
+/// Sends the Candid encoded calls of [`InterCall`](super::InterCall) and [`Management`](super::Management).
+///
+/// [`IcTransport`] is used by default, install a [`MockTransport`] with
+/// [`set_call_transport`] to run calls in native tests.
+pub trait CallTransport {
+    fn call_raw(
+        &self,
+        canister_id: CanisterId,
+        method: &str,
+        args: Vec<u8>,
+        cycles: u128,
+    ) -> CallFuture;
+
+    fn notify_raw(
+        &self,
+        canister_id: CanisterId,
+        method: &str,
+        args: &[u8],
+        cycles: u128,
+    ) -> Result<(), RejectionCode>;
+
+    /// Cycles refunded by the last call that returned.
+    fn cycles_refunded(&self) -> u128;
+}
+
+/// Calls other canisters through the system API.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct IcTransport;
+
+impl CallTransport for IcTransport {
+    fn call_raw(
+        &self,
+        canister_id: CanisterId,
+        method: &str,
+        args: Vec<u8>,
+        cycles: u128,
+    ) -> CallFuture {
+        Box::pin(call_raw128(canister_id, method, args, cycles))
+    }
+
+    fn notify_raw(
+        &self,
+        canister_id: CanisterId,
+        method: &str,
+        args: &[u8],
+        cycles: u128,
+    ) -> Result<(), RejectionCode> {
+        notify_raw(canister_id, method, args, cycles)
+    }
+
+    fn cycles_refunded(&self) -> u128 {
+        msg_cycles_refunded128()
+    }
+}
+
+thread_local! {
+    static CALL_TRANSPORT: RefCell<Rc<dyn CallTransport>> = RefCell::new(Rc::new(IcTransport));
+}
+
+/// Replaces the transport of the outgoing calls on this thread.
+pub fn set_call_transport<T: CallTransport + 'static>(transport: T) {
+    CALL_TRANSPORT.with(|call_transport| *call_transport.borrow_mut() = Rc::new(transport));
+}
+
+/// Goes back to [`IcTransport`].
+pub fn reset_call_transport() {
+    set_call_transport(IcTransport);
+}
+
+pub(crate) fn call_transport() -> Rc<dyn CallTransport> {
+    CALL_TRANSPORT.with(|call_transport| call_transport.borrow().clone())
+}
@@ -0,0 +1,204 @@
+use candid::{
+    utils::{ArgumentDecoder, ArgumentEncoder},
+    CandidType,
+};
+use ic_cdk::api::call::{CallResult, RejectionCode};
+use std::{cell::RefCell, collections::HashMap, future::ready, rc::Rc};
+
+use crate::types::CanisterId;
+
+use super::{set_call_transport, CallFuture, CallTransport};
+
+type MockHandler = Rc<dyn Fn(&[u8]) -> CallResult<Vec<u8>>>;
+
+/// A call received by a [`MockTransport`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct MockCall {
+    pub canister_id: CanisterId,
+    pub method: String,
+    pub args: Vec<u8>,
+    pub cycles: u128,
+    pub notify: bool,
+}
+
+impl MockCall {
+    pub fn decode_args<R>(&self) -> candid::Result<R>
+    where
+        R: for<'a> ArgumentDecoder<'a>,
+    {
+        candid::decode_args(&self.args)
+    }
+}
+
+#[derive(Default)]
+struct MockState {
+    handlers: HashMap<(CanisterId, String), MockHandler>,
+    calls: Vec<MockCall>,
+    refunded: u128,
+}
+
+/// Answers calls with the responses registered per canister and method, and keeps the
+/// calls it received. Clones share the same responses and calls.
+///
+/// Calls to a method without a response are rejected with `DestinationInvalid`.
+///
+/// # Example
+/// ```
+/// use b3_utils::api::{reset_call_transport, InterCall, MockTransport, CallCycles};
+/// use b3_utils::mocks::block_on_mock;
+/// use candid::{Nat, Principal};
+///
+/// let ledger = Principal::from_text("ryjl3-tyaaa-aaaaa-aaaba-cai").unwrap();
+///
+/// let mock = MockTransport::default().reply(ledger, "icrc1_fee", Nat::from(10_000u64));
+///
+/// mock.install();
+///
+/// let fee: Nat = block_on_mock(InterCall(ledger).call("icrc1_fee", (), CallCycles::NoPay)).unwrap();
+///
+/// assert_eq!(fee, Nat::from(10_000u64));
+/// assert_eq!(mock.calls()[0].method, "icrc1_fee");
+///
+/// reset_call_transport();
+/// ```
+#[derive(Clone, Default)]
+pub struct MockTransport(Rc<RefCell<MockState>>);
+
+impl MockTransport {
+    /// Replies to every call with `reply`.
+    pub fn reply<R: CandidType>(self, canister_id: CanisterId, method: &str, reply: R) -> Self {
+        self.reply_with_args(canister_id, method, (reply,))
+    }
+
+    /// Replies to every call with several return values.
+    pub fn reply_with_args<R: ArgumentEncoder>(
+        self,
+        canister_id: CanisterId,
+        method: &str,
+        reply: R,
+    ) -> Self {
+        let reply = candid::encode_args(reply).expect("Unable to encode mock reply");
+
+        self.handler(canister_id, method, move |_| Ok(reply.clone()))
+    }
+
+    /// Rejects every call.
+    pub fn reject(
+        self,
+        canister_id: CanisterId,
+        method: &str,
+        code: RejectionCode,
+        message: &str,
+    ) -> Self {
+        let message = message.to_string();
+
+        self.handler(canister_id, method, move |_| Err((code, message.clone())))
+    }
+
+    /// Answers every call with the result of `handler`, given the encoded arguments.
+    pub fn handler<F>(self, canister_id: CanisterId, method: &str, handler: F) -> Self
+    where
+        F: Fn(&[u8]) -> CallResult<Vec<u8>> + 'static,
+    {
+        self.0
+            .borrow_mut()
+            .handlers
+            .insert((canister_id, method.to_string()), Rc::new(handler));
+        self
+    }
+
+    /// Sets the cycles refunded by every call.
+    pub fn refund(self, cycles: u128) -> Self {
+        self.0.borrow_mut().refunded = cycles;
+        self
+    }
+
+    /// Makes it the transport of the outgoing calls on this thread.
+    pub fn install(&self) {
+        set_call_transport(self.clone());
+    }
+
+    pub fn calls(&self) -> Vec<MockCall> {
+        self.0.borrow().calls.clone()
+    }
+
+    pub fn calls_to(&self, canister_id: CanisterId, method: &str) -> Vec<MockCall> {
+        self.0
+            .borrow()
+            .calls
+            .iter()
+            .filter(|call| call.canister_id == canister_id && call.method == method)
+            .cloned()
+            .collect()
+    }
+
+    fn receive(&self, call: MockCall) -> CallResult<Vec<u8>> {
+        let handler = self
+            .0
+            .borrow()
+            .handlers
+            .get(&(call.canister_id, call.method.clone()))
+            .cloned();
+
+        let result = match handler {
+            Some(handler) => handler(&call.args),
+            None => Err((
+                RejectionCode::DestinationInvalid,
+                format!(
+                    "No mock response for {} on {}",
+                    call.method, call.canister_id
+                ),
+            )),
+        };
+
+        self.0.borrow_mut().calls.push(call);
+
+        result
+    }
+}
+
+impl CallTransport for MockTransport {
+    fn call_raw(
+        &self,
+        canister_id: CanisterId,
+        method: &str,
+        args: Vec<u8>,
+        cycles: u128,
+    ) -> CallFuture {
+        let result = self.receive(MockCall {
+            canister_id,
+            method: method.to_string(),
+            args,
+            cycles,
+            notify: false,
+        });
+
+        Box::pin(ready(result))
+    }
+
+    fn notify_raw(
+        &self,
+        canister_id: CanisterId,
+        method: &str,
+        args: &[u8],
+        cycles: u128,
+    ) -> Result<(), RejectionCode> {
+        let result = self.receive(MockCall {
+            canister_id,
+            method: method.to_string(),
+            args: args.to_vec(),
+            cycles,
+            notify: true,
+        });
+
+        // One-way calls only fail when the call can't be sent
+        match result {
+            Err((RejectionCode::SysTransient, _)) => Err(RejectionCode::SysTransient),
+            _ => Ok(()),
+        }
+    }
+
+    fn cycles_refunded(&self) -> u128 {
+        self.0.borrow().refunded
+    }
+}
@@ -0,0 +1,228 @@
+#[cfg(test)]
+mod tests {
+    use candid::{Nat, Principal};
+    use ic_cdk::api::{
+        call::RejectionCode,
+        management_canister::{main::CanisterStatusResponse, provisional::CanisterIdRecord},
+    };
+
+    use crate::{
+        api::{
+            cycles_usage, init_cycles_ledger, CallCycles, CallRetryPolicy, InterCall,
+            InterCallError, Management, MockTransport,
+        },
+        constants::MANAGMENT_CANISTER_ID,
+        mocks::block_on_mock,
+        NanoTimeStamp,
+    };
+
+    fn canister(id: u8) -> Principal {
+        Principal::from_slice(&[id; 10])
+    }
+
+    #[test]
+    fn test_mock_reply() {
+        let mock = MockTransport::default()
+            .reply(canister(1), "greet", "hello".to_string())
+            .reply_with_args(canister(1), "pair", (1u8, "one".to_string()));
+
+        mock.install();
+
+        let greeting: String = block_on_mock(InterCall(canister(1)).call(
+            "greet",
+            "world".to_string(),
+            CallCycles::NoPay,
+        ))
+        .unwrap();
+
+        assert_eq!(greeting, "hello");
+
+        let pair: (u8, String) = block_on_mock(InterCall(canister(1)).call_with_args(
+            "pair",
+            (true, 2u64),
+            CallCycles::Pay(100),
+        ))
+        .unwrap();
+
+        assert_eq!(pair, (1, "one".to_string()));
+
+        let calls = mock.calls();
+
+        assert_eq!(calls.len(), 2);
+        assert_eq!(
+            calls[0].decode_args::<(String,)>().unwrap(),
+            ("world".to_string(),)
+        );
+        assert_eq!(calls[1].decode_args::<(bool, u64)>().unwrap(), (true, 2));
+        assert_eq!(calls[1].cycles, 100);
+        assert_eq!(mock.calls_to(canister(1), "pair").len(), 1);
+    }
+
+    #[test]
+    fn test_mock_reject() {
+        let mock = MockTransport::default()
+            .reject(
+                canister(1),
+                "transfer",
+                RejectionCode::SysTransient,
+                "Queue full",
+            )
+            .reply(canister(1), "fee", 10u64);
+
+        mock.install();
+
+        let error =
+            block_on_mock(InterCall(canister(1)).call::<_, u64>("transfer", (), CallCycles::NoPay))
+                .unwrap_err();
+
+        assert!(error.is_transient());
+        assert_eq!(error.rejection().unwrap().message, "Queue full");
+
+        let error =
+            block_on_mock(InterCall(canister(2)).call::<_, u64>("transfer", (), CallCycles::NoPay))
+                .unwrap_err();
+
+        assert_eq!(
+            error.rejection().unwrap().code,
+            RejectionCode::DestinationInvalid
+        );
+
+        // The reply doesn't decode as the expected type
+        let error =
+            block_on_mock(InterCall(canister(1)).call::<_, String>("fee", (), CallCycles::NoPay))
+                .unwrap_err();
+
+        assert!(matches!(error, InterCallError::CallError(_)));
+
+        assert!(InterCall(canister(1))
+            .notify("fee", (), CallCycles::NoPay)
+            .is_ok());
+        assert!(InterCall(canister(1))
+            .notify("transfer", (), CallCycles::NoPay)
+            .is_err());
+        assert!(mock.calls().last().unwrap().notify);
+    }
+
+    #[test]
+    fn test_mock_management() {
+        let mock = MockTransport::default().reject(
+            MANAGMENT_CANISTER_ID,
+            "canister_status",
+            RejectionCode::CanisterError,
+            "Canister is stopped",
+        );
+
+        mock.install();
+
+        let error = block_on_mock(Management::canister_status(canister(1))).unwrap_err();
+
+        assert!(error.is_canister_stopped());
+
+        let calls = mock.calls_to(MANAGMENT_CANISTER_ID, "canister_status");
+
+        assert_eq!(
+            calls[0].decode_args::<(CanisterIdRecord,)>().unwrap().0,
+            CanisterIdRecord {
+                canister_id: canister(1)
+            }
+        );
+
+        // Replies of the wrong type are rejected
+        MockTransport::default()
+            .reply(MANAGMENT_CANISTER_ID, "canister_status", ())
+            .install();
+
+        let result: Result<CanisterStatusResponse, _> =
+            block_on_mock(Management::canister_status(canister(1)));
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_mock_retry() {
+        let attempts = std::rc::Rc::new(std::cell::Cell::new(0));
+        let counter = attempts.clone();
+
+        MockTransport::default()
+            .handler(canister(1), "fee", move |_| {
+                counter.set(counter.get() + 1);
+
+                if counter.get() < 3 {
+                    Err((RejectionCode::SysTransient, "Busy".to_string()))
+                } else {
+                    Ok(candid::encode_one(Nat::from(10u64)).unwrap())
+                }
+            })
+            .install();
+
+        let call = InterCall(canister(1))
+            .builder()
+            .retry(CallRetryPolicy::new(2));
+
+        assert!(block_on_mock(call.call::<_, Nat>("fee", (), CallCycles::NoPay)).is_err());
+        assert_eq!(attempts.get(), 2);
+
+        let call = call.retry(CallRetryPolicy::new(5));
+
+        assert_eq!(
+            block_on_mock(call.call::<_, Nat>("fee", (), CallCycles::NoPay)).unwrap(),
+            Nat::from(10u64)
+        );
+        assert_eq!(attempts.get(), 3);
+    }
+
+    #[test]
+    fn test_mock_cycles_accounting() {
+        init_cycles_ledger("cycles_ledger", 10).unwrap();
+
+        MockTransport::default()
+            .reply(canister(1), "deposit", ())
+            .refund(400)
+            .install();
+
+        block_on_mock(InterCall(canister(1)).call::<_, ()>(
+            "deposit",
+            (),
+            CallCycles::Pay128(1_000),
+        ))
+        .unwrap();
+
+        let usage = cycles_usage(NanoTimeStamp::now().get_days());
+
+        assert_eq!(usage[0].1.refunded, 400);
+        assert_eq!(usage[0].1.consumed(), 600);
+    }
+
+    #[cfg(feature = "ledger")]
+    #[test]
+    fn test_mock_icrc1() {
+        use crate::ledger::{ICRCAccount, ICRC1};
+
+        let ledger = ICRC1(canister(1));
+
+        let mock = MockTransport::default()
+            .reply(canister(1), "icrc1_balance_of", Nat::from(500u64))
+            .reply(canister(1), "icrc1_symbol", "ICP".to_string());
+
+        mock.install();
+
+        let account = ICRCAccount::new(canister(2), None);
+
+        assert_eq!(
+            block_on_mock(ledger.balance_of(account.clone())).unwrap(),
+            Nat::from(500u64)
+        );
+        assert_eq!(block_on_mock(ledger.symbol()).unwrap(), "ICP");
+
+        let calls = mock.calls_to(canister(1), "icrc1_balance_of");
+
+        assert_eq!(
+            calls[0].decode_args::<(ICRCAccount,)>().unwrap(),
+            (account,)
+        );
+        assert_eq!(
+            mock.calls_to(canister(1), "icrc1_symbol")[0].args,
+            candid::encode_args(()).unwrap()
+        );
+    }
+}
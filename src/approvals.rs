@@ -0,0 +1,455 @@
+use candid::{CandidType, Decode, Encode, Principal};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::borrow::Cow;
+
+use crate::{
+    memory::{
+        error::StableMemoryError,
+        types::{Bound, DefaultStableBTreeMap, DefaultStableCell, Storable},
+        StableMemoryManager,
+    },
+    nonce::Nonce,
+    types::{ControllerIds, Deadline, OperationId},
+    NanoTimeStamp,
+};
+
+mod error;
+mod test;
+
+pub use error::*;
+
+/// The signers allowed to vote and the number of approvals an operation needs.
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug, Default, PartialEq)]
+pub struct ApprovalPolicy {
+    pub signers: ControllerIds,
+    pub threshold: u32,
+}
+
+impl ApprovalPolicy {
+    pub fn new(signers: ControllerIds, threshold: u32) -> Result<Self, ApprovalError> {
+        let mut unique = signers.clone();
+        unique.sort();
+        unique.dedup();
+
+        if unique.len() != signers.len() {
+            return Err(ApprovalError::InvalidPolicy(
+                "duplicate signers".to_string(),
+            ));
+        }
+
+        if threshold == 0 || threshold as usize > signers.len() {
+            return Err(ApprovalError::InvalidPolicy(format!(
+                "threshold {} out of 1..={}",
+                threshold,
+                signers.len()
+            )));
+        }
+
+        Ok(Self { signers, threshold })
+    }
+
+    pub fn is_signer(&self, principal: &Principal) -> bool {
+        self.signers.contains(principal)
+    }
+}
+
+impl Storable for ApprovalPolicy {
+    const BOUND: Bound = Bound::Unbounded;
+
+    fn from_bytes(bytes: Cow<'_, [u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).unwrap()
+    }
+
+    fn to_bytes(&self) -> Cow<'_, [u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
+}
+
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub enum ProposalStatus {
+    /// Waiting for approvals.
+    Pending,
+    /// Enough signers approved, waiting to be executed.
+    Approved,
+    /// Taken by `start_execution`, waiting for `finish_execution`.
+    Executing,
+    Executed,
+    Failed(String),
+    /// Too many signers rejected it to reach the threshold.
+    Rejected,
+    Cancelled,
+    /// The deadline passed before it was approved or executed.
+    Expired,
+}
+
+/// An operation submitted for approval.
+///
+/// The policy is copied when the operation is proposed, later policy changes only
+/// apply to new proposals.
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct Proposal<T> {
+    pub id: OperationId,
+    pub operation: T,
+    pub proposer: Principal,
+    pub policy: ApprovalPolicy,
+    pub approvals: ControllerIds,
+    pub rejections: ControllerIds,
+    pub deadline: Deadline,
+    pub created_at: NanoTimeStamp,
+    pub status: ProposalStatus,
+}
+
+impl<T> Proposal<T> {
+    pub fn is_expired(&self, now: &NanoTimeStamp) -> bool {
+        now.0 >= self.deadline
+    }
+
+    pub fn has_voted(&self, signer: &Principal) -> bool {
+        self.approvals.contains(signer) || self.rejections.contains(signer)
+    }
+
+    fn update_status(&mut self) {
+        let threshold = self.policy.threshold as usize;
+        let remaining = self.policy.signers.len() - self.rejections.len();
+
+        if self.approvals.len() >= threshold {
+            self.status = ProposalStatus::Approved;
+        } else if remaining < threshold {
+            self.status = ProposalStatus::Rejected;
+        }
+    }
+}
+
+impl<T: CandidType + DeserializeOwned> Storable for Proposal<T> {
+    const BOUND: Bound = Bound::Unbounded;
+
+    fn from_bytes(bytes: Cow<'_, [u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).unwrap()
+    }
+
+    fn to_bytes(&self) -> Cow<'_, [u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
+}
+
+/// Proposals of operations of type `T`, kept in stable memory.
+///
+/// The engine doesn't know how to run an operation: once approved, it is taken with
+/// [`start_execution`](Self::start_execution), run by the canister (possibly across
+/// `await` points) and reported with [`finish_execution`](Self::finish_execution).
+/// Callers are passed explicitly, usually `ic_cdk::caller()`.
+///
+/// Takes two partitions: `{name}` at `id` for the proposals and `{name}_policy` at `id + 1`.
+///
+/// # Example
+/// ```
+/// use b3_utils::approvals::{Approvals, ApprovalPolicy, ProposalStatus};
+/// use b3_utils::memory::StableMemoryManager;
+/// use b3_utils::NanoTimeStamp;
+/// use candid::{CandidType, Principal};
+/// use serde::Deserialize;
+///
+/// #[derive(CandidType, Deserialize, Clone)]
+/// enum Operation {
+///     SetOwner(Principal),
+///     Withdraw { to: Principal, amount: u64 },
+/// }
+///
+/// let alice = Principal::from_slice(&[1]);
+/// let bob = Principal::from_slice(&[2]);
+/// let carol = Principal::from_slice(&[3]);
+///
+/// let mut manager = StableMemoryManager::init();
+/// let mut approvals: Approvals<Operation> = Approvals::init(&mut manager, "approvals", 10).unwrap();
+///
+/// // 2 of 3
+/// approvals.set_policy(ApprovalPolicy::new(vec![alice, bob, carol], 2).unwrap()).unwrap();
+///
+/// let deadline = NanoTimeStamp::now().add_days(1).0;
+/// let id = approvals.propose(alice, Operation::SetOwner(bob), deadline).unwrap();
+///
+/// assert_eq!(approvals.approve(id, carol).unwrap(), ProposalStatus::Approved);
+///
+/// approvals
+///     .execute(id, |operation| match operation {
+///         Operation::SetOwner(_owner) => Ok(()),
+///         Operation::Withdraw { .. } => Err("not supported".to_string()),
+///     })
+///     .unwrap();
+///
+/// assert_eq!(approvals.get(id).unwrap().status, ProposalStatus::Executed);
+/// ```
+pub struct Approvals<T: CandidType + DeserializeOwned + Clone> {
+    proposals: DefaultStableBTreeMap<OperationId, Proposal<T>>,
+    policy: DefaultStableCell<ApprovalPolicy>,
+}
+
+impl<T: CandidType + DeserializeOwned + Clone> Approvals<T> {
+    pub fn init(
+        manager: &mut StableMemoryManager,
+        name: &str,
+        id: u8,
+    ) -> Result<Self, StableMemoryError> {
+        let proposals = manager.init_memory(name, id)?;
+        let policy = manager.init_memory(&format!("{}_policy", name), id + 1)?;
+
+        Ok(Self { proposals, policy })
+    }
+
+    pub fn policy(&self) -> ApprovalPolicy {
+        self.policy.get().clone()
+    }
+
+    /// Replaces the policy of the next proposals.
+    pub fn set_policy(&mut self, policy: ApprovalPolicy) -> Result<ApprovalPolicy, ApprovalError> {
+        self.policy
+            .set(policy)
+            .map_err(|e| ApprovalError::StorageError(format!("{:?}", e)))
+    }
+
+    /// Submits an operation, the proposer's approval is counted right away.
+    pub fn propose(
+        &mut self,
+        proposer: Principal,
+        operation: T,
+        deadline: Deadline,
+    ) -> Result<OperationId, ApprovalError> {
+        let policy = self.policy();
+
+        if policy.threshold == 0 {
+            return Err(ApprovalError::InvalidPolicy("no policy set".to_string()));
+        }
+
+        if !policy.is_signer(&proposer) {
+            return Err(ApprovalError::NotSigner(proposer));
+        }
+
+        let now = NanoTimeStamp::now();
+
+        if now.0 >= deadline {
+            return Err(ApprovalError::InvalidDeadline(deadline));
+        }
+
+        let id = self
+            .proposals
+            .last_key_value()
+            .map(|(id, _)| id.add_64(1))
+            .unwrap_or(Nonce::zero());
+
+        let mut proposal = Proposal {
+            id,
+            operation,
+            proposer,
+            policy,
+            approvals: vec![proposer],
+            rejections: vec![],
+            deadline,
+            created_at: now,
+            status: ProposalStatus::Pending,
+        };
+
+        proposal.update_status();
+
+        self.proposals.insert(id, proposal);
+
+        Ok(id)
+    }
+
+    /// Adds the approval of `signer`, returns the new status of the proposal.
+    pub fn approve(
+        &mut self,
+        id: OperationId,
+        signer: Principal,
+    ) -> Result<ProposalStatus, ApprovalError> {
+        self.vote(id, signer, true)
+    }
+
+    /// Adds the rejection of `signer`, the proposal is rejected once the threshold
+    /// can't be reached anymore.
+    pub fn reject(
+        &mut self,
+        id: OperationId,
+        signer: Principal,
+    ) -> Result<ProposalStatus, ApprovalError> {
+        self.vote(id, signer, false)
+    }
+
+    /// Cancels a proposal that wasn't executed yet, only its proposer can.
+    pub fn cancel(&mut self, id: OperationId, caller: Principal) -> Result<(), ApprovalError> {
+        let mut proposal = self.pending_or_approved(id)?;
+
+        if proposal.proposer != caller {
+            return Err(ApprovalError::NotProposer(caller));
+        }
+
+        proposal.status = ProposalStatus::Cancelled;
+
+        self.proposals.insert(id, proposal);
+
+        Ok(())
+    }
+
+    /// Marks an approved proposal as executing and returns its operation.
+    ///
+    /// The deadline also bounds the execution, an approved proposal past it is expired.
+    pub fn start_execution(&mut self, id: OperationId) -> Result<T, ApprovalError> {
+        let mut proposal = self.proposal(id)?;
+
+        if proposal.status != ProposalStatus::Approved {
+            return Err(ApprovalError::InvalidStatus(id, proposal.status));
+        }
+
+        if proposal.is_expired(&NanoTimeStamp::now()) {
+            proposal.status = ProposalStatus::Expired;
+
+            self.proposals.insert(id, proposal);
+
+            return Err(ApprovalError::ProposalExpired(id));
+        }
+
+        proposal.status = ProposalStatus::Executing;
+
+        let operation = proposal.operation.clone();
+
+        self.proposals.insert(id, proposal);
+
+        Ok(operation)
+    }
+
+    /// Records the result of an operation taken with `start_execution`.
+    pub fn finish_execution(
+        &mut self,
+        id: OperationId,
+        result: Result<(), String>,
+    ) -> Result<ProposalStatus, ApprovalError> {
+        let mut proposal = self.proposal(id)?;
+
+        if proposal.status != ProposalStatus::Executing {
+            return Err(ApprovalError::InvalidStatus(id, proposal.status));
+        }
+
+        proposal.status = match result {
+            Ok(()) => ProposalStatus::Executed,
+            Err(e) => ProposalStatus::Failed(e),
+        };
+
+        let status = proposal.status.clone();
+
+        self.proposals.insert(id, proposal);
+
+        Ok(status)
+    }
+
+    /// Runs an approved operation that doesn't need to await.
+    pub fn execute<F>(&mut self, id: OperationId, run: F) -> Result<ProposalStatus, ApprovalError>
+    where
+        F: FnOnce(&T) -> Result<(), String>,
+    {
+        let operation = self.start_execution(id)?;
+
+        self.finish_execution(id, run(&operation))
+    }
+
+    pub fn get(&self, id: OperationId) -> Option<Proposal<T>> {
+        self.proposals.get(&id)
+    }
+
+    /// Returns the proposals with the given status, oldest first.
+    pub fn get_by_status(&self, status: &ProposalStatus) -> Vec<Proposal<T>> {
+        self.proposals
+            .iter()
+            .map(|(_, proposal)| proposal)
+            .filter(|proposal| &proposal.status == status)
+            .collect()
+    }
+
+    /// Marks the pending and approved proposals past their deadline as expired,
+    /// returns how many.
+    pub fn expire(&mut self, now: &NanoTimeStamp) -> u64 {
+        let expired: Vec<Proposal<T>> = self
+            .proposals
+            .iter()
+            .map(|(_, proposal)| proposal)
+            .filter(|proposal| {
+                matches!(
+                    proposal.status,
+                    ProposalStatus::Pending | ProposalStatus::Approved
+                ) && proposal.is_expired(now)
+            })
+            .collect();
+
+        let count = expired.len() as u64;
+
+        for mut proposal in expired {
+            proposal.status = ProposalStatus::Expired;
+
+            self.proposals.insert(proposal.id, proposal);
+        }
+
+        count
+    }
+
+    pub fn len(&self) -> u64 {
+        self.proposals.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.proposals.is_empty()
+    }
+
+    fn proposal(&self, id: OperationId) -> Result<Proposal<T>, ApprovalError> {
+        self.get(id).ok_or(ApprovalError::ProposalNotFound(id))
+    }
+
+    fn pending_or_approved(&self, id: OperationId) -> Result<Proposal<T>, ApprovalError> {
+        let proposal = self.proposal(id)?;
+
+        match proposal.status {
+            ProposalStatus::Pending | ProposalStatus::Approved => Ok(proposal),
+            status => Err(ApprovalError::InvalidStatus(id, status)),
+        }
+    }
+
+    fn vote(
+        &mut self,
+        id: OperationId,
+        signer: Principal,
+        approve: bool,
+    ) -> Result<ProposalStatus, ApprovalError> {
+        let mut proposal = self.proposal(id)?;
+
+        if proposal.status != ProposalStatus::Pending {
+            return Err(ApprovalError::InvalidStatus(id, proposal.status));
+        }
+
+        if proposal.is_expired(&NanoTimeStamp::now()) {
+            proposal.status = ProposalStatus::Expired;
+
+            self.proposals.insert(id, proposal);
+
+            return Err(ApprovalError::ProposalExpired(id));
+        }
+
+        if !proposal.policy.is_signer(&signer) {
+            return Err(ApprovalError::NotSigner(signer));
+        }
+
+        if proposal.has_voted(&signer) {
+            return Err(ApprovalError::AlreadyVoted(signer));
+        }
+
+        if approve {
+            proposal.approvals.push(signer);
+        } else {
+            proposal.rejections.push(signer);
+        }
+
+        proposal.update_status();
+
+        let status = proposal.status.clone();
+
+        self.proposals.insert(id, proposal);
+
+        Ok(status)
+    }
+}
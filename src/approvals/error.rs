@@ -0,0 +1,36 @@
+use candid::{CandidType, Principal};
+use serde::{Deserialize, Serialize};
+
+use crate::types::OperationId;
+
+use super::ProposalStatus;
+
+#[derive(CandidType, Debug, Clone, Deserialize, Serialize, PartialEq)]
+pub enum ApprovalError {
+    InvalidPolicy(String),
+    InvalidDeadline(u64),
+    NotSigner(Principal),
+    NotProposer(Principal),
+    AlreadyVoted(Principal),
+    ProposalNotFound(OperationId),
+    ProposalExpired(OperationId),
+    InvalidStatus(OperationId, ProposalStatus),
+    StorageError(String),
+}
+
+#[rustfmt::skip]
+impl std::fmt::Display for ApprovalError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ApprovalError::InvalidPolicy(e) => write!(f, "Invalid approval policy: {}", e),
+            ApprovalError::InvalidDeadline(deadline) => write!(f, "Deadline {} is in the past", deadline),
+            ApprovalError::NotSigner(principal) => write!(f, "{} is not a signer", principal),
+            ApprovalError::NotProposer(principal) => write!(f, "{} is not the proposer", principal),
+            ApprovalError::AlreadyVoted(principal) => write!(f, "{} already voted", principal),
+            ApprovalError::ProposalNotFound(id) => write!(f, "Proposal {} not found", id),
+            ApprovalError::ProposalExpired(id) => write!(f, "Proposal {} expired", id),
+            ApprovalError::InvalidStatus(id, status) => write!(f, "Proposal {} is {:?}", id, status),
+            ApprovalError::StorageError(e) => write!(f, "Storage error: {}", e),
+        }
+    }
+}
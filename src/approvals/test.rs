@@ -0,0 +1,261 @@
+#[cfg(test)]
+mod tests {
+    use candid::{CandidType, Principal};
+    use ic_stable_structures::Storable;
+    use serde::Deserialize;
+
+    use crate::{
+        approvals::{ApprovalError, ApprovalPolicy, Approvals, Proposal, ProposalStatus},
+        memory::StableMemoryManager,
+        nonce::Nonce,
+        NanoTimeStamp,
+    };
+
+    #[derive(CandidType, Deserialize, Clone, Debug, PartialEq)]
+    enum Operation {
+        SetOwner(Principal),
+        Withdraw { to: Principal, amount: u64 },
+    }
+
+    fn signer(id: u8) -> Principal {
+        Principal::from_slice(&[id; 29])
+    }
+
+    fn deadline() -> u64 {
+        NanoTimeStamp::now().add_hours(1).0
+    }
+
+    fn init_approvals(manager: &mut StableMemoryManager) -> Approvals<Operation> {
+        let mut approvals = Approvals::init(manager, "approvals", 10).unwrap();
+
+        approvals
+            .set_policy(ApprovalPolicy::new(vec![signer(1), signer(2), signer(3)], 2).unwrap())
+            .unwrap();
+
+        approvals
+    }
+
+    #[test]
+    fn test_approval_policy() {
+        assert!(ApprovalPolicy::new(vec![signer(1), signer(2)], 2).is_ok());
+        assert!(ApprovalPolicy::new(vec![signer(1), signer(2)], 3).is_err());
+        assert!(ApprovalPolicy::new(vec![signer(1)], 0).is_err());
+        assert!(ApprovalPolicy::new(vec![signer(1), signer(1)], 1).is_err());
+    }
+
+    #[test]
+    fn test_proposal_to_and_from_bytes() {
+        let proposal = Proposal {
+            id: Nonce(1),
+            operation: Operation::Withdraw {
+                to: signer(4),
+                amount: 100,
+            },
+            proposer: signer(1),
+            policy: ApprovalPolicy::new(vec![signer(1)], 1).unwrap(),
+            approvals: vec![signer(1)],
+            rejections: vec![],
+            deadline: 10,
+            created_at: NanoTimeStamp(5),
+            status: ProposalStatus::Failed("error".to_string()),
+        };
+
+        assert_eq!(Proposal::from_bytes(proposal.to_bytes()), proposal);
+    }
+
+    #[test]
+    fn test_approve_and_execute() {
+        let mut manager = StableMemoryManager::init();
+
+        let mut approvals = init_approvals(&mut manager);
+
+        assert_eq!(
+            approvals.propose(signer(4), Operation::SetOwner(signer(4)), deadline()),
+            Err(ApprovalError::NotSigner(signer(4)))
+        );
+        assert!(matches!(
+            approvals.propose(signer(1), Operation::SetOwner(signer(4)), 1),
+            Err(ApprovalError::InvalidDeadline(1))
+        ));
+
+        let id = approvals
+            .propose(signer(1), Operation::SetOwner(signer(4)), deadline())
+            .unwrap();
+
+        assert_eq!(id, Nonce(0));
+        assert_eq!(approvals.get(id).unwrap().status, ProposalStatus::Pending);
+
+        // The proposer already approved
+        assert_eq!(
+            approvals.approve(id, signer(1)),
+            Err(ApprovalError::AlreadyVoted(signer(1)))
+        );
+        assert_eq!(
+            approvals.approve(id, signer(4)),
+            Err(ApprovalError::NotSigner(signer(4)))
+        );
+        assert!(matches!(
+            approvals.start_execution(id),
+            Err(ApprovalError::InvalidStatus(_, ProposalStatus::Pending))
+        ));
+
+        assert_eq!(
+            approvals.approve(id, signer(2)).unwrap(),
+            ProposalStatus::Approved
+        );
+
+        // Executed across an await point
+        assert_eq!(
+            approvals.start_execution(id).unwrap(),
+            Operation::SetOwner(signer(4))
+        );
+        assert!(approvals.start_execution(id).is_err());
+        assert_eq!(
+            approvals.finish_execution(id, Ok(())).unwrap(),
+            ProposalStatus::Executed
+        );
+
+        let id = approvals
+            .propose(signer(2), Operation::SetOwner(signer(5)), deadline())
+            .unwrap();
+
+        approvals.approve(id, signer(3)).unwrap();
+
+        assert_eq!(
+            approvals
+                .execute(id, |_| Err("call failed".to_string()))
+                .unwrap(),
+            ProposalStatus::Failed("call failed".to_string())
+        );
+
+        assert_eq!(approvals.len(), 2);
+        assert_eq!(approvals.get_by_status(&ProposalStatus::Executed).len(), 1);
+    }
+
+    #[test]
+    fn test_reject_cancel_and_expire() {
+        let mut manager = StableMemoryManager::init();
+
+        let mut approvals = init_approvals(&mut manager);
+
+        let rejected = approvals
+            .propose(signer(1), Operation::SetOwner(signer(4)), deadline())
+            .unwrap();
+
+        assert_eq!(
+            approvals.reject(rejected, signer(2)).unwrap(),
+            ProposalStatus::Pending
+        );
+        assert_eq!(
+            approvals.reject(rejected, signer(3)).unwrap(),
+            ProposalStatus::Rejected
+        );
+        assert!(matches!(
+            approvals.approve(rejected, signer(3)),
+            Err(ApprovalError::InvalidStatus(_, ProposalStatus::Rejected))
+        ));
+
+        let cancelled = approvals
+            .propose(signer(1), Operation::SetOwner(signer(4)), deadline())
+            .unwrap();
+
+        assert_eq!(
+            approvals.cancel(cancelled, signer(2)),
+            Err(ApprovalError::NotProposer(signer(2)))
+        );
+        assert!(approvals.cancel(cancelled, signer(1)).is_ok());
+        assert_eq!(
+            approvals.get(cancelled).unwrap().status,
+            ProposalStatus::Cancelled
+        );
+
+        let expired = approvals
+            .propose(
+                signer(1),
+                Operation::SetOwner(signer(4)),
+                NanoTimeStamp::now().0 + NanoTimeStamp::NS_PER_MILLISECOND,
+            )
+            .unwrap();
+
+        std::thread::sleep(std::time::Duration::from_millis(2));
+
+        assert_eq!(
+            approvals.approve(expired, signer(2)),
+            Err(ApprovalError::ProposalExpired(expired))
+        );
+        assert_eq!(
+            approvals.get(expired).unwrap().status,
+            ProposalStatus::Expired
+        );
+
+        // An approved proposal can't be executed past its deadline
+        let late = approvals
+            .propose(
+                signer(1),
+                Operation::SetOwner(signer(4)),
+                NanoTimeStamp::now().0 + NanoTimeStamp::NS_PER_MILLISECOND,
+            )
+            .unwrap();
+
+        assert_eq!(
+            approvals.approve(late, signer(2)).unwrap(),
+            ProposalStatus::Approved
+        );
+
+        std::thread::sleep(std::time::Duration::from_millis(2));
+
+        assert_eq!(
+            approvals.start_execution(late),
+            Err(ApprovalError::ProposalExpired(late))
+        );
+        assert_eq!(approvals.get(late).unwrap().status, ProposalStatus::Expired);
+
+        let pending = approvals
+            .propose(signer(1), Operation::SetOwner(signer(4)), deadline())
+            .unwrap();
+        let approved = approvals
+            .propose(signer(1), Operation::SetOwner(signer(4)), deadline())
+            .unwrap();
+
+        approvals.approve(approved, signer(2)).unwrap();
+
+        assert_eq!(approvals.expire(&NanoTimeStamp::now()), 0);
+        assert_eq!(approvals.expire(&NanoTimeStamp::now().add_days(1)), 2);
+        assert_eq!(
+            approvals.get(pending).unwrap().status,
+            ProposalStatus::Expired
+        );
+        assert_eq!(
+            approvals.get(approved).unwrap().status,
+            ProposalStatus::Expired
+        );
+    }
+
+    #[test]
+    fn test_policy_is_copied_to_proposals() {
+        let mut manager = StableMemoryManager::init();
+
+        let mut approvals = init_approvals(&mut manager);
+
+        let id = approvals
+            .propose(signer(1), Operation::SetOwner(signer(4)), deadline())
+            .unwrap();
+
+        approvals
+            .set_policy(ApprovalPolicy::new(vec![signer(1)], 1).unwrap())
+            .unwrap();
+
+        assert_eq!(approvals.get(id).unwrap().policy.threshold, 2);
+        assert_eq!(
+            approvals.approve(id, signer(2)).unwrap(),
+            ProposalStatus::Approved
+        );
+
+        // With a threshold of one, proposals are approved right away
+        let id = approvals
+            .propose(signer(1), Operation::SetOwner(signer(4)), deadline())
+            .unwrap();
+
+        assert_eq!(approvals.get(id).unwrap().status, ProposalStatus::Approved);
+    }
+}
@@ -11,6 +11,8 @@
 //! - `sha2`: Enables SHA-2 hashing functionality.
 //! - `wasm`: Enables WebAssembly-related functionalities.
 //! - `rpc`: Enables EVM-RPC-canister functionalities.
+//! - `timer`: Enables the task timer executor driven by `ic-cdk-timers`.
+//! - `inspect`: Enables the offline inspection of stable memory dumps. Includes dependency `serde_json`.
 //!
 //! To enable a feature, add it to your `Cargo.toml` like so:
 //!
@@ -73,7 +75,9 @@ pub use environment::*;
 mod utils;
 pub use utils::*;
 
+pub mod approvals;
 pub mod owner;
+pub mod ratelimit;
 
 #[cfg(feature = "metadata")]
 pub mod metadata;
@@ -99,6 +103,12 @@ pub mod principal;
 #[cfg(feature = "rpc")]
 pub mod rpc;
 
+// Used by the exported macros, so callers don't need these dependencies
+#[doc(hidden)]
+pub use candid;
+#[doc(hidden)]
+pub use serde;
+
 // VETKD API not yet ready for use
 #[cfg(feature = "exprimental_vetkd")]
 pub mod vetkd;
@@ -1,5 +1,6 @@
-use ic_stable_structures::memory_manager::{MemoryId, MemoryManager};
+use ic_stable_structures::memory_manager::{MemoryId, MemoryManager, VirtualMemory};
 use ic_stable_structures::{DefaultMemoryImpl, Memory};
+use std::{cell::Cell, collections::BTreeMap, rc::Rc};
 
 mod test;
 
@@ -7,7 +8,14 @@ pub mod error;
 use error::StableMemoryError;
 
 pub mod backup;
+pub mod events;
+pub mod indexed;
+pub mod quota;
 pub mod timer;
+pub mod ttl;
+
+#[cfg(feature = "inspect")]
+pub mod inspect;
 
 mod store;
 pub use store::*;
@@ -15,26 +23,46 @@ pub use store::*;
 mod helper;
 pub use helper::*;
 
+mod state;
+pub use state::*;
+
 pub mod partitions;
 pub mod traits;
 pub mod types;
 
-use types::{DefaultStableMinHeap, DefaultVM};
+use types::DefaultStableMinHeap;
 
 use self::backup::BackupPartition;
 use self::partitions::{PartitionName, Partitions};
+use self::quota::{memory_bytes, PartitionQuota, QuotaGuard, QuotaStatus};
 use self::traits::{InitMemory, InitMemoryArg, MemoryType};
 use self::types::PartitionDetail;
 
-pub struct StableMemoryManager {
-    memory_manager: MemoryManager<DefaultMemoryImpl>,
-    backup: BackupPartition,
-    partitions: Partitions,
+/// Splits a memory into named partitions.
+///
+/// The memory defaults to `DefaultMemoryImpl`, the canister stable memory on wasm and a
+/// `VectorMemory` natively. Any other `Memory` can be used with `init_with`, e.g. a
+/// `FileMemory` holding a dump of the stable memory.
+pub struct StableMemoryManager<M: Memory = DefaultMemoryImpl> {
+    memory: M,
+    memory_manager: MemoryManager<M>,
+    backup: BackupPartition<M>,
+    partitions: Partitions<M>,
+    quotas: BTreeMap<String, Rc<Cell<PartitionQuota>>>,
+    /// Partitions covered by a quota, set by `init_memory_with_quota`.
+    quota_partitions: BTreeMap<String, Vec<String>>,
 }
 
 impl StableMemoryManager {
     pub fn init() -> Self {
-        let memory_manager = MemoryManager::init(DefaultMemoryImpl::default());
+        Self::init_with(DefaultMemoryImpl::default())
+    }
+}
+
+impl<M: Memory + Clone> StableMemoryManager<M> {
+    /// Loads the partitions stored in `memory`, or starts from scratch if it is empty.
+    pub fn init_with(memory: M) -> Self {
+        let memory_manager = MemoryManager::init(memory.clone());
         let partitions_vm = memory_manager.get(MemoryId::new(254));
         let partitions = Partitions::init(partitions_vm);
 
@@ -42,12 +70,30 @@ impl StableMemoryManager {
         let backup = BackupPartition::init(backup_vm);
 
         Self {
+            memory,
             memory_manager,
             partitions,
             backup,
+            quotas: BTreeMap::new(),
+            quota_partitions: BTreeMap::new(),
         }
     }
 
+    /// Returns a manager loaded from the same memory, the way `#[post_upgrade]` would see it.
+    ///
+    /// Everything kept on the heap, like the quotas, is lost. Collections created from this
+    /// manager should be dropped and initialized again from the new one.
+    pub fn simulate_upgrade(&self) -> Self {
+        Self::init_with(self.memory.clone())
+    }
+
+    /// Returns the underlying memory.
+    pub fn inner_memory(&self) -> &M {
+        &self.memory
+    }
+}
+
+impl<M: Memory> StableMemoryManager<M> {
     fn check_id(&self, id: u8) -> Result<(), StableMemoryError> {
         if id == 0 || id > 250 {
             return Err(StableMemoryError::IdOutOfRange(id));
@@ -73,7 +119,7 @@ impl StableMemoryManager {
         Ok(())
     }
 
-    pub fn create(&mut self, name: &str, id: u8) -> Result<DefaultVM, StableMemoryError> {
+    pub fn create(&mut self, name: &str, id: u8) -> Result<VirtualMemory<M>, StableMemoryError> {
         let name = PartitionName::from(name);
 
         self.check_partition(&name, id)?;
@@ -87,25 +133,28 @@ impl StableMemoryManager {
         Ok(memory)
     }
 
-    pub fn get(&self, id: u8) -> DefaultVM {
+    pub fn get(&self, id: u8) -> VirtualMemory<M> {
         self.memory_manager.get(MemoryId::new(id))
     }
 
-    pub fn backup(&self) -> &BackupPartition {
+    pub fn backup(&self) -> &BackupPartition<M> {
         &self.backup
     }
 
-    pub fn backup_mut(&mut self) -> &mut BackupPartition {
+    pub fn backup_mut(&mut self) -> &mut BackupPartition<M> {
         &mut self.backup
     }
 
+    /// Lists the partitions, with the quota of the collection each one belongs to.
     pub fn partition_details(&self) -> Vec<PartitionDetail> {
         self.partitions
             .iter()
             .map(|(name, id)| PartitionDetail {
                 id,
-                name: name.to_string(),
                 size: self.get(id).size(),
+                bytes: memory_bytes(&self.get(id)),
+                quota: self.quota(self.quota_name(&name.to_string())),
+                name: name.to_string(),
             })
             .collect()
     }
@@ -114,11 +163,11 @@ impl StableMemoryManager {
         self.partitions.get(&name.into())
     }
 
-    pub fn partitions(&self) -> &Partitions {
+    pub fn partitions(&self) -> &Partitions<M> {
         &self.partitions
     }
 
-    pub fn memory(&self, name: &str) -> Option<DefaultVM> {
+    pub fn memory(&self, name: &str) -> Option<VirtualMemory<M>> {
         let memory_id = self.partitions.get(&name.into())?;
 
         let vm = self.memory_manager.get(MemoryId::new(memory_id));
@@ -126,16 +175,90 @@ impl StableMemoryManager {
         Some(vm)
     }
 
-    pub fn memory_manager(&self) -> &MemoryManager<DefaultMemoryImpl> {
+    /// Sets the quota of a partition, it doesn't need to exist yet.
+    /// A guard already created for the partition sees the new quota.
+    pub fn set_quota(&mut self, name: &str, quota: PartitionQuota) {
+        self.shared_quota(name).set(quota);
+    }
+
+    pub fn quota(&self, name: &str) -> Option<PartitionQuota> {
+        self.quotas.get(name).map(|quota| quota.get())
+    }
+
+    fn shared_quota(&mut self, name: &str) -> Rc<Cell<PartitionQuota>> {
+        self.quotas.entry(name.to_string()).or_default().clone()
+    }
+
+    /// Returns the name of the quota covering a partition, its own name unless it is one
+    /// of the partitions of a collection created by `init_memory_with_quota`.
+    fn quota_name<'a>(&'a self, partition: &'a str) -> &'a str {
+        self.quota_partitions
+            .iter()
+            .find(|(_, partitions)| partitions.iter().any(|name| name == partition))
+            .map_or(partition, |(name, _)| name.as_str())
+    }
+
+    /// Returns the bytes allocated by a partition, or by every partition of a collection
+    /// created by `init_memory_with_quota` under that name (e.g. both partitions of a log).
+    pub fn used_bytes(&self, name: &str) -> Option<u64> {
+        match self.quota_partitions.get(name) {
+            Some(partitions) => partitions
+                .iter()
+                .map(|name| self.memory(name).map(|memory| memory_bytes(&memory)))
+                .sum(),
+            None => self.memory(name).map(|memory| memory_bytes(&memory)),
+        }
+    }
+
+    /// Checks the usage of a partition, or of a collection, against its quota.
+    pub fn check_quota(&self, name: &str) -> Result<QuotaStatus, StableMemoryError> {
+        let used = self.used_bytes(name).unwrap_or_default();
+
+        match self.quota(name) {
+            Some(quota) => quota.check(name, used),
+            None => Ok(QuotaStatus::Within),
+        }
+    }
+
+    /// Same as `init_memory`, with writes checked against `quota`.
+    /// The quota covers every partition of the collection (e.g. both partitions of a log).
+    pub fn init_memory_with_quota<T: InitMemory<T, M>>(
+        &mut self,
+        name: &str,
+        id: u8,
+        quota: PartitionQuota,
+    ) -> Result<QuotaGuard<T, M>, StableMemoryError> {
+        let inner = self.init_memory::<T>(name, id)?;
+
+        let names = T::memory_type().partition_names(name);
+
+        let memories = names.iter().filter_map(|name| self.memory(name)).collect();
+
+        self.quota_partitions.insert(name.to_string(), names);
+        self.set_quota(name, quota);
+
+        Ok(QuotaGuard::new(
+            inner,
+            name,
+            memories,
+            self.shared_quota(name),
+        ))
+    }
+
+    pub fn memory_manager(&self) -> &MemoryManager<M> {
         &self.memory_manager
     }
 
-    pub fn init_memory<T: InitMemory<T>>(
+    pub fn init_memory<T: InitMemory<T, M>>(
         &mut self,
         name: &str,
         id: u8,
     ) -> Result<T, StableMemoryError> {
+        let partitions = T::memory_type().partition_names(name).len() as u8;
+
+        // The last partition must stay below the reserved ids too
         self.check_id(id)?;
+        self.check_id(id + partitions - 1)?;
 
         let init_arg = match T::memory_type() {
             MemoryType::Vec => {
@@ -169,6 +292,18 @@ impl StableMemoryManager {
 
                 InitMemoryArg::Single(memory)
             }
+            MemoryType::TtlMap => {
+                let entries_memory = self.create(name, id)?;
+                let expiry_memory = self.create(&format!("{}_expiry", name), id + 1)?;
+
+                InitMemoryArg::Double(entries_memory, expiry_memory)
+            }
+            MemoryType::NonceMap => {
+                let items_memory = self.create(name, id)?;
+                let nonce_memory = self.create(&format!("{}_nonce", name), id + 1)?;
+
+                InitMemoryArg::Double(items_memory, nonce_memory)
+            }
         };
 
         T::init(init_arg)
@@ -1,22 +1,22 @@
-use ic_stable_structures::{writer::Writer, Memory};
+use ic_stable_structures::{
+    memory_manager::VirtualMemory, writer::Writer, DefaultMemoryImpl, Memory,
+};
 use std::borrow::BorrowMut;
 
-use super::types::DefaultVM;
-
 mod test;
 
-pub struct BackupPartition(DefaultVM);
+pub struct BackupPartition<M: Memory = DefaultMemoryImpl>(VirtualMemory<M>);
 
-impl BackupPartition {
-    pub fn init(default_vm: DefaultVM) -> Self {
+impl<M: Memory> BackupPartition<M> {
+    pub fn init(default_vm: VirtualMemory<M>) -> Self {
         Self(default_vm)
     }
 
-    pub fn backup(&self) -> &DefaultVM {
+    pub fn backup(&self) -> &VirtualMemory<M> {
         &self.0
     }
 
-    pub fn backup_mut(&mut self) -> &mut DefaultVM {
+    pub fn backup_mut(&mut self) -> &mut VirtualMemory<M> {
         &mut self.0
     }
 
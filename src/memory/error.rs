@@ -7,6 +7,15 @@ pub enum StableMemoryError {
     IdAlreadyUsed(String),
     IdOutOfRange(u8),
     UnableToCreateMemory(String),
+    IndexNotFound(String),
+    IndexInconsistent(String),
+    ItemNotFound(u64),
+    QuotaExceeded { name: String, used: u64, limit: u64 },
+    PartitionNotFound(String),
+    DecoderNotFound(String),
+    InvalidDump(String),
+    SubscriberNotFound(String),
+    CursorOutOfRange(u64),
 }
 
 #[rustfmt::skip]
@@ -19,7 +28,16 @@ impl std::fmt::Display for StableMemoryError {
             StableMemoryError::WrongInitializationArgument => write!(f, "Wrong initialization argument"),
             StableMemoryError::IdOutOfRange(id) => write!(f, "Wrong ID {} - must be between 1 and 250", id),
             StableMemoryError::IdAlreadyUsed(name) => write!(f, "ID already used for partition {}", name),
-            StableMemoryError::UnableToCreateMemory(err) => write!(f, "Unable to create memory: {:?}", err.to_string())
+            StableMemoryError::UnableToCreateMemory(err) => write!(f, "Unable to create memory: {:?}", err.to_string()),
+            StableMemoryError::IndexNotFound(name) => write!(f, "Index {} not found", name),
+            StableMemoryError::IndexInconsistent(name) => write!(f, "Index {} is inconsistent with the map", name),
+            StableMemoryError::ItemNotFound(id) => write!(f, "Item {} not found", id),
+            StableMemoryError::QuotaExceeded { name, used, limit } => write!(f, "Quota exceeded for partition {}: {} bytes used, limit is {}", name, used, limit),
+            StableMemoryError::PartitionNotFound(name) => write!(f, "Partition {} not found", name),
+            StableMemoryError::DecoderNotFound(name) => write!(f, "No decoder registered for partition {}", name),
+            StableMemoryError::InvalidDump(err) => write!(f, "Invalid stable memory dump: {}", err),
+            StableMemoryError::SubscriberNotFound(name) => write!(f, "Subscriber {} not found", name),
+            StableMemoryError::CursorOutOfRange(seq) => write!(f, "Cursor {} is out of range", seq)
         }
     }
 }
@@ -0,0 +1,320 @@
+use ic_stable_structures::{storable::Bound, Storable};
+use std::{
+    borrow::Cow,
+    ops::{Bound as RangeBound, RangeBounds},
+};
+
+use super::{
+    error::StableMemoryError,
+    types::{DefaultStableBTreeMap, DefaultStableCell, DefaultStableLog, DefaultVM, StableLog},
+    StableMemoryManager,
+};
+use crate::nonce::Nonce;
+
+mod test;
+
+/// The compaction state of an [`EventLog`]: the snapshot of the compacted events
+/// and the sequence number of the first event still in the log.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct EventLogMeta<S> {
+    pub first: Nonce,
+    pub snapshot: S,
+}
+
+/// Layout: `[first 8][snapshot ..]`.
+impl<S: Storable> Storable for EventLogMeta<S> {
+    fn to_bytes(&self) -> Cow<'_, [u8]> {
+        let snapshot_bytes = self.snapshot.to_bytes();
+
+        let mut bytes = Vec::with_capacity(8 + snapshot_bytes.len());
+        bytes.extend_from_slice(&self.first.to_le_bytes());
+        bytes.extend_from_slice(&snapshot_bytes);
+        bytes.into()
+    }
+
+    fn from_bytes(bytes: Cow<'_, [u8]>) -> Self {
+        Self {
+            first: Nonce::from_le_bytes(bytes[0..8].try_into().unwrap()),
+            snapshot: S::from_bytes(bytes[8..].to_vec().into()),
+        }
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
+
+/// An append-only log of events with sequence numbers, consumer cursors and compaction.
+///
+/// Events are numbered from zero in append order. Each subscriber has a cursor, the
+/// sequence number of the next event it has to process, persisted across upgrades.
+/// Compaction folds the oldest events into a snapshot of type `S` and drops them from
+/// the log, it never goes past an event a subscriber has not processed yet.
+///
+/// The log takes four partitions: `{name}_index` and `{name}_data` at `id` and `id + 1`
+/// for the events, `{name}_cur` at `id + 2` for the cursors and `{name}_meta` at `id + 3`
+/// for the snapshot.
+///
+/// # Example
+/// ```
+/// use b3_utils::memory::{events::EventLog, StableMemoryManager};
+/// use b3_utils::nonce::Nonce;
+///
+/// let mut manager = StableMemoryManager::init();
+///
+/// // Deposits, the snapshot is the running balance
+/// let mut deposits: EventLog<u64, u64> = EventLog::init(&mut manager, "deposits", 10).unwrap();
+///
+/// deposits.append(&10).unwrap();
+/// deposits.append(&20).unwrap();
+/// deposits.append(&30).unwrap();
+///
+/// deposits.subscribe("mailer", Nonce::zero());
+///
+/// let pending = deposits.poll("mailer", 2).unwrap();
+/// assert_eq!(pending, vec![(Nonce(0), 10), (Nonce(1), 20)]);
+///
+/// deposits.commit("mailer", Nonce(2)).unwrap();
+///
+/// // Only the events processed by every subscriber can be compacted
+/// let compacted = deposits.compact(Nonce(3), |balance, amount| *balance += amount).unwrap();
+/// assert_eq!(compacted, 2);
+///
+/// assert_eq!(deposits.snapshot(), 30);
+/// assert_eq!(deposits.first_seq(), Nonce(2));
+/// assert_eq!(deposits.replay(|balance, amount| *balance += amount), 60);
+/// ```
+pub struct EventLog<E: Storable, S: Storable + Default + Clone> {
+    name: String,
+    log: DefaultStableLog<E>,
+    index_memory: DefaultVM,
+    data_memory: DefaultVM,
+    cursors: DefaultStableBTreeMap<String, Nonce>,
+    meta: DefaultStableCell<EventLogMeta<S>>,
+}
+
+impl<E: Storable, S: Storable + Default + Clone> EventLog<E, S> {
+    pub fn init(
+        manager: &mut StableMemoryManager,
+        name: &str,
+        id: u8,
+    ) -> Result<Self, StableMemoryError> {
+        let log = manager.init_memory(name, id)?;
+        let cursors = manager.init_memory(&format!("{}_cur", name), id + 2)?;
+        let meta = manager.init_memory(&format!("{}_meta", name), id + 3)?;
+
+        let index_memory = manager
+            .memory(&format!("{}_index", name))
+            .ok_or(StableMemoryError::UnableToCreateMemory(name.to_string()))?;
+        let data_memory = manager
+            .memory(&format!("{}_data", name))
+            .ok_or(StableMemoryError::UnableToCreateMemory(name.to_string()))?;
+
+        Ok(Self {
+            name: name.to_string(),
+            log,
+            index_memory,
+            data_memory,
+            cursors,
+            meta,
+        })
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the sequence number of the oldest event still in the log.
+    pub fn first_seq(&self) -> Nonce {
+        self.meta.get().first
+    }
+
+    /// Returns the sequence number the next appended event will get.
+    pub fn next_seq(&self) -> Nonce {
+        self.first_seq().add_64(self.log.len())
+    }
+
+    /// Returns the number of events in the log, compacted events excluded.
+    pub fn len(&self) -> u64 {
+        self.log.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.log.is_empty()
+    }
+
+    /// Appends an event and returns its sequence number.
+    pub fn append(&mut self, event: &E) -> Result<Nonce, StableMemoryError> {
+        let seq = self.next_seq();
+
+        self.log
+            .append(event)
+            .map_err(|e| StableMemoryError::UnableToCreateMemory(format!("{:?}", e)))?;
+
+        Ok(seq)
+    }
+
+    /// Returns an event, `None` if it was compacted or not appended yet.
+    pub fn get(&self, seq: Nonce) -> Option<E> {
+        let first = self.first_seq();
+
+        if seq < first {
+            return None;
+        }
+
+        self.log.get(seq.get() - first.get())
+    }
+
+    /// Returns the events whose sequence number falls in `range`, compacted events excluded.
+    pub fn range(&self, range: impl RangeBounds<Nonce>) -> Vec<(Nonce, E)> {
+        let start = match range.start_bound() {
+            RangeBound::Included(seq) => seq.get(),
+            RangeBound::Excluded(seq) => seq.get().saturating_add(1),
+            RangeBound::Unbounded => 0,
+        };
+        let end = match range.end_bound() {
+            RangeBound::Included(seq) => seq.get().saturating_add(1),
+            RangeBound::Excluded(seq) => seq.get(),
+            RangeBound::Unbounded => u64::MAX,
+        };
+
+        let first = self.first_seq().get();
+        let start = start.max(first);
+        let end = end.min(self.next_seq().get());
+
+        (start..end)
+            .filter_map(|seq| Some((Nonce(seq), self.log.get(seq - first)?)))
+            .collect()
+    }
+
+    /// Returns at most `limit` events starting at `from`.
+    pub fn read(&self, from: Nonce, limit: u64) -> Vec<(Nonce, E)> {
+        self.range(from..from.add_64(limit))
+    }
+
+    pub fn snapshot(&self) -> S {
+        self.meta.get().snapshot.clone()
+    }
+
+    /// Returns the current state: the snapshot with every event of the log applied.
+    pub fn replay<F>(&self, apply: F) -> S
+    where
+        F: Fn(&mut S, &E),
+    {
+        let mut state = self.snapshot();
+
+        for event in self.log.iter() {
+            apply(&mut state, &event);
+        }
+
+        state
+    }
+
+    /// Registers a subscriber, starting at `from` or at the oldest event still in the log.
+    /// Subscribing again keeps the existing cursor, which is returned.
+    pub fn subscribe(&mut self, subscriber: &str, from: Nonce) -> Nonce {
+        if let Some(cursor) = self.cursors.get(&subscriber.to_string()) {
+            return cursor;
+        }
+
+        let cursor = from.max(self.first_seq()).min(self.next_seq());
+
+        self.cursors.insert(subscriber.to_string(), cursor);
+
+        cursor
+    }
+
+    pub fn unsubscribe(&mut self, subscriber: &str) -> Option<Nonce> {
+        self.cursors.remove(&subscriber.to_string())
+    }
+
+    /// Returns the sequence number of the next event `subscriber` has to process.
+    pub fn cursor(&self, subscriber: &str) -> Option<Nonce> {
+        self.cursors.get(&subscriber.to_string())
+    }
+
+    pub fn subscribers(&self) -> Vec<(String, Nonce)> {
+        self.cursors.iter().collect()
+    }
+
+    /// Returns at most `limit` events from the cursor of `subscriber`, without moving it.
+    pub fn poll(&self, subscriber: &str, limit: u64) -> Result<Vec<(Nonce, E)>, StableMemoryError> {
+        let cursor = self.subscriber_cursor(subscriber)?;
+
+        Ok(self.read(cursor, limit))
+    }
+
+    /// Moves the cursor of `subscriber` to `next`, every event before it is processed.
+    pub fn commit(&mut self, subscriber: &str, next: Nonce) -> Result<(), StableMemoryError> {
+        let cursor = self.subscriber_cursor(subscriber)?;
+
+        if next < cursor || next > self.next_seq() {
+            return Err(StableMemoryError::CursorOutOfRange(next.get()));
+        }
+
+        self.cursors.insert(subscriber.to_string(), next);
+
+        Ok(())
+    }
+
+    /// Returns the sequence number compaction can go up to: the lowest cursor, or the
+    /// end of the log if there is no subscriber.
+    pub fn compactable(&self) -> Nonce {
+        self.cursors
+            .iter()
+            .map(|(_, cursor)| cursor)
+            .min()
+            .unwrap_or(self.next_seq())
+    }
+
+    /// Folds the events before `up_to` into the snapshot and drops them from the log,
+    /// returns the number of compacted events.
+    ///
+    /// `up_to` is capped by [`compactable`](Self::compactable). The whole log is loaded
+    /// into the heap and the remaining events are rewritten at the start of it, so the
+    /// cost grows with the size of the log. The stable memory of the log is never freed.
+    ///
+    /// # Panics
+    /// If the log can't be rewritten, trapping rolls back the message so no event is lost.
+    pub fn compact<F>(&mut self, up_to: Nonce, apply: F) -> Result<u64, StableMemoryError>
+    where
+        F: Fn(&mut S, &E),
+    {
+        let first = self.first_seq();
+        let up_to = up_to.min(self.compactable());
+
+        if up_to <= first {
+            return Ok(0);
+        }
+
+        let count = up_to.get() - first.get();
+
+        let mut meta = self.meta.get().clone();
+
+        let mut remaining = Vec::new();
+        for (position, event) in self.log.iter().enumerate() {
+            if (position as u64) < count {
+                apply(&mut meta.snapshot, &event);
+            } else {
+                remaining.push(event);
+            }
+        }
+
+        meta.first = up_to;
+
+        self.log = StableLog::new(self.index_memory.clone(), self.data_memory.clone());
+
+        for event in remaining.iter() {
+            self.log.append(event).expect("Unable to rewrite event log");
+        }
+
+        self.meta.set(meta).expect("Unable to set event log meta");
+
+        Ok(count)
+    }
+
+    fn subscriber_cursor(&self, subscriber: &str) -> Result<Nonce, StableMemoryError> {
+        self.cursor(subscriber)
+            .ok_or(StableMemoryError::SubscriberNotFound(
+                subscriber.to_string(),
+            ))
+    }
+}
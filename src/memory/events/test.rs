@@ -0,0 +1,171 @@
+#[cfg(test)]
+mod tests {
+    use ic_stable_structures::Storable;
+
+    use crate::{
+        memory::{
+            error::StableMemoryError,
+            events::{EventLog, EventLogMeta},
+            StableMemoryManager,
+        },
+        nonce::Nonce,
+    };
+
+    fn sum(total: &mut u64, event: &u64) {
+        *total += event;
+    }
+
+    fn init_log(manager: &mut StableMemoryManager) -> EventLog<u64, u64> {
+        EventLog::init(manager, "events", 10).unwrap()
+    }
+
+    #[test]
+    fn test_event_log_meta_to_and_from_bytes() {
+        let meta = EventLogMeta {
+            first: Nonce(5),
+            snapshot: 42u64,
+        };
+
+        assert_eq!(EventLogMeta::<u64>::from_bytes(meta.to_bytes()), meta);
+    }
+
+    #[test]
+    fn test_event_log_append_and_range() {
+        let mut manager = StableMemoryManager::init();
+
+        let mut log = init_log(&mut manager);
+
+        assert_eq!(manager.partition("events_index"), Some(10));
+        assert_eq!(manager.partition("events_data"), Some(11));
+        assert_eq!(manager.partition("events_cur"), Some(12));
+        assert_eq!(manager.partition("events_meta"), Some(13));
+
+        for event in 0..5 {
+            assert_eq!(log.append(&(event * 10)).unwrap(), Nonce(event));
+        }
+
+        assert_eq!(log.next_seq(), Nonce(5));
+        assert_eq!(log.get(Nonce(2)), Some(20));
+        assert_eq!(log.get(Nonce(5)), None);
+
+        assert_eq!(
+            log.range(Nonce(1)..=Nonce(2)),
+            vec![(Nonce(1), 10), (Nonce(2), 20)]
+        );
+        assert_eq!(log.range(Nonce(4)..).len(), 1);
+        assert_eq!(log.read(Nonce(3), 10), vec![(Nonce(3), 30), (Nonce(4), 40)]);
+    }
+
+    #[test]
+    fn test_event_log_cursors() {
+        let mut manager = StableMemoryManager::init();
+
+        let mut log = init_log(&mut manager);
+
+        for event in 0..5 {
+            log.append(&event).unwrap();
+        }
+
+        assert_eq!(log.subscribe("a", Nonce::zero()), Nonce(0));
+        assert_eq!(log.subscribe("b", Nonce(100)), Nonce(5));
+
+        // Subscribing again keeps the cursor
+        assert_eq!(log.subscribe("b", Nonce(0)), Nonce(5));
+
+        assert_eq!(
+            log.poll("a", 2).unwrap(),
+            vec![(Nonce(0), 0), (Nonce(1), 1)]
+        );
+        assert!(log.poll("b", 2).unwrap().is_empty());
+
+        log.commit("a", Nonce(2)).unwrap();
+
+        assert_eq!(log.cursor("a"), Some(Nonce(2)));
+        assert_eq!(log.poll("a", 1).unwrap(), vec![(Nonce(2), 2)]);
+
+        assert!(matches!(
+            log.commit("a", Nonce(1)),
+            Err(StableMemoryError::CursorOutOfRange(1))
+        ));
+        assert!(matches!(
+            log.commit("a", Nonce(6)),
+            Err(StableMemoryError::CursorOutOfRange(6))
+        ));
+        assert!(matches!(
+            log.poll("c", 1),
+            Err(StableMemoryError::SubscriberNotFound(_))
+        ));
+
+        assert_eq!(log.compactable(), Nonce(2));
+        assert_eq!(log.unsubscribe("a"), Some(Nonce(2)));
+        assert_eq!(log.compactable(), Nonce(5));
+    }
+
+    #[test]
+    fn test_event_log_compaction() {
+        let mut manager = StableMemoryManager::init();
+
+        let mut log = init_log(&mut manager);
+
+        for event in 1..=10 {
+            log.append(&event).unwrap();
+        }
+
+        log.subscribe("consumer", Nonce(0));
+        log.commit("consumer", Nonce(4)).unwrap();
+
+        // Capped by the consumer cursor
+        assert_eq!(log.compact(Nonce(8), sum).unwrap(), 4);
+        assert_eq!(log.first_seq(), Nonce(4));
+        assert_eq!(log.snapshot(), 1 + 2 + 3 + 4);
+        assert_eq!(log.len(), 6);
+
+        assert_eq!(log.get(Nonce(3)), None);
+        assert_eq!(log.get(Nonce(4)), Some(5));
+        assert_eq!(log.range(..Nonce(6)), vec![(Nonce(4), 5), (Nonce(5), 6)]);
+        assert_eq!(log.replay(sum), 55);
+
+        assert_eq!(log.compact(Nonce(8), sum).unwrap(), 0);
+
+        // Sequence numbers keep growing after compaction
+        assert_eq!(log.append(&11).unwrap(), Nonce(10));
+        assert_eq!(log.get(Nonce(10)), Some(11));
+
+        log.commit("consumer", Nonce(11)).unwrap();
+
+        assert_eq!(log.compact(Nonce(11), sum).unwrap(), 7);
+        assert!(log.is_empty());
+        assert_eq!(log.snapshot(), 66);
+        assert_eq!(log.next_seq(), Nonce(11));
+
+        // New subscribers can't start before the log
+        assert_eq!(log.subscribe("late", Nonce(0)), Nonce(11));
+    }
+
+    #[test]
+    fn test_event_log_survives_upgrade() {
+        let mut manager = StableMemoryManager::init();
+
+        {
+            let mut log = init_log(&mut manager);
+
+            for event in 1..=4 {
+                log.append(&event).unwrap();
+            }
+
+            log.subscribe("consumer", Nonce(0));
+            log.commit("consumer", Nonce(2)).unwrap();
+            log.compact(Nonce(2), sum).unwrap();
+        }
+
+        let mut manager = manager.simulate_upgrade();
+
+        let log = init_log(&mut manager);
+
+        assert_eq!(log.first_seq(), Nonce(2));
+        assert_eq!(log.next_seq(), Nonce(4));
+        assert_eq!(log.snapshot(), 3);
+        assert_eq!(log.cursor("consumer"), Some(Nonce(2)));
+        assert_eq!(log.replay(sum), 10);
+    }
+}
@@ -0,0 +1,513 @@
+use candid::Principal;
+use ic_stable_structures::{storable::Bound, Storable};
+use std::{borrow::Cow, ops::Bound as RangeBound, ops::RangeBounds};
+
+use super::{error::StableMemoryError, types::DefaultStableBTreeMap, StableMemoryManager};
+use crate::{nonce::Nonce, principal::StoredPrincipal, NanoTimeStamp};
+
+mod test;
+
+/// A value that can be used as a secondary index key.
+///
+/// The bytes must sort in the same order as the values, so that range queries
+/// over the index follow the ordering of the values.
+pub trait IndexValue {
+    fn index_bytes(&self) -> Vec<u8>;
+}
+
+macro_rules! impl_unsigned_index_value {
+    ($($t:ty),*) => {
+        $(
+            impl IndexValue for $t {
+                fn index_bytes(&self) -> Vec<u8> {
+                    self.to_be_bytes().to_vec()
+                }
+            }
+        )*
+    };
+}
+
+macro_rules! impl_signed_index_value {
+    ($($t:ty => $u:ty),*) => {
+        $(
+            impl IndexValue for $t {
+                fn index_bytes(&self) -> Vec<u8> {
+                    // Flipping the sign bit makes negative values sort first.
+                    ((*self as $u) ^ (1 << (<$u>::BITS - 1))).to_be_bytes().to_vec()
+                }
+            }
+        )*
+    };
+}
+
+impl_unsigned_index_value!(u8, u16, u32, u64, u128);
+impl_signed_index_value!(i8 => u8, i16 => u16, i32 => u32, i64 => u64, i128 => u128);
+
+impl IndexValue for bool {
+    fn index_bytes(&self) -> Vec<u8> {
+        vec![*self as u8]
+    }
+}
+
+impl IndexValue for str {
+    fn index_bytes(&self) -> Vec<u8> {
+        self.as_bytes().to_vec()
+    }
+}
+
+impl IndexValue for String {
+    fn index_bytes(&self) -> Vec<u8> {
+        self.as_bytes().to_vec()
+    }
+}
+
+impl IndexValue for Vec<u8> {
+    fn index_bytes(&self) -> Vec<u8> {
+        self.clone()
+    }
+}
+
+impl IndexValue for Principal {
+    fn index_bytes(&self) -> Vec<u8> {
+        self.as_slice().to_vec()
+    }
+}
+
+impl IndexValue for StoredPrincipal {
+    fn index_bytes(&self) -> Vec<u8> {
+        self.as_slice().to_vec()
+    }
+}
+
+impl IndexValue for NanoTimeStamp {
+    fn index_bytes(&self) -> Vec<u8> {
+        self.0.index_bytes()
+    }
+}
+
+impl IndexValue for Nonce {
+    fn index_bytes(&self) -> Vec<u8> {
+        self.get().index_bytes()
+    }
+}
+
+impl<T: IndexValue> IndexValue for Option<T> {
+    fn index_bytes(&self) -> Vec<u8> {
+        match self {
+            None => vec![0],
+            Some(value) => {
+                let mut bytes = vec![1];
+                bytes.extend(value.index_bytes());
+                bytes
+            }
+        }
+    }
+}
+
+impl<T: IndexValue + ?Sized> IndexValue for &T {
+    fn index_bytes(&self) -> Vec<u8> {
+        (*self).index_bytes()
+    }
+}
+
+/// Entry of a secondary index: the indexed value followed by the primary key.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct IndexEntry {
+    pub value: Vec<u8>,
+    pub key: Vec<u8>,
+}
+
+impl Storable for IndexEntry {
+    fn to_bytes(&self) -> Cow<'_, [u8]> {
+        let mut bytes = Vec::with_capacity(4 + self.value.len() + self.key.len());
+        bytes.extend_from_slice(&(self.value.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(&self.value);
+        bytes.extend_from_slice(&self.key);
+        bytes.into()
+    }
+
+    fn from_bytes(bytes: Cow<'_, [u8]>) -> Self {
+        let value_len = u32::from_le_bytes(bytes[0..4].try_into().unwrap()) as usize;
+
+        Self {
+            value: bytes[4..4 + value_len].to_vec(),
+            key: bytes[4 + value_len..].to_vec(),
+        }
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
+
+type IndexExtractor<V> = Box<dyn Fn(&V) -> Vec<u8>>;
+
+struct SecondaryIndex<V> {
+    name: String,
+    entries: DefaultStableBTreeMap<IndexEntry, ()>,
+    extract: IndexExtractor<V>,
+}
+
+impl<V> SecondaryIndex<V> {
+    fn entry(&self, key: &[u8], value: &V) -> IndexEntry {
+        IndexEntry {
+            value: (self.extract)(value),
+            key: key.to_vec(),
+        }
+    }
+}
+
+/// A [`DefaultStableBTreeMap`] with secondary indexes kept up to date on every write.
+///
+/// Every index lives in its own partition named `{map}_{index}`, allocated through the
+/// [`StableMemoryManager`]. An index added over existing entries is built on creation.
+///
+/// # Example
+/// ```
+/// use b3_utils::memory::{indexed::IndexedMap, StableMemoryManager};
+///
+/// let mut manager = StableMemoryManager::init();
+///
+/// // Emails by user id, indexed by their domain and their length
+/// let mut emails: IndexedMap<u64, String> = IndexedMap::init(&mut manager, "emails", 10)
+///     .and_then(|map| {
+///         map.with_index(&mut manager, "domain", 11, |email: &String| {
+///             email.split('@').nth(1).unwrap_or_default().to_string()
+///         })
+///     })
+///     .and_then(|map| map.with_index(&mut manager, "length", 12, |email: &String| email.len() as u64))
+///     .unwrap();
+///
+/// emails.insert(1, "alice@example.com".to_string());
+/// emails.insert(2, "bob@example.com".to_string());
+/// emails.insert(3, "carol@other.com".to_string());
+///
+/// let example = emails.get_by("domain", "example.com").unwrap();
+/// assert_eq!(example.len(), 2);
+///
+/// let short = emails.range_by("length", ..16u64).unwrap();
+/// assert_eq!(short.len(), 2);
+///
+/// let longest = emails.range_by("length", 16u64..).unwrap();
+/// assert_eq!(longest, vec![(1, "alice@example.com".to_string())]);
+/// ```
+pub struct IndexedMap<K: Storable + Ord + Clone, V: Storable> {
+    name: String,
+    map: DefaultStableBTreeMap<K, V>,
+    indexes: Vec<SecondaryIndex<V>>,
+}
+
+impl<K: Storable + Ord + Clone, V: Storable> IndexedMap<K, V> {
+    pub fn init(
+        manager: &mut StableMemoryManager,
+        name: &str,
+        id: u8,
+    ) -> Result<Self, StableMemoryError> {
+        let map = manager.init_memory(name, id)?;
+
+        Ok(Self {
+            name: name.to_string(),
+            map,
+            indexes: Vec::new(),
+        })
+    }
+
+    /// Adds a secondary index on the value returned by `extract`.
+    pub fn with_index<I, F>(
+        mut self,
+        manager: &mut StableMemoryManager,
+        index: &str,
+        id: u8,
+        extract: F,
+    ) -> Result<Self, StableMemoryError>
+    where
+        I: IndexValue,
+        F: Fn(&V) -> I + 'static,
+    {
+        if self.indexes.iter().any(|i| i.name == index) {
+            return Err(StableMemoryError::PartitionExists);
+        }
+
+        let entries = manager.init_memory(&format!("{}_{}", self.name, index), id)?;
+
+        self.indexes.push(SecondaryIndex {
+            name: index.to_string(),
+            entries,
+            extract: Box::new(move |value| extract(value).index_bytes()),
+        });
+
+        let position = self.indexes.len() - 1;
+
+        if self.indexes[position].entries.is_empty() && !self.map.is_empty() {
+            self.rebuild(position);
+        }
+
+        Ok(self)
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn index_names(&self) -> Vec<String> {
+        self.indexes.iter().map(|i| i.name.clone()).collect()
+    }
+
+    pub fn map(&self) -> &DefaultStableBTreeMap<K, V> {
+        &self.map
+    }
+
+    pub fn get(&self, key: &K) -> Option<V> {
+        self.map.get(key)
+    }
+
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.map.contains_key(key)
+    }
+
+    pub fn len(&self) -> u64 {
+        self.map.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (K, V)> + '_ {
+        self.map.iter()
+    }
+
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        let key_bytes = key.to_bytes().into_owned();
+
+        for index in self.indexes.iter_mut() {
+            let entry = index.entry(&key_bytes, &value);
+            index.entries.insert(entry, ());
+        }
+
+        let old = self.map.insert(key, value);
+
+        if let Some(old) = &old {
+            self.unindex(&key_bytes, old);
+        }
+
+        old
+    }
+
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        let old = self.map.remove(key)?;
+
+        self.unindex(&key.to_bytes(), &old);
+
+        Some(old)
+    }
+
+    pub fn clear(&mut self) {
+        let keys: Vec<K> = self.map.iter().map(|(key, _)| key).collect();
+
+        for key in keys {
+            self.map.remove(&key);
+        }
+
+        for index in self.indexes.iter_mut() {
+            clear_entries(&mut index.entries);
+        }
+    }
+
+    /// Returns every entry whose indexed value equals `value`.
+    pub fn get_by<I: IndexValue + ?Sized>(
+        &self,
+        index: &str,
+        value: &I,
+    ) -> Result<Vec<(K, V)>, StableMemoryError> {
+        let value = value.index_bytes();
+
+        self.scan(
+            index,
+            RangeBound::Included(value.clone()),
+            RangeBound::Included(value),
+        )
+    }
+
+    /// Returns the first entry whose indexed value equals `value`, in primary key bytes order.
+    pub fn first_by<I: IndexValue + ?Sized>(
+        &self,
+        index: &str,
+        value: &I,
+    ) -> Result<Option<(K, V)>, StableMemoryError> {
+        let index = self.index(index)?;
+        let value = value.index_bytes();
+
+        let entry = index
+            .entries
+            .range(
+                IndexEntry {
+                    value: value.clone(),
+                    key: Vec::new(),
+                }..,
+            )
+            .next()
+            .filter(|(entry, _)| entry.value == value);
+
+        Ok(entry.and_then(|(entry, _)| self.lookup(&entry)))
+    }
+
+    /// Returns the entries whose indexed value falls in `range`, ordered by that value.
+    pub fn range_by<I: IndexValue>(
+        &self,
+        index: &str,
+        range: impl RangeBounds<I>,
+    ) -> Result<Vec<(K, V)>, StableMemoryError> {
+        let start = map_bound(range.start_bound());
+        let end = map_bound(range.end_bound());
+
+        self.scan(index, start, end)
+    }
+
+    /// Returns the number of entries whose indexed value equals `value`.
+    pub fn count_by<I: IndexValue + ?Sized>(
+        &self,
+        index: &str,
+        value: &I,
+    ) -> Result<u64, StableMemoryError> {
+        let index = self.index(index)?;
+        let value = value.index_bytes();
+
+        let count = index
+            .entries
+            .range(
+                IndexEntry {
+                    value: value.clone(),
+                    key: Vec::new(),
+                }..,
+            )
+            .take_while(|(entry, _)| entry.value == value)
+            .count();
+
+        Ok(count as u64)
+    }
+
+    /// Checks that every index holds exactly one entry per value of the map.
+    pub fn check_consistency(&self) -> Result<(), StableMemoryError> {
+        for index in self.indexes.iter() {
+            if index.entries.len() != self.map.len() {
+                return Err(StableMemoryError::IndexInconsistent(index.name.clone()));
+            }
+
+            for (key, value) in self.map.iter() {
+                let entry = index.entry(&key.to_bytes(), &value);
+
+                if !index.entries.contains_key(&entry) {
+                    return Err(StableMemoryError::IndexInconsistent(index.name.clone()));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Drops and rebuilds an index from the entries of the map.
+    pub fn rebuild_index(&mut self, index: &str) -> Result<(), StableMemoryError> {
+        let position = self
+            .indexes
+            .iter()
+            .position(|i| i.name == index)
+            .ok_or(StableMemoryError::IndexNotFound(index.to_string()))?;
+
+        self.rebuild(position);
+
+        Ok(())
+    }
+
+    fn rebuild(&mut self, position: usize) {
+        let index = &mut self.indexes[position];
+
+        clear_entries(&mut index.entries);
+
+        for (key, value) in self.map.iter() {
+            let entry = index.entry(&key.to_bytes(), &value);
+            index.entries.insert(entry, ());
+        }
+    }
+
+    fn index(&self, index: &str) -> Result<&SecondaryIndex<V>, StableMemoryError> {
+        self.indexes
+            .iter()
+            .find(|i| i.name == index)
+            .ok_or(StableMemoryError::IndexNotFound(index.to_string()))
+    }
+
+    fn unindex(&mut self, key: &[u8], value: &V) {
+        for index in self.indexes.iter_mut() {
+            let entry = index.entry(key, value);
+
+            // The new value may share the same indexed value.
+            let still_used = self
+                .map
+                .get(&K::from_bytes(Cow::Borrowed(key)))
+                .map(|current| index.entry(key, &current) == entry)
+                .unwrap_or(false);
+
+            if !still_used {
+                index.entries.remove(&entry);
+            }
+        }
+    }
+
+    fn lookup(&self, entry: &IndexEntry) -> Option<(K, V)> {
+        let key = K::from_bytes(Cow::Borrowed(&entry.key));
+
+        self.map.get(&key).map(|value| (key, value))
+    }
+
+    fn scan(
+        &self,
+        index: &str,
+        start: RangeBound<Vec<u8>>,
+        end: RangeBound<Vec<u8>>,
+    ) -> Result<Vec<(K, V)>, StableMemoryError> {
+        let index = self.index(index)?;
+
+        let from = match &start {
+            RangeBound::Included(value) | RangeBound::Excluded(value) => IndexEntry {
+                value: value.clone(),
+                key: Vec::new(),
+            },
+            RangeBound::Unbounded => IndexEntry {
+                value: Vec::new(),
+                key: Vec::new(),
+            },
+        };
+
+        let entries = index
+            .entries
+            .range(from..)
+            .skip_while(|(entry, _)| match &start {
+                RangeBound::Excluded(value) => &entry.value == value,
+                _ => false,
+            })
+            .take_while(|(entry, _)| match &end {
+                RangeBound::Included(value) => &entry.value <= value,
+                RangeBound::Excluded(value) => &entry.value < value,
+                RangeBound::Unbounded => true,
+            })
+            .filter_map(|(entry, _)| self.lookup(&entry))
+            .collect();
+
+        Ok(entries)
+    }
+}
+
+fn map_bound<I: IndexValue>(bound: RangeBound<&I>) -> RangeBound<Vec<u8>> {
+    match bound {
+        RangeBound::Included(value) => RangeBound::Included(value.index_bytes()),
+        RangeBound::Excluded(value) => RangeBound::Excluded(value.index_bytes()),
+        RangeBound::Unbounded => RangeBound::Unbounded,
+    }
+}
+
+fn clear_entries(entries: &mut DefaultStableBTreeMap<IndexEntry, ()>) {
+    let keys: Vec<IndexEntry> = entries.iter().map(|(entry, _)| entry).collect();
+
+    for key in keys {
+        entries.remove(&key);
+    }
+}
@@ -0,0 +1,250 @@
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+
+    use ic_stable_structures::{storable::Bound, Storable};
+
+    use crate::memory::{
+        error::StableMemoryError,
+        indexed::{IndexValue, IndexedMap},
+        StableMemoryManager,
+    };
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct User {
+        email: String,
+        created_at: u64,
+    }
+
+    impl Storable for User {
+        fn to_bytes(&self) -> Cow<'_, [u8]> {
+            let mut bytes = self.created_at.to_le_bytes().to_vec();
+            bytes.extend_from_slice(self.email.as_bytes());
+            bytes.into()
+        }
+
+        fn from_bytes(bytes: Cow<'_, [u8]>) -> Self {
+            Self {
+                created_at: u64::from_le_bytes(bytes[0..8].try_into().unwrap()),
+                email: String::from_utf8(bytes[8..].to_vec()).unwrap(),
+            }
+        }
+
+        const BOUND: Bound = Bound::Unbounded;
+    }
+
+    fn user(email: &str, created_at: u64) -> User {
+        User {
+            email: email.to_string(),
+            created_at,
+        }
+    }
+
+    fn init_users(manager: &mut StableMemoryManager) -> IndexedMap<u64, User> {
+        IndexedMap::init(manager, "users", 10)
+            .and_then(|map| map.with_index(manager, "email", 11, |user: &User| user.email.clone()))
+            .and_then(|map| {
+                map.with_index(manager, "created_at", 12, |user: &User| user.created_at)
+            })
+            .unwrap()
+    }
+
+    #[test]
+    fn test_indexed_map_insert_and_get_by() {
+        let mut manager = StableMemoryManager::init();
+
+        let mut users = init_users(&mut manager);
+
+        users.insert(1, user("alice@example.com", 100));
+        users.insert(2, user("bob@example.com", 200));
+        users.insert(3, user("carol@example.com", 200));
+
+        assert_eq!(users.len(), 3);
+        assert_eq!(
+            manager.partition("users_email"),
+            Some(11),
+            "index partition is allocated through the manager"
+        );
+
+        let bob = users.first_by("email", "bob@example.com").unwrap();
+        assert_eq!(bob, Some((2, user("bob@example.com", 200))));
+
+        let same_time = users.get_by("created_at", &200u64).unwrap();
+        assert_eq!(
+            same_time.iter().map(|(id, _)| *id).collect::<Vec<_>>(),
+            vec![2, 3]
+        );
+        assert_eq!(users.count_by("created_at", &200u64).unwrap(), 2);
+
+        assert!(users
+            .first_by("email", "dave@example.com")
+            .unwrap()
+            .is_none());
+
+        assert!(matches!(
+            users.get_by("name", "alice"),
+            Err(StableMemoryError::IndexNotFound(_))
+        ));
+
+        users.check_consistency().unwrap();
+    }
+
+    #[test]
+    fn test_indexed_map_update_and_remove() {
+        let mut manager = StableMemoryManager::init();
+
+        let mut users = init_users(&mut manager);
+
+        users.insert(1, user("alice@example.com", 100));
+        users.insert(2, user("bob@example.com", 200));
+
+        // Changing the email moves the index entry
+        let old = users.insert(1, user("alice@new.com", 100));
+
+        assert_eq!(old, Some(user("alice@example.com", 100)));
+        assert!(users
+            .first_by("email", "alice@example.com")
+            .unwrap()
+            .is_none());
+        assert_eq!(
+            users.first_by("email", "alice@new.com").unwrap().unwrap().0,
+            1
+        );
+
+        // Keeping the same created_at keeps its index entry
+        assert_eq!(users.count_by("created_at", &100u64).unwrap(), 1);
+
+        users.check_consistency().unwrap();
+
+        assert_eq!(users.remove(&2), Some(user("bob@example.com", 200)));
+        assert!(users.get_by("created_at", &200u64).unwrap().is_empty());
+        assert_eq!(users.remove(&2), None);
+
+        users.check_consistency().unwrap();
+
+        users.clear();
+
+        assert!(users.is_empty());
+        assert!(users.range_by::<u64>("created_at", ..).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_indexed_map_range_by() {
+        let mut manager = StableMemoryManager::init();
+
+        let mut users = init_users(&mut manager);
+
+        for (id, created_at) in [(1, 300), (2, 100), (3, 200), (4, 400), (5, 200)] {
+            users.insert(id, user(&format!("user{}@example.com", id), created_at));
+        }
+
+        let ids =
+            |entries: Vec<(u64, User)>| entries.into_iter().map(|(id, _)| id).collect::<Vec<_>>();
+
+        assert_eq!(
+            ids(users.range_by("created_at", 200u64..).unwrap()),
+            vec![3, 5, 1, 4]
+        );
+        assert_eq!(
+            ids(users.range_by("created_at", 200u64..400).unwrap()),
+            vec![3, 5, 1]
+        );
+        assert_eq!(
+            ids(users.range_by("created_at", ..=200u64).unwrap()),
+            vec![2, 3, 5]
+        );
+        assert_eq!(
+            ids(users.range_by::<u64>("created_at", ..).unwrap()),
+            vec![2, 3, 5, 1, 4]
+        );
+        assert_eq!(
+            ids(users
+                .range_by(
+                    "created_at",
+                    (
+                        std::ops::Bound::Excluded(200u64),
+                        std::ops::Bound::Unbounded
+                    )
+                )
+                .unwrap()),
+            vec![1, 4]
+        );
+    }
+
+    #[test]
+    fn test_indexed_map_rebuilds_new_index() {
+        let mut manager = StableMemoryManager::init();
+
+        let mut users: IndexedMap<u64, User> = IndexedMap::init(&mut manager, "users", 10).unwrap();
+
+        users.insert(1, user("alice@example.com", 100));
+        users.insert(2, user("bob@example.com", 200));
+
+        // Re-initializing with an index builds it from the existing entries
+        let users = init_users(&mut manager);
+
+        assert_eq!(users.len(), 2);
+        assert_eq!(users.index_names(), vec!["email", "created_at"]);
+        assert_eq!(
+            users
+                .first_by("email", "bob@example.com")
+                .unwrap()
+                .unwrap()
+                .0,
+            2
+        );
+
+        users.check_consistency().unwrap();
+
+        // An index registered twice is rejected
+        assert!(matches!(
+            init_users(&mut manager)
+                .with_index(&mut manager, "email", 13, |user: &User| user.created_at),
+            Err(StableMemoryError::PartitionExists)
+        ));
+    }
+
+    #[test]
+    fn test_indexed_map_inconsistent_index() {
+        let mut manager = StableMemoryManager::init();
+
+        let mut users = init_users(&mut manager);
+
+        users.insert(1, user("alice@example.com", 100));
+
+        // An index created with a different extractor on the same partition is inconsistent
+        let mut users: IndexedMap<u64, User> = IndexedMap::init(&mut manager, "users", 10)
+            .and_then(|map| {
+                map.with_index(&mut manager, "email", 11, |user: &User| user.created_at)
+            })
+            .unwrap();
+
+        assert!(matches!(
+            users.check_consistency(),
+            Err(StableMemoryError::IndexInconsistent(_))
+        ));
+
+        users.rebuild_index("email").unwrap();
+
+        users.check_consistency().unwrap();
+        assert_eq!(users.count_by("email", &100u64).unwrap(), 1);
+    }
+
+    #[test]
+    fn test_index_value_ordering() {
+        let mut values = [-5i64, 3, -1, 0, i64::MIN, i64::MAX];
+        let mut bytes: Vec<Vec<u8>> = values.iter().map(|v| v.index_bytes()).collect();
+
+        values.sort();
+        bytes.sort();
+
+        assert_eq!(
+            bytes,
+            values.iter().map(|v| v.index_bytes()).collect::<Vec<_>>()
+        );
+
+        assert!(None::<u64>.index_bytes() < Some(0u64).index_bytes());
+        assert!(1u32.index_bytes() < 256u32.index_bytes());
+        assert!("ab".index_bytes() < "abc".index_bytes());
+    }
+}
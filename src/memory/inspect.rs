@@ -0,0 +1,285 @@
+use ic_stable_structures::{
+    memory_manager::{MemoryId, MemoryManager, VirtualMemory},
+    FileMemory, Memory, StableBTreeMap, StableLog, Storable,
+};
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+use std::{
+    any::Any,
+    collections::BTreeMap,
+    fs::File,
+    panic::{catch_unwind, AssertUnwindSafe},
+    path::Path,
+};
+
+use super::StableMemoryManager;
+use super::{error::StableMemoryError, quota::WASM_PAGE_SIZE, types::PartitionDetail};
+
+mod test;
+
+const MANAGER_MAGIC: &[u8; 3] = b"MGR";
+const BTREE_MAGIC: &[u8; 3] = b"BTR";
+const LOG_INDEX_MAGIC: &[u8; 3] = b"GLI";
+const LOG_DATA_MAGIC: &[u8; 3] = b"GLD";
+
+const PARTITIONS_ID: u8 = 254;
+
+type Decoder<M> = Box<dyn Fn(&StableMemoryManager<M>) -> Result<Value, StableMemoryError>>;
+type BackupDecoder = Box<dyn Fn(&[u8]) -> Result<Value, StableMemoryError>>;
+
+/// Reads a stable memory dump offline, e.g. from a host binary.
+///
+/// The dump is never written to: structures are only loaded when their header is
+/// present, so an empty or foreign partition is reported instead of initialized.
+/// Partitions are decoded to JSON by the decoders registered for their name.
+///
+/// ```no_run
+/// use b3_utils::memory::inspect::Inspector;
+///
+/// let mut inspector = Inspector::open("stable_memory.bin").unwrap();
+///
+/// inspector
+///     .register_map::<u64, String>("users")
+///     .register_log::<u64>("events");
+///
+/// println!("{}", inspector.to_json());
+/// ```
+pub struct Inspector<M: Memory + Clone = FileMemory> {
+    manager: StableMemoryManager<M>,
+    decoders: BTreeMap<String, Decoder<M>>,
+    backup_decoder: Option<BackupDecoder>,
+}
+
+impl Inspector {
+    /// Opens a raw stable memory dump read-only.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, StableMemoryError> {
+        let file = File::open(path).map_err(|e| StableMemoryError::InvalidDump(e.to_string()))?;
+
+        let len = file
+            .metadata()
+            .map_err(|e| StableMemoryError::InvalidDump(e.to_string()))?
+            .len();
+
+        if len % WASM_PAGE_SIZE != 0 {
+            return Err(StableMemoryError::InvalidDump(format!(
+                "size {} is not a multiple of the page size",
+                len
+            )));
+        }
+
+        Self::load(FileMemory::new(file))
+    }
+}
+
+impl<M: Memory + Clone> Inspector<M> {
+    /// Loads the partitions of a memory written by a `StableMemoryManager`.
+    pub fn load(memory: M) -> Result<Self, StableMemoryError> {
+        if !has_magic(&memory, MANAGER_MAGIC) {
+            return Err(StableMemoryError::InvalidDump(
+                "no memory manager found".to_string(),
+            ));
+        }
+
+        let partitions = MemoryManager::init(memory.clone()).get(MemoryId::new(PARTITIONS_ID));
+
+        if !has_magic(&partitions, BTREE_MAGIC) {
+            return Err(StableMemoryError::InvalidDump(
+                "no partitions found".to_string(),
+            ));
+        }
+
+        Ok(Self {
+            manager: StableMemoryManager::init_with(memory),
+            decoders: BTreeMap::new(),
+            backup_decoder: None,
+        })
+    }
+
+    pub fn manager(&self) -> &StableMemoryManager<M> {
+        &self.manager
+    }
+
+    /// Lists the partitions with their sizes.
+    pub fn partitions(&self) -> Vec<PartitionDetail> {
+        self.manager.partition_details()
+    }
+
+    /// Registers a decoder for a partition, it receives the manager to load the
+    /// partition from.
+    pub fn register<F>(&mut self, name: &str, decoder: F) -> &mut Self
+    where
+        F: Fn(&StableMemoryManager<M>) -> Result<Value, StableMemoryError> + 'static,
+    {
+        self.decoders.insert(name.to_string(), Box::new(decoder));
+        self
+    }
+
+    /// Decodes a `StableBTreeMap` partition to an array of `{ "key", "value" }` objects.
+    pub fn register_map<K, V>(&mut self, name: &str) -> &mut Self
+    where
+        K: Storable + Ord + Clone + Serialize + 'static,
+        V: Storable + Serialize + 'static,
+    {
+        let partition = name.to_string();
+
+        self.register(name, move |manager| {
+            let memory = partition_memory(manager, &partition)?;
+
+            if !has_magic(&memory, BTREE_MAGIC) {
+                return Err(StableMemoryError::InvalidDump(format!(
+                    "partition {} is not a map",
+                    partition
+                )));
+            }
+
+            let map: StableBTreeMap<K, V, VirtualMemory<M>> = StableBTreeMap::load(memory);
+
+            map.iter()
+                .map(|(key, value)| {
+                    Ok(json!({ "key": to_value(&key)?, "value": to_value(&value)? }))
+                })
+                .collect::<Result<Vec<_>, _>>()
+                .map(Value::Array)
+        })
+    }
+
+    /// Decodes a `StableLog` registered as `name` (stored in `{name}_index` and
+    /// `{name}_data`) to an array of entries.
+    pub fn register_log<T>(&mut self, name: &str) -> &mut Self
+    where
+        T: Storable + Serialize + 'static,
+    {
+        let partition = name.to_string();
+
+        self.register(name, move |manager| {
+            let index = partition_memory(manager, &format!("{}_index", partition))?;
+            let data = partition_memory(manager, &format!("{}_data", partition))?;
+
+            if !has_magic(&index, LOG_INDEX_MAGIC) || !has_magic(&data, LOG_DATA_MAGIC) {
+                return Err(StableMemoryError::InvalidDump(format!(
+                    "partition {} is not a log",
+                    partition
+                )));
+            }
+
+            let log: StableLog<T, VirtualMemory<M>, VirtualMemory<M>> =
+                StableLog::init(index, data)
+                    .map_err(|e| StableMemoryError::InvalidDump(format!("{:?}", e)))?;
+
+            log.iter()
+                .map(|entry| to_value(&entry))
+                .collect::<Result<Vec<_>, _>>()
+                .map(Value::Array)
+        })
+    }
+
+    /// Registers the decoder of the bytes stored in the backup partition.
+    pub fn register_backup<F>(&mut self, decoder: F) -> &mut Self
+    where
+        F: Fn(&[u8]) -> Result<Value, StableMemoryError> + 'static,
+    {
+        self.backup_decoder = Some(Box::new(decoder));
+        self
+    }
+
+    /// Decodes a partition with its registered decoder.
+    ///
+    /// `Storable::from_bytes` panics on bytes of another type, e.g. a map registered
+    /// with the wrong key or value type, the panic is returned as an error.
+    pub fn decode(&self, name: &str) -> Result<Value, StableMemoryError> {
+        let decoder = self
+            .decoders
+            .get(name)
+            .ok_or(StableMemoryError::DecoderNotFound(name.to_string()))?;
+
+        catch_decode_panic(|| decoder(&self.manager))
+    }
+
+    /// Decodes the backup partition, as a byte array if no decoder is registered.
+    pub fn decode_backup(&self) -> Result<Value, StableMemoryError> {
+        let backup = self.manager.backup();
+
+        if backup.len() == 0 {
+            return Ok(Value::Null);
+        }
+
+        let bytes = backup.get_backup();
+
+        match &self.backup_decoder {
+            Some(decoder) => catch_decode_panic(|| decoder(&bytes)),
+            None => to_value(&bytes),
+        }
+    }
+
+    /// Returns the partitions, the decoded partitions and the backup.
+    ///
+    /// A partition that fails to decode, or whose decoder panics, is reported as
+    /// `{ "error": ... }`.
+    pub fn to_json(&self) -> Value {
+        let mut data = Map::new();
+
+        for name in self.decoders.keys() {
+            let value = self
+                .decode(name)
+                .unwrap_or_else(|e| json!({ "error": e.to_string() }));
+
+            data.insert(name.clone(), value);
+        }
+
+        let backup = self
+            .decode_backup()
+            .unwrap_or_else(|e| json!({ "error": e.to_string() }));
+
+        json!({
+            "partitions": self.partitions(),
+            "data": data,
+            "backup": backup,
+        })
+    }
+}
+
+fn has_magic<M: Memory>(memory: &M, magic: &[u8; 3]) -> bool {
+    if memory.size() == 0 {
+        return false;
+    }
+
+    let mut bytes = [0u8; 3];
+    memory.read(0, &mut bytes);
+
+    &bytes == magic
+}
+
+fn partition_memory<M: Memory>(
+    manager: &StableMemoryManager<M>,
+    name: &str,
+) -> Result<VirtualMemory<M>, StableMemoryError> {
+    manager
+        .memory(name)
+        .ok_or(StableMemoryError::PartitionNotFound(name.to_string()))
+}
+
+fn catch_decode_panic<F>(decode: F) -> Result<Value, StableMemoryError>
+where
+    F: FnOnce() -> Result<Value, StableMemoryError>,
+{
+    catch_unwind(AssertUnwindSafe(decode)).unwrap_or_else(|panic| {
+        Err(StableMemoryError::InvalidDump(format!(
+            "decoder panicked: {}",
+            panic_message(panic.as_ref())
+        )))
+    })
+}
+
+fn panic_message(panic: &(dyn Any + Send)) -> &str {
+    if let Some(message) = panic.downcast_ref::<&str>() {
+        message
+    } else if let Some(message) = panic.downcast_ref::<String>() {
+        message
+    } else {
+        "unknown error"
+    }
+}
+
+fn to_value<T: Serialize>(value: &T) -> Result<Value, StableMemoryError> {
+    serde_json::to_value(value).map_err(|e| StableMemoryError::InvalidDump(e.to_string()))
+}
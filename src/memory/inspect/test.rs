@@ -0,0 +1,136 @@
+#[cfg(test)]
+mod tests {
+    use ic_stable_structures::{
+        memory_manager::VirtualMemory, FileMemory, Memory, StableBTreeMap, StableLog, VectorMemory,
+    };
+    use serde_json::json;
+
+    use crate::memory::{error::StableMemoryError, inspect::Inspector, StableMemoryManager};
+
+    type Map<M> = StableBTreeMap<u64, String, VirtualMemory<M>>;
+    type Log<M> = StableLog<u64, VirtualMemory<M>, VirtualMemory<M>>;
+
+    fn write_state<M: Memory + Clone>(memory: M) {
+        let mut manager = StableMemoryManager::init_with(memory);
+
+        let mut users: Map<M> = manager.init_memory("users", 1).unwrap();
+        users.insert(1, "alice".to_string());
+        users.insert(2, "bob".to_string());
+
+        let events: Log<M> = manager.init_memory("events", 2).unwrap();
+        events.append(&10).unwrap();
+        events.append(&20).unwrap();
+
+        let _: Map<M> = manager.init_memory("unused", 10).unwrap();
+
+        manager.backup_mut().set_backup(vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn test_inspect_file_dump() {
+        let path =
+            std::env::temp_dir().join(format!("b3_utils_test_inspect_{}.bin", std::process::id()));
+
+        let file = std::fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(&path)
+            .unwrap();
+
+        write_state(FileMemory::new(file));
+
+        let len = std::fs::metadata(&path).unwrap().len();
+
+        let mut inspector = Inspector::open(&path).unwrap();
+
+        let names: Vec<String> = inspector.partitions().into_iter().map(|p| p.name).collect();
+
+        assert_eq!(
+            names,
+            vec!["events_data", "events_index", "unused", "users"]
+        );
+
+        inspector
+            .register_map::<u64, String>("users")
+            .register_log::<u64>("events");
+
+        assert_eq!(
+            inspector.decode("users").unwrap(),
+            json!([{ "key": 1, "value": "alice" }, { "key": 2, "value": "bob" }])
+        );
+        assert_eq!(inspector.decode("events").unwrap(), json!([10, 20]));
+        assert_eq!(inspector.decode_backup().unwrap(), json!([1, 2, 3]));
+
+        assert!(matches!(
+            inspector.decode("unused"),
+            Err(StableMemoryError::DecoderNotFound(_))
+        ));
+
+        // Inspecting never writes to the dump
+        assert_eq!(std::fs::metadata(&path).unwrap().len(), len);
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_inspect_to_json() {
+        let memory = VectorMemory::default();
+
+        write_state(memory.clone());
+
+        let mut inspector = Inspector::load(memory).unwrap();
+
+        inspector
+            .register_map::<u64, String>("users")
+            .register_map::<u64, u64>("events_index")
+            .register_map::<u64, String>("missing")
+            .register_backup(|bytes| Ok(json!(bytes.len())));
+
+        let dump = inspector.to_json();
+
+        assert_eq!(dump["partitions"].as_array().unwrap().len(), 4);
+        assert_eq!(dump["data"]["users"][1]["value"], "bob");
+        assert_eq!(dump["backup"], 3);
+
+        // Wrong decoders are reported in place of the data
+        assert!(dump["data"]["events_index"]["error"]
+            .as_str()
+            .unwrap()
+            .contains("not a map"));
+        assert!(dump["data"]["missing"]["error"]
+            .as_str()
+            .unwrap()
+            .contains("not found"));
+    }
+
+    #[test]
+    fn test_inspect_wrong_types() {
+        let memory = VectorMemory::default();
+
+        write_state(memory.clone());
+
+        let mut inspector = Inspector::load(memory).unwrap();
+
+        inspector.register_map::<u64, u64>("users");
+
+        let error = inspector.decode("users").unwrap_err();
+
+        assert!(error.to_string().contains("decoder panicked"));
+        assert!(inspector.to_json()["data"]["users"]["error"].is_string());
+    }
+
+    #[test]
+    fn test_inspect_invalid_dump() {
+        assert!(matches!(
+            Inspector::load(VectorMemory::default()),
+            Err(StableMemoryError::InvalidDump(_))
+        ));
+
+        assert!(matches!(
+            Inspector::open("/nonexistent/stable_memory.bin"),
+            Err(StableMemoryError::InvalidDump(_))
+        ));
+    }
+}
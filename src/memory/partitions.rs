@@ -1,15 +1,17 @@
 mod name;
 
-use ic_stable_structures::btreemap::Iter;
+use ic_stable_structures::{
+    btreemap::Iter, memory_manager::VirtualMemory, DefaultMemoryImpl, Memory, StableBTreeMap,
+};
 pub use name::*;
 
-use super::types::{DefaultStableBTreeMap, DefaultVM};
+pub struct Partitions<M: Memory = DefaultMemoryImpl>(
+    StableBTreeMap<PartitionName, u8, VirtualMemory<M>>,
+);
 
-pub struct Partitions(DefaultStableBTreeMap<PartitionName, u8>);
-
-impl Partitions {
-    pub fn init(default_vm: DefaultVM) -> Self {
-        Self(DefaultStableBTreeMap::init(default_vm))
+impl<M: Memory> Partitions<M> {
+    pub fn init(default_vm: VirtualMemory<M>) -> Self {
+        Self(StableBTreeMap::init(default_vm))
     }
 
     pub fn get(&self, name: &PartitionName) -> Option<u8> {
@@ -20,7 +22,7 @@ impl Partitions {
         self.0.insert(name, id)
     }
 
-    pub fn iter(&self) -> Iter<PartitionName, u8, DefaultVM> {
+    pub fn iter(&self) -> Iter<PartitionName, u8, VirtualMemory<M>> {
         self.0.iter()
     }
 
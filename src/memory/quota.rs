@@ -0,0 +1,235 @@
+use candid::CandidType;
+use ic_stable_structures::{
+    memory_manager::VirtualMemory, DefaultMemoryImpl, Memory, StableBTreeMap, StableLog, StableVec,
+    Storable,
+};
+use serde::{Deserialize, Serialize};
+use std::{cell::Cell, ops::Deref, rc::Rc};
+
+use super::{error::StableMemoryError, ttl::TtlMap, NonceMap};
+use crate::{nonce::Nonce, NanoTimeStamp};
+
+mod test;
+
+pub const WASM_PAGE_SIZE: u64 = 65536;
+
+/// Byte limits of a partition.
+///
+/// Usage is measured in allocated pages, so a write can still grow a partition
+/// past its hard limit by the pages it needs, the following writes are rejected.
+#[derive(CandidType, Deserialize, Serialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PartitionQuota {
+    /// Usage above this limit is reported but writes still succeed.
+    pub soft: Option<u64>,
+    /// Writes fail with `StableMemoryError::QuotaExceeded` once usage reaches this limit.
+    pub hard: Option<u64>,
+}
+
+#[derive(CandidType, Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum QuotaStatus {
+    Within,
+    SoftExceeded,
+    HardExceeded,
+}
+
+impl PartitionQuota {
+    pub fn new(soft: Option<u64>, hard: Option<u64>) -> Self {
+        Self { soft, hard }
+    }
+
+    pub fn soft(mut self, bytes: u64) -> Self {
+        self.soft = Some(bytes);
+        self
+    }
+
+    pub fn hard(mut self, bytes: u64) -> Self {
+        self.hard = Some(bytes);
+        self
+    }
+
+    pub fn status(&self, used: u64) -> QuotaStatus {
+        match (self.soft, self.hard) {
+            (_, Some(hard)) if used >= hard => QuotaStatus::HardExceeded,
+            (Some(soft), _) if used >= soft => QuotaStatus::SoftExceeded,
+            _ => QuotaStatus::Within,
+        }
+    }
+
+    /// Returns an error if `used` reached the hard limit.
+    pub fn check(&self, name: &str, used: u64) -> Result<QuotaStatus, StableMemoryError> {
+        match self.status(used) {
+            QuotaStatus::HardExceeded => Err(StableMemoryError::QuotaExceeded {
+                name: name.to_string(),
+                used,
+                limit: self.hard.unwrap_or_default(),
+            }),
+            status => Ok(status),
+        }
+    }
+}
+
+/// Returns the bytes allocated by a partition, whole pages whether they are used or not.
+pub fn memory_bytes<M: Memory>(memory: &M) -> u64 {
+    memory.size() * WASM_PAGE_SIZE
+}
+
+/// A stable collection whose writes are checked against a [`PartitionQuota`].
+///
+/// Reads go through `Deref`, writes through the methods of this type.
+/// Created by [`StableMemoryManager::init_memory_with_quota`](super::StableMemoryManager::init_memory_with_quota),
+/// the quota is shared with the manager.
+pub struct QuotaGuard<T, M: Memory = DefaultMemoryImpl> {
+    inner: T,
+    name: String,
+    memories: Vec<VirtualMemory<M>>,
+    quota: Rc<Cell<PartitionQuota>>,
+}
+
+impl<T, M: Memory> QuotaGuard<T, M> {
+    pub(crate) fn new(
+        inner: T,
+        name: &str,
+        memories: Vec<VirtualMemory<M>>,
+        quota: Rc<Cell<PartitionQuota>>,
+    ) -> Self {
+        Self {
+            inner,
+            name: name.to_string(),
+            memories,
+            quota,
+        }
+    }
+
+    pub fn quota(&self) -> PartitionQuota {
+        self.quota.get()
+    }
+
+    /// Changes the quota of the partition, for the manager as well.
+    pub fn set_quota(&mut self, quota: PartitionQuota) {
+        self.quota.set(quota);
+    }
+
+    /// Returns the bytes allocated by the partitions of the collection.
+    pub fn used_bytes(&self) -> u64 {
+        self.memories.iter().map(memory_bytes).sum()
+    }
+
+    pub fn status(&self) -> QuotaStatus {
+        self.quota().status(self.used_bytes())
+    }
+
+    pub fn check(&self) -> Result<QuotaStatus, StableMemoryError> {
+        self.quota().check(&self.name, self.used_bytes())
+    }
+
+    pub fn inner(&self) -> &T {
+        &self.inner
+    }
+
+    /// Gives unchecked access to the collection, e.g. to remove entries.
+    pub fn inner_mut(&mut self) -> &mut T {
+        &mut self.inner
+    }
+
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+}
+
+impl<T, M: Memory> Deref for QuotaGuard<T, M> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.inner
+    }
+}
+
+impl<K: Storable + Ord + Clone, V: Storable, M: Memory>
+    QuotaGuard<StableBTreeMap<K, V, VirtualMemory<M>>, M>
+{
+    pub fn insert(&mut self, key: K, value: V) -> Result<Option<V>, StableMemoryError> {
+        self.check()?;
+
+        Ok(self.inner.insert(key, value))
+    }
+
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        self.inner.remove(key)
+    }
+}
+
+impl<T: Storable, M: Memory> QuotaGuard<StableVec<T, VirtualMemory<M>>, M> {
+    pub fn push(&mut self, item: &T) -> Result<(), StableMemoryError> {
+        self.check()?;
+
+        self.inner
+            .push(item)
+            .map_err(|e| StableMemoryError::UnableToCreateMemory(format!("{:?}", e)))
+    }
+
+    pub fn pop(&mut self) -> Option<T> {
+        self.inner.pop()
+    }
+}
+
+impl<T: Storable, M: Memory> QuotaGuard<StableLog<T, VirtualMemory<M>, VirtualMemory<M>>, M> {
+    pub fn append(&mut self, item: &T) -> Result<u64, StableMemoryError> {
+        self.check()?;
+
+        self.inner
+            .append(item)
+            .map_err(|e| StableMemoryError::UnableToCreateMemory(format!("{:?}", e)))
+    }
+}
+
+impl<K: Storable + Ord + Clone, V: Storable> QuotaGuard<TtlMap<K, V>> {
+    pub fn insert(&mut self, key: K, value: V) -> Result<Option<V>, StableMemoryError> {
+        self.check()?;
+
+        Ok(self.inner.insert(key, value))
+    }
+
+    pub fn insert_with_ttl(
+        &mut self,
+        key: K,
+        value: V,
+        ttl: u64,
+    ) -> Result<Option<V>, StableMemoryError> {
+        self.check()?;
+
+        Ok(self.inner.insert_with_ttl(key, value, ttl))
+    }
+
+    pub fn insert_until(
+        &mut self,
+        key: K,
+        value: V,
+        expires_at: NanoTimeStamp,
+    ) -> Result<Option<V>, StableMemoryError> {
+        self.check()?;
+
+        Ok(self.inner.insert_until(key, value, expires_at))
+    }
+
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        self.inner.remove(key)
+    }
+}
+
+impl<T: Storable + Clone> QuotaGuard<NonceMap<T>> {
+    pub fn push(&mut self, item: T) -> Result<Nonce, StableMemoryError> {
+        self.check()?;
+
+        self.inner.push(item)
+    }
+
+    pub fn insert(&mut self, id: Nonce, item: T) -> Result<Option<T>, StableMemoryError> {
+        self.check()?;
+
+        Ok(self.inner.insert(id, item))
+    }
+
+    pub fn remove(&mut self, id: &Nonce) -> Option<T> {
+        self.inner.remove(id)
+    }
+}
@@ -0,0 +1,201 @@
+#[cfg(test)]
+mod tests {
+    use crate::memory::{
+        error::StableMemoryError,
+        quota::{PartitionQuota, QuotaStatus, WASM_PAGE_SIZE},
+        ttl::TtlMap,
+        types::{DefaultStableBTreeMap, DefaultStableLog},
+        NonceMap, StableMemoryManager,
+    };
+    use crate::nonce::Nonce;
+
+    const MIB: u64 = 1024 * 1024;
+
+    #[test]
+    fn test_quota_status() {
+        let quota = PartitionQuota::default().soft(10).hard(20);
+
+        assert_eq!(quota.status(5), QuotaStatus::Within);
+        assert_eq!(quota.status(10), QuotaStatus::SoftExceeded);
+        assert_eq!(quota.status(20), QuotaStatus::HardExceeded);
+
+        assert!(matches!(
+            quota.check("test", 25),
+            Err(StableMemoryError::QuotaExceeded {
+                used: 25,
+                limit: 20,
+                ..
+            })
+        ));
+
+        assert_eq!(
+            PartitionQuota::default().status(u64::MAX),
+            QuotaStatus::Within
+        );
+    }
+
+    #[test]
+    fn test_quota_guard_insert() {
+        let mut manager = StableMemoryManager::init();
+
+        let mut map = manager
+            .init_memory_with_quota::<DefaultStableBTreeMap<u64, u64>>(
+                "quota_map",
+                10,
+                PartitionQuota::default().soft(WASM_PAGE_SIZE).hard(MIB),
+            )
+            .unwrap();
+
+        assert_eq!(map.insert(1, 10).unwrap(), None);
+        assert_eq!(map.get(&1), Some(10));
+        assert_eq!(map.status(), QuotaStatus::SoftExceeded);
+        assert_eq!(
+            manager.check_quota("quota_map").unwrap(),
+            QuotaStatus::SoftExceeded
+        );
+
+        // Lowering the hard limit below the usage rejects the next writes
+        map.set_quota(PartitionQuota::default().hard(WASM_PAGE_SIZE));
+
+        assert!(matches!(
+            map.insert(2, 20),
+            Err(StableMemoryError::QuotaExceeded { .. })
+        ));
+
+        // The manager and the guard share the quota
+        assert_eq!(
+            manager.quota("quota_map"),
+            Some(PartitionQuota::default().hard(WASM_PAGE_SIZE))
+        );
+        assert!(manager.check_quota("quota_map").is_err());
+
+        manager.set_quota("quota_map", PartitionQuota::default().hard(MIB));
+
+        assert_eq!(map.insert(2, 20).unwrap(), None);
+        assert_eq!(map.len(), 2);
+
+        // Removing is always allowed
+        assert_eq!(map.remove(&1), Some(10));
+    }
+
+    #[test]
+    fn test_quota_guard_ttl_and_nonce_maps() {
+        let mut manager = StableMemoryManager::init();
+
+        let mut sessions = manager
+            .init_memory_with_quota::<TtlMap<u64, u64>>(
+                "quota_ttl",
+                10,
+                PartitionQuota::default().hard(MIB),
+            )
+            .unwrap();
+
+        let mut items = manager
+            .init_memory_with_quota::<NonceMap<u64>>(
+                "quota_items",
+                20,
+                PartitionQuota::default().hard(MIB),
+            )
+            .unwrap();
+
+        assert_eq!(sessions.insert(1, 10).unwrap(), None);
+        assert_eq!(sessions.insert_with_ttl(2, 20, 1_000).unwrap(), None);
+        assert_eq!(items.push(30).unwrap(), Nonce(0));
+
+        manager.set_quota("quota_ttl", PartitionQuota::default().hard(WASM_PAGE_SIZE));
+        manager.set_quota(
+            "quota_items",
+            PartitionQuota::default().hard(WASM_PAGE_SIZE),
+        );
+
+        assert!(matches!(
+            sessions.insert(3, 30),
+            Err(StableMemoryError::QuotaExceeded { .. })
+        ));
+        assert!(matches!(
+            sessions.insert_with_ttl(3, 30, 1_000),
+            Err(StableMemoryError::QuotaExceeded { .. })
+        ));
+        assert!(matches!(
+            items.push(40),
+            Err(StableMemoryError::QuotaExceeded { .. })
+        ));
+        assert!(matches!(
+            items.insert(Nonce(0), 40),
+            Err(StableMemoryError::QuotaExceeded { .. })
+        ));
+
+        // The rejected push didn't use an id
+        assert_eq!(items.next_id(), Nonce(1));
+
+        assert_eq!(sessions.remove(&1), Some(10));
+        assert_eq!(items.remove(&Nonce(0)), Some(30));
+    }
+
+    #[test]
+    fn test_quota_guard_covers_all_partitions() {
+        let mut manager = StableMemoryManager::init();
+
+        let log = manager
+            .init_memory_with_quota::<DefaultStableLog<u64>>(
+                "quota_log",
+                10,
+                PartitionQuota::default().hard(MIB),
+            )
+            .unwrap();
+
+        let index_bytes = manager.used_bytes("quota_log_index").unwrap();
+        let data_bytes = manager.used_bytes("quota_log_data").unwrap();
+
+        assert_eq!(log.used_bytes(), index_bytes + data_bytes);
+        assert_eq!(log.used_bytes() % WASM_PAGE_SIZE, 0);
+
+        // The manager measures the same partitions as the guard
+        assert_eq!(manager.used_bytes("quota_log"), Some(log.used_bytes()));
+
+        manager.set_quota("quota_log", PartitionQuota::default().hard(index_bytes));
+
+        assert!(log.check().is_err());
+        assert!(matches!(
+            manager.check_quota("quota_log"),
+            Err(StableMemoryError::QuotaExceeded { used, .. }) if used == log.used_bytes()
+        ));
+
+        let details = manager.partition_details();
+
+        assert!(details
+            .iter()
+            .filter(|detail| detail.name.starts_with("quota_log_"))
+            .all(|detail| detail.quota == Some(PartitionQuota::default().hard(index_bytes))));
+    }
+
+    #[test]
+    fn test_partition_details_quota() {
+        let mut manager = StableMemoryManager::init();
+
+        manager.set_quota(
+            "details_map",
+            PartitionQuota::default().hard(WASM_PAGE_SIZE),
+        );
+
+        let _: DefaultStableBTreeMap<u64, u64> = manager.init_memory("details_map", 10).unwrap();
+
+        let details = manager.partition_details();
+        let detail = details.iter().find(|d| d.name == "details_map").unwrap();
+
+        assert_eq!(detail.bytes, detail.size * WASM_PAGE_SIZE);
+        assert_eq!(
+            detail.quota,
+            Some(PartitionQuota::default().hard(WASM_PAGE_SIZE))
+        );
+
+        assert!(matches!(
+            manager.check_quota("details_map"),
+            Err(StableMemoryError::QuotaExceeded { .. })
+        ));
+        assert_eq!(
+            manager.check_quota("no_quota").unwrap(),
+            QuotaStatus::Within
+        );
+    }
+}
@@ -0,0 +1,393 @@
+use ic_stable_structures::Storable;
+use std::{cell::RefCell, marker::PhantomData, thread::LocalKey};
+
+use super::{
+    error::StableMemoryError,
+    helper::{StateAccess, StateMutations},
+    types::{DefaultStableBTreeMap, DefaultStableCell, DefaultStableVec, DefaultVM},
+};
+use crate::nonce::Nonce;
+
+mod test;
+
+/// A stable map whose ids come from a persisted counter, so an id is never handed out
+/// twice, even after the item is removed or the map is cleared.
+///
+/// The map uses two partitions: `{name}` at `id` and `{name}_nonce` at `id + 1`.
+pub struct NonceMap<T: Storable> {
+    items: DefaultStableBTreeMap<Nonce, T>,
+    nonce: DefaultStableCell<Nonce>,
+}
+
+impl<T: Storable + Clone> NonceMap<T> {
+    pub fn init(
+        items_memory: DefaultVM,
+        nonce_memory: DefaultVM,
+    ) -> Result<Self, StableMemoryError> {
+        let nonce = DefaultStableCell::init(nonce_memory, Nonce::zero())
+            .map_err(|e| StableMemoryError::UnableToCreateMemory(format!("{:?}", e)))?;
+
+        Ok(Self {
+            items: DefaultStableBTreeMap::init(items_memory),
+            nonce,
+        })
+    }
+
+    /// Returns the id the next pushed item will get.
+    pub fn next_id(&self) -> Nonce {
+        *self.nonce.get()
+    }
+
+    /// Stores `item` under a new id and advances the counter.
+    pub fn push(&mut self, item: T) -> Result<Nonce, StableMemoryError> {
+        let id = self.next_id();
+
+        self.nonce
+            .set(id.add_64(1))
+            .map_err(|e| StableMemoryError::UnableToCreateMemory(format!("{:?}", e)))?;
+
+        self.items.insert(id, item);
+
+        Ok(id)
+    }
+
+    pub fn get(&self, id: &Nonce) -> Option<T> {
+        self.items.get(id)
+    }
+
+    pub fn contains_key(&self, id: &Nonce) -> bool {
+        self.items.contains_key(id)
+    }
+
+    /// Replaces the item at `id`, returns the previous one.
+    pub fn insert(&mut self, id: Nonce, item: T) -> Option<T> {
+        self.items.insert(id, item)
+    }
+
+    pub fn remove(&mut self, id: &Nonce) -> Option<T> {
+        self.items.remove(id)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Nonce, T)> + '_ {
+        self.items.iter()
+    }
+
+    pub fn len(&self) -> u64 {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Removes every item, the counter keeps going.
+    pub fn clear(&mut self) {
+        while self.items.pop_last().is_some() {}
+    }
+}
+
+/// A stable collection of items addressed by a [`Nonce`].
+pub trait StableCollection<T> {
+    fn get_item(&self, id: &Nonce) -> Option<T>;
+    fn set_item(&mut self, id: &Nonce, item: T) -> Result<(), StableMemoryError>;
+    /// Stores a new item under a freshly generated id.
+    fn push_item(&mut self, item: T) -> Result<Nonce, StableMemoryError>;
+    fn remove_item(&mut self, id: &Nonce) -> Result<Option<T>, StableMemoryError>;
+    fn items(&self) -> Vec<(Nonce, T)>;
+    fn item_count(&self) -> u64;
+    fn clear_items(&mut self);
+}
+
+/// Ids come from the counter of the map, starting from zero.
+impl<T: Storable + Clone> StableCollection<T> for NonceMap<T> {
+    fn get_item(&self, id: &Nonce) -> Option<T> {
+        self.get(id)
+    }
+
+    fn set_item(&mut self, id: &Nonce, item: T) -> Result<(), StableMemoryError> {
+        if !self.contains_key(id) {
+            return Err(StableMemoryError::ItemNotFound(id.get()));
+        }
+
+        self.insert(*id, item);
+
+        Ok(())
+    }
+
+    fn push_item(&mut self, item: T) -> Result<Nonce, StableMemoryError> {
+        self.push(item)
+    }
+
+    fn remove_item(&mut self, id: &Nonce) -> Result<Option<T>, StableMemoryError> {
+        Ok(self.remove(id))
+    }
+
+    fn items(&self) -> Vec<(Nonce, T)> {
+        self.iter().collect()
+    }
+
+    fn item_count(&self) -> u64 {
+        self.len()
+    }
+
+    fn clear_items(&mut self) {
+        self.clear()
+    }
+}
+
+/// Ids are the positions in the vector, items can't be removed one by one.
+impl<T: Storable + Clone> StableCollection<T> for DefaultStableVec<T> {
+    fn get_item(&self, id: &Nonce) -> Option<T> {
+        self.get(id.get())
+    }
+
+    fn set_item(&mut self, id: &Nonce, item: T) -> Result<(), StableMemoryError> {
+        if id.get() >= self.len() {
+            return Err(StableMemoryError::ItemNotFound(id.get()));
+        }
+
+        self.set(id.get(), &item);
+
+        Ok(())
+    }
+
+    fn push_item(&mut self, item: T) -> Result<Nonce, StableMemoryError> {
+        let id = Nonce(self.len());
+
+        self.push(&item)
+            .map_err(|e| StableMemoryError::UnableToCreateMemory(format!("{:?}", e)))?;
+
+        Ok(id)
+    }
+
+    fn remove_item(&mut self, _id: &Nonce) -> Result<Option<T>, StableMemoryError> {
+        Err(StableMemoryError::InvalidMemoryType)
+    }
+
+    fn items(&self) -> Vec<(Nonce, T)> {
+        self.iter()
+            .enumerate()
+            .map(|(index, item)| (Nonce(index as u64), item))
+            .collect()
+    }
+
+    fn item_count(&self) -> u64 {
+        self.len()
+    }
+
+    fn clear_items(&mut self) {
+        while self.pop().is_some() {}
+    }
+}
+
+/// A collection declared in a thread-local, usually through [`stable_state!`](crate::stable_state).
+///
+/// Every `StableState` implements [`StateAccess`] and [`StateMutations`].
+pub trait StableState: 'static {
+    type Item: Storable + Clone;
+    type View: From<(Nonce, Self::Item)>;
+    type Collection: StableCollection<Self::Item> + 'static;
+
+    fn store() -> &'static LocalKey<RefCell<Self::Collection>>;
+}
+
+/// Read handle on a single item of a [`StableState`].
+pub struct ReadState<S: StableState> {
+    id: Nonce,
+    state: PhantomData<S>,
+}
+
+impl<S: StableState> ReadState<S> {
+    pub fn id(&self) -> Nonce {
+        self.id
+    }
+
+    pub fn exists(&self) -> bool {
+        S::store().with(|s| s.borrow().get_item(&self.id).is_some())
+    }
+
+    pub fn get(&self) -> Result<S::Item, StableMemoryError> {
+        S::store()
+            .with(|s| s.borrow().get_item(&self.id))
+            .ok_or(StableMemoryError::ItemNotFound(self.id.get()))
+    }
+
+    pub fn view(&self) -> Result<S::View, StableMemoryError> {
+        self.get().map(|item| S::View::from((self.id, item)))
+    }
+
+    pub fn with<F, R>(&self, f: F) -> Result<R, StableMemoryError>
+    where
+        F: FnOnce(&S::Item) -> R,
+    {
+        self.get().map(|item| f(&item))
+    }
+}
+
+/// Write handle on a single item of a [`StableState`].
+pub struct WriteState<S: StableState> {
+    id: Nonce,
+    state: PhantomData<S>,
+}
+
+impl<S: StableState> WriteState<S> {
+    pub fn id(&self) -> Nonce {
+        self.id
+    }
+
+    pub fn set(&self, item: S::Item) -> Result<(), StableMemoryError> {
+        S::store().with(|s| s.borrow_mut().set_item(&self.id, item))
+    }
+
+    /// Applies `f` to the item and stores the result.
+    pub fn update<F, R>(&self, f: F) -> Result<R, StableMemoryError>
+    where
+        F: FnOnce(&mut S::Item) -> R,
+    {
+        S::store().with(|s| {
+            let mut store = s.borrow_mut();
+
+            let mut item = store
+                .get_item(&self.id)
+                .ok_or(StableMemoryError::ItemNotFound(self.id.get()))?;
+
+            let result = f(&mut item);
+
+            store.set_item(&self.id, item)?;
+
+            Ok(result)
+        })
+    }
+
+    pub fn remove(&self) -> Result<S::Item, StableMemoryError> {
+        S::store()
+            .with(|s| s.borrow_mut().remove_item(&self.id))?
+            .ok_or(StableMemoryError::ItemNotFound(self.id.get()))
+    }
+}
+
+impl<S: StableState> StateAccess for S {
+    type Item = S::Item;
+    type View = S::View;
+    type ReadState = ReadState<S>;
+    type Id = Nonce;
+
+    fn read(id: Nonce) -> ReadState<S> {
+        ReadState {
+            id,
+            state: PhantomData,
+        }
+    }
+
+    fn iter<F, R>(mut f: F) -> Vec<R>
+    where
+        F: FnMut(&Nonce, &S::Item) -> R,
+    {
+        S::store().with(|s| {
+            s.borrow()
+                .items()
+                .iter()
+                .map(|(id, item)| f(id, item))
+                .collect()
+        })
+    }
+
+    fn views() -> Vec<S::View> {
+        S::store().with(|s| s.borrow().items().into_iter().map(S::View::from).collect())
+    }
+
+    fn len() -> u64 {
+        S::store().with(|s| s.borrow().item_count())
+    }
+}
+
+impl<S: StableState> StateMutations for S {
+    type Error = StableMemoryError;
+    type AddArgs = S::Item;
+    type WriteState = WriteState<S>;
+    type Id = Nonce;
+
+    fn add(item: S::Item) -> Result<Nonce, StableMemoryError> {
+        S::store().with(|s| s.borrow_mut().push_item(item))
+    }
+
+    fn write(id: Nonce) -> WriteState<S> {
+        WriteState {
+            id,
+            state: PhantomData,
+        }
+    }
+
+    fn reset() {
+        S::store().with(|s| s.borrow_mut().clear_items())
+    }
+}
+
+/// Declares a stable collection in a thread-local and implements [`StableState`] for it,
+/// which gives it `read`, `iter`, `views`, `len`, `add`, `write` and `reset`.
+///
+/// `map` collections are backed by a [`NonceMap<T>`], which takes the partitions `id` and
+/// `id + 1`, `vec` collections by a `DefaultStableVec<T>`. The view defaults to `(Nonce, T)` and can be set with `as`,
+/// it must implement `From<(Nonce, T)>`.
+///
+/// # Example
+/// ```
+/// use b3_utils::memory::{StateAccess, StateMutations};
+/// use b3_utils::{nonce::Nonce, stable_state};
+///
+/// pub struct Balance {
+///     pub id: Nonce,
+///     pub amount: u64,
+/// }
+///
+/// impl From<(Nonce, u64)> for Balance {
+///     fn from((id, amount): (Nonce, u64)) -> Self {
+///         Self { id, amount }
+///     }
+/// }
+///
+/// stable_state! {
+///     pub struct Balances: map<u64> as Balance = ("balances", 1);
+///     pub struct History: vec<u64> = ("history", 3);
+/// }
+///
+/// let id = Balances::add(100).unwrap();
+///
+/// Balances::write(id).update(|amount| *amount += 50).unwrap();
+/// History::add(50).unwrap();
+///
+/// assert_eq!(Balances::read(id).get().unwrap(), 150);
+/// assert_eq!(Balances::views()[0].amount, 150);
+/// assert_eq!(History::len(), 1);
+/// ```
+#[macro_export]
+macro_rules! stable_state {
+    ($($(#[$meta:meta])* $vis:vis struct $name:ident: $kind:ident<$item:ty> $(as $view:ty)? = ($memory:expr, $id:expr);)+) => {
+        $(
+            $crate::stable_state!(@state $(#[$meta])* $vis $name, $kind, $item, ($($view)?), $memory, $id);
+        )+
+    };
+    (@state $(#[$meta:meta])* $vis:vis $name:ident, $kind:ident, $item:ty, ($($view:ty)?), $memory:expr, $id:expr) => {
+        $(#[$meta])*
+        $vis struct $name;
+
+        impl $crate::memory::StableState for $name {
+            type Item = $item;
+            type View = $crate::stable_state!(@view $item $(, $view)?);
+            type Collection = $crate::stable_state!(@collection $kind, $item);
+
+            fn store() -> &'static std::thread::LocalKey<std::cell::RefCell<Self::Collection>> {
+                thread_local! {
+                    static STORE: std::cell::RefCell<$crate::stable_state!(@collection $kind, $item)> =
+                        $crate::memory::init_stable_mem_refcell($memory, $id).unwrap();
+                }
+
+                &STORE
+            }
+        }
+    };
+    (@view $item:ty) => { ($crate::nonce::Nonce, $item) };
+    (@view $item:ty, $view:ty) => { $view };
+    (@collection map, $item:ty) => { $crate::memory::NonceMap<$item> };
+    (@collection vec, $item:ty) => { $crate::memory::types::DefaultStableVec<$item> };
+}
@@ -0,0 +1,102 @@
+#[cfg(test)]
+mod tests {
+    use crate::{
+        memory::{error::StableMemoryError, StateAccess, StateMutations},
+        nonce::Nonce,
+        stable_state,
+    };
+
+    #[derive(Debug, PartialEq)]
+    struct Amount(u64);
+
+    impl From<(Nonce, u64)> for Amount {
+        fn from((_, amount): (Nonce, u64)) -> Self {
+            Self(amount)
+        }
+    }
+
+    stable_state! {
+        struct Balances: map<u64> = ("state_balances", 200);
+        struct Amounts: map<u64> as Amount = ("state_amounts", 202);
+        struct History: vec<u64> = ("state_test_history", 204);
+    }
+
+    #[test]
+    fn test_map_state() {
+        let first = Balances::add(100).unwrap();
+        let second = Balances::add(200).unwrap();
+
+        assert_eq!(first, Nonce(0));
+        assert_eq!(second, Nonce(1));
+        assert_eq!(Balances::len(), 2);
+
+        assert_eq!(Balances::read(first).get().unwrap(), 100);
+        assert!(!Balances::read(Nonce(5)).exists());
+        assert!(matches!(
+            Balances::read(Nonce(5)).get(),
+            Err(StableMemoryError::ItemNotFound(5))
+        ));
+
+        let doubled = Balances::write(second)
+            .update(|amount| {
+                *amount *= 2;
+                *amount
+            })
+            .unwrap();
+
+        assert_eq!(doubled, 400);
+        assert_eq!(Balances::read(second).get().unwrap(), 400);
+
+        assert!(Balances::write(Nonce(5)).set(1).is_err());
+
+        assert_eq!(
+            Balances::iter(|id, amount| (*id, *amount)),
+            vec![(first, 100), (second, 400)]
+        );
+        assert_eq!(Balances::views(), vec![(first, 100), (second, 400)]);
+
+        // Ids are never handed out again, even after a remove or a reset
+        assert_eq!(Balances::write(second).remove().unwrap(), 400);
+        assert_eq!(Balances::add(300).unwrap(), Nonce(2));
+
+        Balances::reset();
+
+        assert_eq!(Balances::len(), 0);
+        assert_eq!(Balances::add(400).unwrap(), Nonce(3));
+        assert!(!Balances::read(second).exists());
+    }
+
+    #[test]
+    fn test_state_custom_view() {
+        let id = Amounts::add(7).unwrap();
+
+        assert_eq!(Amounts::views(), vec![Amount(7)]);
+        assert_eq!(Amounts::read(id).view().unwrap(), Amount(7));
+    }
+
+    #[test]
+    fn test_vec_state() {
+        let first = History::add(1).unwrap();
+        let second = History::add(2).unwrap();
+
+        assert_eq!((first, second), (Nonce(0), Nonce(1)));
+
+        History::write(first).set(10).unwrap();
+
+        assert_eq!(History::read(first).with(|value| value + 1).unwrap(), 11);
+        assert_eq!(History::views(), vec![(first, 10), (second, 2)]);
+
+        assert!(matches!(
+            History::write(first).remove(),
+            Err(StableMemoryError::InvalidMemoryType)
+        ));
+        assert!(matches!(
+            History::write(Nonce(2)).set(3),
+            Err(StableMemoryError::ItemNotFound(2))
+        ));
+
+        History::reset();
+
+        assert_eq!(History::len(), 0);
+    }
+}
@@ -60,6 +60,21 @@ where
     })
 }
 
+/// Replaces the global manager with one over a fresh memory, so tests can start from scratch.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn reset_stable_mem() {
+    STABLE_MEMORY.with(|states| *states.borrow_mut() = StableMemoryManager::init());
+}
+
+/// Reloads the global manager from its memory, as after a canister upgrade.
+/// See [`StableMemoryManager::simulate_upgrade`].
+pub fn upgrade_stable_mem() {
+    STABLE_MEMORY.with(|states| {
+        let upgraded = states.borrow().simulate_upgrade();
+        *states.borrow_mut() = upgraded;
+    });
+}
+
 pub fn init_stable_mem<F: InitMemory<F>>(name: &str, id: u8) -> Result<F, StableMemoryError> {
     with_stable_mem_mut(|pm| pm.init_memory(name, id))
 }
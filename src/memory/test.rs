@@ -1,8 +1,17 @@
 #[cfg(test)]
 mod tests {
+    use ic_stable_structures::{
+        memory_manager::VirtualMemory, FileMemory, StableBTreeMap, VectorMemory,
+    };
+
     use crate::memory::{
+        error::StableMemoryError,
+        init_stable_mem,
+        quota::PartitionQuota,
+        reset_stable_mem,
+        ttl::TtlMap,
         types::{DefaultStableBTreeMap, DefaultStableLog, DefaultStableMinHeap, DefaultStableVec},
-        with_stable_mem, with_stable_mem_mut, StableMemoryManager,
+        upgrade_stable_mem, with_stable_mem, with_stable_mem_mut, NonceMap, StableMemoryManager,
     };
 
     #[test]
@@ -284,4 +293,132 @@ mod tests {
 
         assert_eq!(backup.len(), 0);
     }
+
+    #[test]
+    fn test_init_memory_last_id() {
+        let mut stable_memory = StableMemoryManager::init();
+
+        // The second partition would land on the reserved id 251
+        assert!(matches!(
+            stable_memory.init_memory::<DefaultStableLog<u8>>("log", 250),
+            Err(StableMemoryError::IdOutOfRange(251))
+        ));
+        assert!(matches!(
+            stable_memory.init_memory::<TtlMap<u8, u8>>("ttl", 250),
+            Err(StableMemoryError::IdOutOfRange(251))
+        ));
+        assert!(matches!(
+            stable_memory.init_memory::<NonceMap<u8>>("items", 250),
+            Err(StableMemoryError::IdOutOfRange(251))
+        ));
+        assert_eq!(stable_memory.partitions().len(), 0);
+
+        assert!(stable_memory
+            .init_memory::<DefaultStableLog<u8>>("log", 248)
+            .is_ok());
+        assert!(stable_memory
+            .init_memory::<DefaultStableVec<u8>>("vec", 250)
+            .is_ok());
+    }
+
+    #[test]
+    fn test_vector_memory_upgrade() {
+        let mut stable_memory = StableMemoryManager::init_with(VectorMemory::default());
+
+        stable_memory.set_quota("users", PartitionQuota::default().hard(1));
+
+        let mut map: StableBTreeMap<u32, u32, VirtualMemory<VectorMemory>> =
+            stable_memory.init_memory("users", 10).unwrap();
+
+        map.insert(1, 10);
+        map.insert(2, 20);
+
+        drop(map);
+
+        let mut stable_memory = stable_memory.simulate_upgrade();
+
+        // Partitions are stable, quotas live on the heap
+        assert_eq!(stable_memory.partition("users"), Some(10));
+        assert_eq!(stable_memory.quota("users"), None);
+
+        let map: StableBTreeMap<u32, u32, VirtualMemory<VectorMemory>> =
+            stable_memory.init_memory("users", 10).unwrap();
+
+        assert_eq!(map.len(), 2);
+        assert_eq!(map.get(&2), Some(20));
+
+        assert!(stable_memory
+            .init_memory::<StableBTreeMap<u32, u32, VirtualMemory<VectorMemory>>>("other", 10)
+            .is_err());
+    }
+
+    #[test]
+    fn test_file_memory() {
+        let path = std::env::temp_dir().join(format!(
+            "b3_utils_test_file_memory_{}.bin",
+            std::process::id()
+        ));
+
+        {
+            let file = std::fs::OpenOptions::new()
+                .read(true)
+                .write(true)
+                .create(true)
+                .truncate(true)
+                .open(&path)
+                .unwrap();
+
+            let mut stable_memory = StableMemoryManager::init_with(FileMemory::new(file));
+
+            let log: crate::memory::types::StableLog<
+                u64,
+                VirtualMemory<FileMemory>,
+                VirtualMemory<FileMemory>,
+            > = stable_memory.init_memory("events", 20).unwrap();
+
+            log.append(&1).unwrap();
+            log.append(&2).unwrap();
+        }
+
+        let file = std::fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(&path)
+            .unwrap();
+
+        let mut stable_memory = StableMemoryManager::init_with(FileMemory::new(file));
+
+        assert_eq!(stable_memory.partition("events_index"), Some(20));
+        assert_eq!(stable_memory.partition("events_data"), Some(21));
+
+        let log: crate::memory::types::StableLog<
+            u64,
+            VirtualMemory<FileMemory>,
+            VirtualMemory<FileMemory>,
+        > = stable_memory.init_memory("events", 20).unwrap();
+
+        assert_eq!(log.len(), 2);
+        assert_eq!(log.get(1), Some(2));
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_global_reset_and_upgrade() {
+        reset_stable_mem();
+
+        let mut map: DefaultStableBTreeMap<u32, u32> = init_stable_mem("global", 10).unwrap();
+
+        map.insert(1, 1);
+
+        upgrade_stable_mem();
+
+        let map: DefaultStableBTreeMap<u32, u32> = init_stable_mem("global", 10).unwrap();
+
+        assert_eq!(map.get(&1), Some(1));
+
+        reset_stable_mem();
+
+        with_stable_mem(|memory| assert_eq!(memory.partitions().len(), 0));
+    }
 }
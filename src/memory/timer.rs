@@ -1,24 +1,131 @@
 use super::types::DefaultVM;
-use crate::{memory::DefaultStableMinHeap, NanoTimeStamp};
+use crate::{memory::DefaultStableMinHeap, CronSchedule, NanoTimeStamp};
 use candid::CandidType;
 use ic_stable_structures::{storable::Bound, vec::InitError, GrowFailed, Storable};
 use serde::{Deserialize, Serialize};
-use std::{borrow::Cow, cmp::Ordering};
+use std::{
+    borrow::Cow,
+    cmp::{Ordering, Reverse},
+    collections::BTreeMap,
+};
 
 mod test;
 
+mod dead_letter;
+pub use dead_letter::*;
+
+mod retry;
+pub use retry::*;
+
+mod legacy;
+use legacy::LegacyTaskTimerEntry;
+
+#[cfg(feature = "timer")]
+mod executor;
+#[cfg(feature = "timer")]
+pub use executor::*;
+
 #[derive(CandidType, Debug, Clone, Serialize, Deserialize)]
 pub struct TaskTimerEntry<T> {
     pub time: NanoTimeStamp,
     pub task: T,
     pub interval: Option<NanoTimeStamp>,
+    pub retry: TaskRetry,
+    pub cron: Option<CronSchedule>,
+    /// Higher priorities run first among due entries and win ties on `time`.
+    pub priority: u8,
+}
+
+impl<T> TaskTimerEntry<T> {
+    pub fn new(time: NanoTimeStamp, task: T) -> Self {
+        Self {
+            time,
+            task,
+            interval: None,
+            retry: TaskRetry::default(),
+            cron: None,
+            priority: 0,
+        }
+    }
+
+    /// Creates an entry firing at every occurrence of the schedule after `now`.
+    /// Returns `None` if the schedule never fires.
+    pub fn cron(schedule: CronSchedule, task: T, now: &NanoTimeStamp) -> Option<Self> {
+        let time = schedule.next_after(now)?;
+
+        Some(Self::new(time, task).with_cron(schedule))
+    }
+
+    pub fn with_interval(mut self, interval: NanoTimeStamp) -> Self {
+        self.interval = Some(interval);
+        self
+    }
+
+    /// Reschedules the entry at the next occurrence of the schedule once popped.
+    /// Takes precedence over `interval`.
+    pub fn with_cron(mut self, schedule: CronSchedule) -> Self {
+        self.cron = Some(schedule);
+        self
+    }
+
+    /// Returns the time of the next occurrence of a recurring entry.
+    pub fn next_time(&self) -> Option<NanoTimeStamp> {
+        match (&self.cron, &self.interval) {
+            (Some(cron), _) => cron.next_after(&self.time),
+            (None, Some(interval)) => Some(self.time.clone() + interval.clone()),
+            (None, None) => None,
+        }
+    }
+
+    pub fn with_max_attempts(mut self, max_attempts: u32) -> Self {
+        self.retry = TaskRetry::new(max_attempts);
+        self
+    }
+
+    pub fn with_priority(mut self, priority: u8) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    /// Returns the priority raised by one for every `aging` elapsed since the entry was due,
+    /// so low priority entries can't be starved forever. An `aging` of zero disables it.
+    pub fn effective_priority(&self, now: &NanoTimeStamp, aging: &NanoTimeStamp) -> u64 {
+        let waited = now.0.saturating_sub(self.time.0);
+
+        match aging.0 {
+            0 => self.priority as u64,
+            aging => (self.priority as u64).saturating_add(waited / aging),
+        }
+    }
 }
 
 pub struct DefaultTaskTimer<T: Storable>(DefaultStableMinHeap<TaskTimerEntry<T>>);
 
 impl<T: Storable + Clone> DefaultTaskTimer<T> {
+    /// Loads the heap from the memory, heaps written with the legacy layout are migrated.
     pub fn init(vm: DefaultVM) -> Result<Self, InitError> {
-        let task_timer = Self(DefaultStableMinHeap::init(vm)?);
+        match DefaultStableMinHeap::init(vm.clone()) {
+            Ok(heap) => Ok(Self(heap)),
+            Err(InitError::IncompatibleElementType) => Self::migrate_legacy(vm),
+            Err(err) => Err(err),
+        }
+    }
+
+    fn migrate_legacy(vm: DefaultVM) -> Result<Self, InitError> {
+        let legacy: DefaultStableMinHeap<LegacyTaskTimerEntry<T>> =
+            DefaultStableMinHeap::init(vm.clone())?;
+
+        let timers: Vec<TaskTimerEntry<T>> = legacy.iter().map(TaskTimerEntry::from).collect();
+
+        let mut task_timer =
+            Self(DefaultStableMinHeap::new(vm).map_err(|_| InitError::OutOfMemory)?);
+
+        for timer in timers {
+            task_timer
+                .push_timer(&timer)
+                .map_err(|_| InitError::OutOfMemory)?;
+        }
+
         Ok(task_timer)
     }
 
@@ -44,22 +151,117 @@ impl<T: Storable + Clone> DefaultTaskTimer<T> {
 
     pub fn pop_timer(&mut self) -> Option<TaskTimerEntry<T>> {
         let timer = self.0.pop();
-        if let Some(timer) = timer.clone() {
-            if let Some(interval) = timer.interval {
-                // If it's an interval timer, reschedule it
-                let new_time = timer.time + interval.clone();
-                let new_timer = TaskTimerEntry {
-                    time: new_time,
-                    task: timer.task.clone(),
-                    interval: Some(interval),
-                };
-                let _ = self.push_timer(&new_timer);
-            }
+        if let Some(timer) = &timer {
+            let _ = self.reschedule(timer);
         }
 
         timer
     }
 
+    /// Pushes the next occurrence of a recurring entry, does nothing for one-shot entries.
+    pub fn reschedule(&mut self, timer: &TaskTimerEntry<T>) -> Result<(), GrowFailed> {
+        match timer.next_time() {
+            Some(new_time) => self.push_timer(&TaskTimerEntry {
+                time: new_time,
+                task: timer.task.clone(),
+                interval: timer.interval.clone(),
+                retry: TaskRetry::new(timer.retry.max_attempts),
+                cron: timer.cron,
+                priority: timer.priority,
+            }),
+            None => Ok(()),
+        }
+    }
+
+    /// Pops every entry that is due at `now`, up to `limit` entries.
+    /// Interval entries are rescheduled the same way as in `pop_timer`.
+    pub fn pop_due_timers(&mut self, now: &NanoTimeStamp, limit: usize) -> Vec<TaskTimerEntry<T>> {
+        let mut due = Vec::new();
+
+        while due.len() < limit {
+            match self.0.peek() {
+                Some(timer) if &timer.time <= now => {}
+                _ => break,
+            }
+
+            if let Some(timer) = self.pop_timer() {
+                due.push(timer);
+            }
+        }
+
+        due
+    }
+
+    /// Pops the `limit` due entries with the highest effective priority, ordered by their
+    /// effective priority then by time. Every due entry is considered, not only the earliest.
+    ///
+    /// Unlike `pop_due_timers`, recurring entries are not rescheduled: the caller either
+    /// runs an entry and calls `reschedule`, or puts it back with `push_timer`.
+    pub fn pop_due_by_priority(
+        &mut self,
+        now: &NanoTimeStamp,
+        limit: usize,
+        aging: &NanoTimeStamp,
+    ) -> Vec<TaskTimerEntry<T>> {
+        let key = |timer: &TaskTimerEntry<T>| {
+            (
+                Reverse(timer.effective_priority(now, aging)),
+                timer.time.clone(),
+            )
+        };
+
+        // Pick the keys first, the heap is only ordered by time
+        let mut keys: Vec<_> = self
+            .0
+            .iter()
+            .filter(|timer| &timer.time <= now)
+            .map(|timer| key(&timer))
+            .collect();
+
+        keys.sort();
+        keys.truncate(limit);
+
+        let mut selected: BTreeMap<_, usize> = BTreeMap::new();
+        for key in keys {
+            *selected.entry(key).or_default() += 1;
+        }
+
+        let mut due = Vec::new();
+        let mut skipped = Vec::new();
+
+        while !selected.is_empty() {
+            let timer = match self.0.pop() {
+                Some(timer) => timer,
+                None => break,
+            };
+
+            let timer_key = key(&timer);
+
+            match selected.get_mut(&timer_key) {
+                Some(count) => {
+                    *count -= 1;
+                    if *count == 0 {
+                        selected.remove(&timer_key);
+                    }
+
+                    due.push(timer);
+                }
+                None => skipped.push(timer),
+            }
+        }
+
+        // The heap just held these entries, putting them back doesn't grow it
+        for timer in skipped {
+            self.0
+                .push(&timer)
+                .expect("Unable to put back a skipped timer");
+        }
+
+        due.sort_by_key(key);
+
+        due
+    }
+
     pub fn clear_timer(&mut self) {
         while self.0.pop().is_some() {}
     }
@@ -75,6 +277,9 @@ impl<T: Storable + Clone> DefaultTaskTimer<T> {
             time: NanoTimeStamp::from(interval.0 + now),
             task,
             interval: Some(interval),
+            retry: TaskRetry::default(),
+            cron: None,
+            priority: 0,
         };
         self.push_timer(&timer)
     }
@@ -88,53 +293,128 @@ impl<T> PartialOrd for TaskTimerEntry<T> {
 
 impl<T> Ord for TaskTimerEntry<T> {
     fn cmp(&self, other: &Self) -> Ordering {
-        self.time.cmp(&other.time)
+        self.time
+            .cmp(&other.time)
+            .then_with(|| other.priority.cmp(&self.priority))
     }
 }
 
 impl<T> PartialEq for TaskTimerEntry<T> {
     fn eq(&self, other: &Self) -> bool {
-        self.time == other.time
+        self.time == other.time && self.priority == other.priority
     }
 }
 
 impl<T> Eq for TaskTimerEntry<T> {}
 
+impl<T> TaskTimerEntry<T> {
+    /// Current version of the binary layout, written as the first byte.
+    pub const LAYOUT_VERSION: u8 = 1;
+
+    /// Bytes reserved on top of the task size, leaving room for new fields
+    /// without changing the bound of existing heaps.
+    pub const LAYOUT_OVERHEAD: u32 = 64;
+
+    const FLAG_INTERVAL: u8 = 1;
+    const FLAG_CRON: u8 = 1 << 1;
+    const FLAG_PRIORITY: u8 = 1 << 2;
+}
+
+/// Layout version 1:
+///
+/// | bytes | field                                  |
+/// |-------|----------------------------------------|
+/// | 1     | layout version                         |
+/// | 1     | flags (interval, cron, priority)       |
+/// | 8     | time                                   |
+/// | 8     | retry                                  |
+/// | 8     | interval, only if its flag is set      |
+/// | 20    | cron, only if its flag is set          |
+/// | 1     | priority, only if its flag is set      |
+/// | ..    | task, until the end                    |
 impl<T: Storable> Storable for TaskTimerEntry<T> {
-    fn to_bytes(&self) -> Cow<[u8]> {
-        let time_bytes = self.time.to_le_bytes();
+    fn to_bytes(&self) -> Cow<'_, [u8]> {
         let task_bytes = self.task.to_bytes();
-        let interval_bytes = match &self.interval {
-            Some(interval) => interval.to_le_bytes().to_vec(),
-            None => vec![0; 16], // Use 16 bytes for consistency
-        };
-        let total_size = 8 + task_bytes.len() + 16;
-        let mut bytes = vec![0; total_size];
-        bytes[0..8].copy_from_slice(&time_bytes);
-        bytes[8..8 + task_bytes.len()].copy_from_slice(&task_bytes);
-        bytes[8 + task_bytes.len()..].copy_from_slice(&interval_bytes);
+
+        let mut flags = 0;
+        if self.interval.is_some() {
+            flags |= Self::FLAG_INTERVAL;
+        }
+        if self.cron.is_some() {
+            flags |= Self::FLAG_CRON;
+        }
+        if self.priority != 0 {
+            flags |= Self::FLAG_PRIORITY;
+        }
+
+        let mut bytes = Vec::with_capacity(47 + task_bytes.len());
+        bytes.push(Self::LAYOUT_VERSION);
+        bytes.push(flags);
+        bytes.extend_from_slice(&self.time.to_le_bytes());
+        bytes.extend_from_slice(&self.retry.to_le_bytes());
+        if let Some(interval) = &self.interval {
+            bytes.extend_from_slice(&interval.to_le_bytes());
+        }
+        if let Some(cron) = &self.cron {
+            bytes.extend_from_slice(&cron.to_le_bytes());
+        }
+        if self.priority != 0 {
+            bytes.push(self.priority);
+        }
+        bytes.extend_from_slice(&task_bytes);
         bytes.into()
     }
 
-    fn from_bytes(bytes: Cow<[u8]>) -> Self {
-        let time = NanoTimeStamp::from_le_bytes(bytes[0..8].try_into().unwrap());
-        let task_end = bytes.len() - 16;
-        let task = T::from_bytes(bytes[8..task_end].into());
-        let interval = if bytes[task_end..] == [0; 16] {
+    fn from_bytes(bytes: Cow<'_, [u8]>) -> Self {
+        match bytes[0] {
+            1 => {}
+            version => panic!("Unsupported TaskTimerEntry layout version: {}", version),
+        }
+
+        let flags = bytes[1];
+        let time = NanoTimeStamp::from_le_bytes(bytes[2..10].try_into().unwrap());
+        let retry = TaskRetry::from_le_bytes(bytes[10..18].try_into().unwrap());
+
+        let mut offset = 18;
+
+        let interval = if flags & Self::FLAG_INTERVAL != 0 {
+            let interval =
+                NanoTimeStamp::from_le_bytes(bytes[offset..offset + 8].try_into().unwrap());
+            offset += 8;
+            Some(interval)
+        } else {
+            None
+        };
+
+        let cron = if flags & Self::FLAG_CRON != 0 {
+            let cron = CronSchedule::from_le_bytes(bytes[offset..offset + 20].try_into().unwrap());
+            offset += 20;
+            Some(cron)
+        } else {
             None
+        };
+
+        let priority = if flags & Self::FLAG_PRIORITY != 0 {
+            offset += 1;
+            bytes[offset - 1]
         } else {
-            let nanos = u128::from_le_bytes(bytes[task_end..].try_into().unwrap());
-            Some(NanoTimeStamp(nanos as u64))
+            0
         };
+
+        let task = T::from_bytes(bytes[offset..].to_vec().into());
+
         Self {
             time,
             task,
             interval,
+            retry,
+            cron,
+            priority,
         }
     }
 
     const BOUND: Bound = Bound::Bounded {
         is_fixed_size: false,
-        max_size: 24 + T::BOUND.max_size(), // 8 for time, 16 for interval, plus task size
+        max_size: Self::LAYOUT_OVERHEAD + T::BOUND.max_size(),
     };
 }
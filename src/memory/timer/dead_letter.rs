@@ -0,0 +1,98 @@
+use candid::CandidType;
+use ic_stable_structures::{storable::Bound, Storable};
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+
+use crate::{
+    memory::{error::StableMemoryError, types::DefaultVM, NonceMap},
+    nonce::Nonce,
+    NanoTimeStamp,
+};
+
+/// A task that exhausted all of its attempts.
+#[derive(CandidType, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DeadLetter<T> {
+    pub task: T,
+    pub attempts: u32,
+    pub error: String,
+    pub scheduled_at: NanoTimeStamp,
+    pub failed_at: NanoTimeStamp,
+}
+
+/// Stable list of failed tasks, kept until a controller removes or requeues them.
+///
+/// Ids are never reused, even after a letter is removed or the queue is cleared.
+/// The queue uses two partitions: `{name}` at `id` and `{name}_nonce` at `id + 1`.
+pub struct DeadLetterQueue<T: Storable>(NonceMap<DeadLetter<T>>);
+
+impl<T: Storable + Clone> DeadLetterQueue<T> {
+    pub fn init(
+        letters_memory: DefaultVM,
+        nonce_memory: DefaultVM,
+    ) -> Result<Self, StableMemoryError> {
+        NonceMap::init(letters_memory, nonce_memory).map(Self)
+    }
+
+    pub fn push(&mut self, letter: DeadLetter<T>) -> Nonce {
+        self.0.push(letter).expect("Unable to push dead letter")
+    }
+
+    pub fn get(&self, id: &Nonce) -> Option<DeadLetter<T>> {
+        self.0.get(id)
+    }
+
+    pub fn remove(&mut self, id: &Nonce) -> Option<DeadLetter<T>> {
+        self.0.remove(id)
+    }
+
+    pub fn list(&self) -> Vec<(Nonce, DeadLetter<T>)> {
+        self.0.iter().collect()
+    }
+
+    pub fn len(&self) -> u64 {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.0.clear()
+    }
+}
+
+impl<T: Storable> Storable for DeadLetter<T> {
+    fn to_bytes(&self) -> Cow<'_, [u8]> {
+        let error_bytes = self.error.as_bytes();
+        let task_bytes = self.task.to_bytes();
+
+        let mut bytes = Vec::with_capacity(24 + error_bytes.len() + task_bytes.len());
+        bytes.extend_from_slice(&self.scheduled_at.to_le_bytes());
+        bytes.extend_from_slice(&self.failed_at.to_le_bytes());
+        bytes.extend_from_slice(&self.attempts.to_le_bytes());
+        bytes.extend_from_slice(&(error_bytes.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(error_bytes);
+        bytes.extend_from_slice(&task_bytes);
+        bytes.into()
+    }
+
+    fn from_bytes(bytes: Cow<'_, [u8]>) -> Self {
+        let scheduled_at = NanoTimeStamp::from_le_bytes(bytes[0..8].try_into().unwrap());
+        let failed_at = NanoTimeStamp::from_le_bytes(bytes[8..16].try_into().unwrap());
+        let attempts = u32::from_le_bytes(bytes[16..20].try_into().unwrap());
+        let error_len = u32::from_le_bytes(bytes[20..24].try_into().unwrap()) as usize;
+        let error = String::from_utf8_lossy(&bytes[24..24 + error_len]).into_owned();
+        let task = T::from_bytes(bytes[24 + error_len..].to_vec().into());
+
+        Self {
+            task,
+            attempts,
+            error,
+            scheduled_at,
+            failed_at,
+        }
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
@@ -0,0 +1,467 @@
+use std::{
+    cell::{Cell, RefCell},
+    collections::HashMap,
+    fmt,
+    future::Future,
+    pin::Pin,
+    rc::Rc,
+    thread::LocalKey,
+    time::Duration,
+};
+
+use ic_cdk_timers::TimerId;
+use ic_stable_structures::{GrowFailed, Storable};
+
+use super::{DeadLetter, DeadLetterQueue, DefaultTaskTimer, RetryPolicy, TaskTimerEntry};
+use crate::{api::Management, nonce::Nonce, CronSchedule, NanoTimeStamp};
+
+type TaskFuture = Pin<Box<dyn Future<Output = Result<(), String>>>>;
+type TaskHandler<T> = Rc<dyn Fn(T) -> TaskFuture>;
+type TaskKind<T> = Rc<dyn Fn(&T) -> String>;
+
+/// The thread-local a [`DefaultTaskTimer`] is usually declared in.
+pub type TaskTimerStore<T> = LocalKey<RefCell<DefaultTaskTimer<T>>>;
+
+/// The thread-local a [`DeadLetterQueue`] is usually declared in.
+pub type DeadLetterStore<T> = LocalKey<RefCell<DeadLetterQueue<T>>>;
+
+/// Drives a [`DefaultTaskTimer`] with the `ic-cdk-timers` global timer.
+///
+/// A single timer is kept armed for the earliest entry of the heap. When it fires,
+/// every due entry (up to `batch_size` per round) is popped and handed to the handler,
+/// then the next wake-up is armed again.
+///
+/// The armed timer lives on the heap, so `start` must be called from `#[init]` and
+/// `#[post_upgrade]`.
+///
+/// A failed task is rescheduled with the backoff of the [`RetryPolicy`] until its
+/// `max_attempts` are exhausted, then moved to the [`DeadLetterQueue`] if one is set.
+///
+/// Due tasks are dispatched by priority, aged by `priority_aging` so that low priority
+/// tasks eventually run. Tasks whose kind reached its concurrency limit, or that don't fit
+/// in the instruction budget of the round, stay in the heap for a later round.
+///
+/// # Example
+/// ```no_run
+/// use b3_utils::memory::{
+///     init_stable_mem_refcell,
+///     timer::{DeadLetterQueue, DefaultTaskTimer, RetryPolicy, TaskTimerExecutor},
+/// };
+/// use std::cell::RefCell;
+///
+/// thread_local! {
+///     static TASK_TIMER: RefCell<DefaultTaskTimer<u64>> = init_stable_mem_refcell("timer", 1).unwrap();
+///     static DEAD_LETTERS: RefCell<DeadLetterQueue<u64>> = init_stable_mem_refcell("dead_letters", 2).unwrap();
+///     static EXECUTOR: TaskTimerExecutor<u64> = TaskTimerExecutor::new(&TASK_TIMER, execute_task)
+///         .retry_policy(RetryPolicy::new(5))
+///         .dead_letters(&DEAD_LETTERS)
+///         .task_kind(|task| if task % 2 == 0 { "even".into() } else { "odd".into() })
+///         .concurrency_limit("odd", 1)
+///         .instruction_budget(1_000_000_000);
+/// }
+///
+/// async fn execute_task(task: u64) -> Result<(), String> {
+///     ic_cdk::println!("Executing task {}", task);
+///     Ok(())
+/// }
+///
+/// // #[init] and #[post_upgrade]
+/// EXECUTOR.with(|executor| executor.start());
+///
+/// // Anywhere in an update call
+/// EXECUTOR.with(|executor| executor.schedule_after_secs(10, 42)).unwrap();
+/// ```
+pub struct TaskTimerExecutor<T: Storable + 'static> {
+    timer: &'static TaskTimerStore<T>,
+    handler: TaskHandler<T>,
+    batch_size: usize,
+    retry_policy: Rc<RetryPolicy>,
+    dead_letters: Option<&'static DeadLetterStore<T>>,
+    armed: Rc<Cell<Option<(u64, TimerId)>>>,
+    scan_size: usize,
+    priority_aging: NanoTimeStamp,
+    instruction_budget: Option<u64>,
+    concurrency_delay: NanoTimeStamp,
+    task_kind: Option<TaskKind<T>>,
+    concurrency_limits: Rc<HashMap<String, usize>>,
+    running: Rc<RefCell<HashMap<String, usize>>>,
+}
+
+impl<T: Storable> Clone for TaskTimerExecutor<T> {
+    fn clone(&self) -> Self {
+        Self {
+            timer: self.timer,
+            handler: self.handler.clone(),
+            batch_size: self.batch_size,
+            retry_policy: self.retry_policy.clone(),
+            dead_letters: self.dead_letters,
+            armed: self.armed.clone(),
+            scan_size: self.scan_size,
+            priority_aging: self.priority_aging.clone(),
+            instruction_budget: self.instruction_budget,
+            concurrency_delay: self.concurrency_delay.clone(),
+            task_kind: self.task_kind.clone(),
+            concurrency_limits: self.concurrency_limits.clone(),
+            running: self.running.clone(),
+        }
+    }
+}
+
+impl<T: Storable + Clone + 'static> TaskTimerExecutor<T> {
+    pub const DEFAULT_BATCH_SIZE: usize = 10;
+    pub const DEFAULT_SCAN_SIZE: usize = 100;
+    pub const DEFAULT_PRIORITY_AGING: NanoTimeStamp = NanoTimeStamp(NanoTimeStamp::NS_PER_MINUTE);
+    pub const DEFAULT_CONCURRENCY_DELAY: NanoTimeStamp =
+        NanoTimeStamp(NanoTimeStamp::NS_PER_SECOND);
+
+    pub fn new<F, Fut, E>(timer: &'static TaskTimerStore<T>, handler: F) -> Self
+    where
+        F: Fn(T) -> Fut + 'static,
+        Fut: Future<Output = Result<(), E>> + 'static,
+        E: fmt::Display,
+    {
+        let handler: TaskHandler<T> = Rc::new(move |task| {
+            let future = handler(task);
+
+            Box::pin(async move { future.await.map_err(|e| e.to_string()) })
+        });
+
+        Self {
+            timer,
+            handler,
+            batch_size: Self::DEFAULT_BATCH_SIZE,
+            retry_policy: Rc::new(RetryPolicy::default()),
+            dead_letters: None,
+            armed: Rc::new(Cell::new(None)),
+            scan_size: Self::DEFAULT_SCAN_SIZE,
+            priority_aging: Self::DEFAULT_PRIORITY_AGING,
+            instruction_budget: None,
+            concurrency_delay: Self::DEFAULT_CONCURRENCY_DELAY,
+            task_kind: None,
+            concurrency_limits: Rc::new(HashMap::new()),
+            running: Rc::new(RefCell::new(HashMap::new())),
+        }
+    }
+
+    /// Sets the maximum number of tasks dispatched per round.
+    pub fn batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    /// Sets the backoff and the default number of attempts of the scheduled tasks.
+    pub fn retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = Rc::new(retry_policy);
+        self
+    }
+
+    /// Sets the queue receiving the tasks that exhausted all of their attempts.
+    pub fn dead_letters(mut self, dead_letters: &'static DeadLetterStore<T>) -> Self {
+        self.dead_letters = Some(dead_letters);
+        self
+    }
+
+    /// Sets the maximum number of due tasks taken per round, picked by priority among all due tasks.
+    pub fn scan_size(mut self, scan_size: usize) -> Self {
+        self.scan_size = scan_size.max(1);
+        self
+    }
+
+    /// Sets the waiting time after which a due task gains one priority level, zero disables aging.
+    pub fn priority_aging(mut self, priority_aging: NanoTimeStamp) -> Self {
+        self.priority_aging = priority_aging;
+        self
+    }
+
+    /// Stops dispatching tasks in a round once the instruction counter reaches `budget`.
+    pub fn instruction_budget(mut self, budget: u64) -> Self {
+        self.instruction_budget = Some(budget);
+        self
+    }
+
+    /// Sets how tasks are grouped for the concurrency limits.
+    pub fn task_kind<F>(mut self, task_kind: F) -> Self
+    where
+        F: Fn(&T) -> String + 'static,
+    {
+        self.task_kind = Some(Rc::new(task_kind));
+        self
+    }
+
+    /// Limits the number of tasks of the given kind running at the same time.
+    pub fn concurrency_limit(mut self, kind: impl Into<String>, limit: usize) -> Self {
+        Rc::make_mut(&mut self.concurrency_limits).insert(kind.into(), limit.max(1));
+        self
+    }
+
+    /// Sets the delay before checking again tasks blocked by a concurrency limit,
+    /// they are also checked as soon as a task of a limited kind completes.
+    pub fn concurrency_delay(mut self, delay: NanoTimeStamp) -> Self {
+        self.concurrency_delay = delay;
+        self
+    }
+
+    /// Returns the number of running tasks of the given kind.
+    pub fn running(&self, kind: &str) -> usize {
+        self.running.borrow().get(kind).copied().unwrap_or(0)
+    }
+
+    /// Arms the timer for the earliest task, should be called from `#[init]` and `#[post_upgrade]`.
+    pub fn start(&self) {
+        self.arm();
+    }
+
+    /// Clears the armed timer, the tasks stay in the heap.
+    pub fn stop(&self) {
+        if let Some((_, timer_id)) = self.armed.take() {
+            ic_cdk_timers::clear_timer(timer_id);
+        }
+    }
+
+    /// Returns the time of the next wake-up, if any.
+    pub fn next_wake_up(&self) -> Option<NanoTimeStamp> {
+        self.armed.get().map(|(time, _)| NanoTimeStamp(time))
+    }
+
+    pub fn schedule(&self, timer: TaskTimerEntry<T>) -> Result<(), GrowFailed> {
+        self.timer.with(|tt| tt.borrow_mut().push_timer(&timer))?;
+
+        self.arm();
+
+        Ok(())
+    }
+
+    pub fn schedule_at(&self, time: NanoTimeStamp, task: T) -> Result<(), GrowFailed> {
+        self.schedule(
+            TaskTimerEntry::new(time, task).with_max_attempts(self.retry_policy.max_attempts),
+        )
+    }
+
+    pub fn schedule_after_secs(&self, secs: u64, task: T) -> Result<(), GrowFailed> {
+        self.schedule_at(NanoTimeStamp::now().add_secs(secs), task)
+    }
+
+    pub fn schedule_interval(&self, interval: NanoTimeStamp, task: T) -> Result<(), GrowFailed> {
+        self.schedule(
+            TaskTimerEntry::new(NanoTimeStamp::now() + interval.clone(), task)
+                .with_interval(interval)
+                .with_max_attempts(self.retry_policy.max_attempts),
+        )
+    }
+
+    /// Schedules a task at every occurrence of the calendar schedule.
+    pub fn schedule_cron(&self, schedule: CronSchedule, task: T) -> Result<(), GrowFailed> {
+        match TaskTimerEntry::cron(schedule, task, &NanoTimeStamp::now()) {
+            Some(timer) => self.schedule(timer.with_max_attempts(self.retry_policy.max_attempts)),
+            None => Ok(()),
+        }
+    }
+
+    /// Removes a task from the dead-letter queue and schedules it again with fresh attempts.
+    pub fn requeue_dead_letter(&self, id: &Nonce) -> Result<Option<T>, GrowFailed> {
+        let letter = match self.dead_letters {
+            Some(dead_letters) => dead_letters.with(|dl| dl.borrow_mut().remove(id)),
+            None => None,
+        };
+
+        match letter {
+            Some(letter) => {
+                self.schedule_at(NanoTimeStamp::now(), letter.task.clone())?;
+
+                Ok(Some(letter.task))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Dispatches the due tasks of this round and arms the next wake-up.
+    pub fn run(&self) {
+        self.armed.set(None);
+
+        let now = NanoTimeStamp::now();
+
+        let due = self.timer.with(|tt| {
+            tt.borrow_mut()
+                .pop_due_by_priority(&now, self.scan_size, &self.priority_aging)
+        });
+
+        let mut dispatched = 0;
+        let mut deferred = Vec::new();
+        let mut blocked = false;
+
+        for timer in due {
+            if dispatched >= self.batch_size || self.over_budget() {
+                deferred.push(timer);
+                continue;
+            }
+
+            let kind = match self.acquire(&timer.task) {
+                Ok(kind) => kind,
+                Err(()) => {
+                    blocked = true;
+                    deferred.push(timer);
+                    continue;
+                }
+            };
+
+            if self
+                .timer
+                .with(|tt| tt.borrow_mut().reschedule(&timer))
+                .is_err()
+            {
+                ic_cdk::println!("Unable to reschedule recurring task");
+            }
+
+            dispatched += 1;
+
+            let executor = self.clone();
+
+            ic_cdk::spawn(async move {
+                let result = (executor.handler)(timer.task.clone()).await;
+
+                if let Some(kind) = kind {
+                    executor.release(&kind);
+                }
+
+                if let Err(err) = result {
+                    executor.fail(timer, err).await;
+                }
+            });
+        }
+
+        let ready = !deferred.is_empty() && (dispatched >= self.batch_size || self.over_budget());
+
+        self.timer.with(|tt| {
+            let mut tt = tt.borrow_mut();
+
+            for timer in deferred {
+                if tt.push_timer(&timer).is_err() {
+                    ic_cdk::println!("Unable to defer task scheduled at {}", timer.time);
+                }
+            }
+        });
+
+        // Tasks blocked only by their concurrency limit are checked again later
+        // or as soon as a slot is released.
+        if blocked && !ready {
+            self.arm_not_before(now + self.concurrency_delay.clone());
+        } else {
+            self.arm();
+        }
+    }
+
+    fn over_budget(&self) -> bool {
+        match self.instruction_budget {
+            Some(budget) => ic_cdk::api::instruction_counter() >= budget,
+            None => false,
+        }
+    }
+
+    /// Takes a slot for the kind of the task, returns the kind if it is limited.
+    fn acquire(&self, task: &T) -> Result<Option<String>, ()> {
+        let kind = match &self.task_kind {
+            Some(task_kind) => task_kind(task),
+            None => return Ok(None),
+        };
+
+        let limit = match self.concurrency_limits.get(&kind) {
+            Some(limit) => *limit,
+            None => return Ok(None),
+        };
+
+        let mut running = self.running.borrow_mut();
+        let count = running.entry(kind.clone()).or_default();
+
+        if *count >= limit {
+            return Err(());
+        }
+
+        *count += 1;
+
+        Ok(Some(kind))
+    }
+
+    fn release(&self, kind: &str) {
+        if let Some(count) = self.running.borrow_mut().get_mut(kind) {
+            *count = count.saturating_sub(1);
+        }
+
+        self.arm();
+    }
+
+    async fn fail(&self, mut timer: TaskTimerEntry<T>, error: String) {
+        if timer.retry.fail() {
+            let random = if self.retry_policy.jitter {
+                Management::raw_rand()
+                    .await
+                    .ok()
+                    .and_then(|bytes| bytes.get(0..8).map(|b| b.try_into().unwrap()))
+                    .map(u64::from_le_bytes)
+            } else {
+                None
+            };
+
+            let delay = self.retry_policy.backoff(timer.retry.attempts, random);
+
+            let retry = TaskTimerEntry {
+                time: NanoTimeStamp::now() + delay,
+                task: timer.task.clone(),
+                interval: None,
+                retry: timer.retry,
+                cron: None,
+                priority: timer.priority,
+            };
+
+            if self.schedule(retry).is_ok() {
+                return;
+            }
+
+            ic_cdk::println!("Unable to reschedule task, moving it to the dead letters");
+        }
+
+        let letter = DeadLetter {
+            task: timer.task,
+            attempts: timer.retry.attempts,
+            error,
+            scheduled_at: timer.time,
+            failed_at: NanoTimeStamp::now(),
+        };
+
+        match self.dead_letters {
+            Some(dead_letters) => {
+                dead_letters.with(|dl| dl.borrow_mut().push(letter));
+            }
+            None => ic_cdk::println!(
+                "Task scheduled at {} failed after {} attempts: {}",
+                letter.scheduled_at,
+                letter.attempts,
+                letter.error
+            ),
+        }
+    }
+
+    fn arm(&self) {
+        self.arm_not_before(NanoTimeStamp(0));
+    }
+
+    fn arm_not_before(&self, not_before: NanoTimeStamp) {
+        let next = match self.timer.with(|tt| tt.borrow().peek_timer()) {
+            Some(next) => next.time.max(not_before),
+            None => return self.stop(),
+        };
+
+        if let Some((time, _)) = self.armed.get() {
+            if time <= next.0 {
+                return;
+            }
+        }
+
+        self.stop();
+
+        let delay = Duration::from_nanos(next.time_until());
+        let executor = self.clone();
+        let timer_id = ic_cdk_timers::set_timer(delay, move || executor.run());
+
+        self.armed.set(Some((next.0, timer_id)));
+    }
+}
@@ -0,0 +1,82 @@
+use ic_stable_structures::{storable::Bound, Storable};
+use std::{borrow::Cow, cmp::Ordering};
+
+use super::{TaskRetry, TaskTimerEntry};
+use crate::NanoTimeStamp;
+
+/// Entry written by the unversioned layout: time, task, then 16 bytes of interval.
+/// Only used to read heaps created before the layout was versioned.
+pub(super) struct LegacyTaskTimerEntry<T> {
+    pub(super) time: NanoTimeStamp,
+    pub(super) task: T,
+    pub(super) interval: Option<NanoTimeStamp>,
+}
+
+impl<T> From<LegacyTaskTimerEntry<T>> for TaskTimerEntry<T> {
+    fn from(legacy: LegacyTaskTimerEntry<T>) -> Self {
+        Self {
+            time: legacy.time,
+            task: legacy.task,
+            interval: legacy.interval,
+            retry: TaskRetry::default(),
+            cron: None,
+            priority: 0,
+        }
+    }
+}
+
+impl<T> PartialOrd for LegacyTaskTimerEntry<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T> Ord for LegacyTaskTimerEntry<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.time.cmp(&other.time)
+    }
+}
+
+impl<T> PartialEq for LegacyTaskTimerEntry<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.time == other.time
+    }
+}
+
+impl<T> Eq for LegacyTaskTimerEntry<T> {}
+
+impl<T: Storable> Storable for LegacyTaskTimerEntry<T> {
+    fn to_bytes(&self) -> Cow<'_, [u8]> {
+        let task_bytes = self.task.to_bytes();
+        let mut bytes = vec![0; 8 + task_bytes.len() + 16];
+        bytes[0..8].copy_from_slice(&self.time.to_le_bytes());
+        bytes[8..8 + task_bytes.len()].copy_from_slice(&task_bytes);
+        if let Some(interval) = &self.interval {
+            let start = 8 + task_bytes.len();
+            bytes[start..start + 8].copy_from_slice(&interval.to_le_bytes());
+        }
+        bytes.into()
+    }
+
+    fn from_bytes(bytes: Cow<'_, [u8]>) -> Self {
+        let time = NanoTimeStamp::from_le_bytes(bytes[0..8].try_into().unwrap());
+        let task_end = bytes.len() - 16;
+        let task = T::from_bytes(bytes[8..task_end].to_vec().into());
+        let interval = if bytes[task_end..] == [0; 16] {
+            None
+        } else {
+            let nanos = u128::from_le_bytes(bytes[task_end..].try_into().unwrap());
+            Some(NanoTimeStamp(nanos as u64))
+        };
+        Self {
+            time,
+            task,
+            interval,
+        }
+    }
+
+    const BOUND: Bound = Bound::Bounded {
+        is_fixed_size: false,
+        max_size: 24 + T::BOUND.max_size(),
+    };
+}
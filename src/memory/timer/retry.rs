@@ -0,0 +1,119 @@
+use candid::CandidType;
+use serde::{Deserialize, Serialize};
+
+use crate::NanoTimeStamp;
+
+/// Retry metadata carried by every [`TaskTimerEntry`](super::TaskTimerEntry).
+#[derive(CandidType, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TaskRetry {
+    /// Number of failed attempts so far.
+    pub attempts: u32,
+    /// Total number of attempts allowed, `1` means the task is never retried.
+    pub max_attempts: u32,
+}
+
+impl Default for TaskRetry {
+    fn default() -> Self {
+        Self {
+            attempts: 0,
+            max_attempts: 1,
+        }
+    }
+}
+
+impl TaskRetry {
+    pub fn new(max_attempts: u32) -> Self {
+        Self {
+            attempts: 0,
+            max_attempts: max_attempts.max(1),
+        }
+    }
+
+    /// Records a failed attempt and returns true if the task can run again.
+    pub fn fail(&mut self) -> bool {
+        self.attempts = self.attempts.saturating_add(1);
+
+        self.attempts < self.max_attempts
+    }
+
+    pub fn to_le_bytes(&self) -> [u8; 8] {
+        let mut bytes = [0u8; 8];
+        bytes[0..4].copy_from_slice(&self.attempts.to_le_bytes());
+        bytes[4..8].copy_from_slice(&self.max_attempts.to_le_bytes());
+        bytes
+    }
+
+    pub fn from_le_bytes(bytes: [u8; 8]) -> Self {
+        Self {
+            attempts: u32::from_le_bytes(bytes[0..4].try_into().unwrap()),
+            max_attempts: u32::from_le_bytes(bytes[4..8].try_into().unwrap()),
+        }
+    }
+}
+
+/// Exponential backoff used to reschedule failed tasks.
+#[derive(CandidType, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RetryPolicy {
+    /// Default number of attempts for tasks scheduled through the executor helpers.
+    pub max_attempts: u32,
+    /// Delay before the first retry, doubled on every following attempt.
+    pub base_delay: NanoTimeStamp,
+    /// Upper bound of the delay.
+    pub max_delay: NanoTimeStamp,
+    /// Randomize the second half of the delay using `raw_rand`.
+    pub jitter: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 1,
+            base_delay: NanoTimeStamp(NanoTimeStamp::NS_PER_SECOND),
+            max_delay: NanoTimeStamp(NanoTimeStamp::NS_PER_HOUR),
+            jitter: true,
+        }
+    }
+}
+
+impl RetryPolicy {
+    pub fn new(max_attempts: u32) -> Self {
+        Self {
+            max_attempts: max_attempts.max(1),
+            ..Default::default()
+        }
+    }
+
+    pub fn base_delay(mut self, base_delay: NanoTimeStamp) -> Self {
+        self.base_delay = base_delay;
+        self
+    }
+
+    pub fn max_delay(mut self, max_delay: NanoTimeStamp) -> Self {
+        self.max_delay = max_delay;
+        self
+    }
+
+    pub fn jitter(mut self, jitter: bool) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    /// Returns the delay before the next attempt, given the number of failed attempts.
+    /// When `random` is provided, the delay is picked between half and the full backoff.
+    pub fn backoff(&self, attempts: u32, random: Option<u64>) -> NanoTimeStamp {
+        let exponent = attempts.saturating_sub(1).min(63);
+        let delay = self
+            .base_delay
+            .0
+            .saturating_mul(1u64 << exponent)
+            .min(self.max_delay.0);
+
+        match random {
+            Some(random) if self.jitter => {
+                let half = delay / 2;
+                NanoTimeStamp(half + random % (delay - half + 1))
+            }
+            _ => NanoTimeStamp(delay),
+        }
+    }
+}
@@ -4,7 +4,19 @@ mod tests {
 
     use ic_stable_structures::{storable::Bound, Storable};
 
-    use crate::memory::timer::TaskTimerEntry;
+    use proptest::prelude::*;
+
+    use crate::{
+        memory::{
+            timer::{
+                legacy::LegacyTaskTimerEntry, DeadLetter, DeadLetterQueue, DefaultTaskTimer,
+                RetryPolicy, TaskRetry, TaskTimerEntry,
+            },
+            types::DefaultStableMinHeap,
+            StableMemoryManager,
+        },
+        CronSchedule, NanoTimeStamp,
+    };
 
     #[test]
     fn test_timer_entry_to_and_from_bytes() {
@@ -44,14 +56,10 @@ mod tests {
             }
         }
 
-        let entry = TaskTimerEntry {
-            time: 1234567890.into(),
-            task: TestTask::A,
-            interval: None,
-        };
+        let entry = TaskTimerEntry::new(1234567890.into(), TestTask::A);
 
         let bytes = entry.to_bytes();
-        assert_eq!(bytes.len(), 32);
+        assert_eq!(bytes.len(), 26);
 
         let entry_from_bytes = TaskTimerEntry::from_bytes(bytes);
 
@@ -59,25 +67,17 @@ mod tests {
         assert_eq!(entry_from_bytes.time, 1234567890.into());
         assert_eq!(entry_from_bytes.task, TestTask::A);
 
-        let entry = TaskTimerEntry {
-            time: 1234567890.into(),
-            task: TestTask::B,
-            interval: None,
-        };
+        let entry = TaskTimerEntry::new(1234567890.into(), TestTask::B);
 
         let bytes = entry.to_bytes();
-        assert_eq!(bytes.len(), 32);
+        assert_eq!(bytes.len(), 26);
 
         let entry_from_bytes = TaskTimerEntry::from_bytes(bytes);
 
         assert_eq!(entry, entry_from_bytes);
         assert_eq!(entry_from_bytes.time, 1234567890.into());
 
-        let entry = TaskTimerEntry {
-            time: 1234567890.into(),
-            task: TestTask::C("Hello World!".to_string()),
-            interval: None,
-        };
+        let entry = TaskTimerEntry::new(1234567890.into(), TestTask::C("Hello World!".to_string()));
 
         let bytes = entry.to_bytes();
         assert!(bytes.len() < size_of::<TaskTimerEntry<TestTask>>());
@@ -91,4 +91,408 @@ mod tests {
             TestTask::C("Hello World!".to_string())
         );
     }
+
+    #[test]
+    fn test_pop_due_timers() {
+        let mut stable_memory = StableMemoryManager::init();
+
+        let mut timer: DefaultTaskTimer<u64> = stable_memory.init_memory("test_timer", 10).unwrap();
+
+        for (time, task) in [(30, 3), (10, 1), (20, 2), (40, 4)] {
+            timer
+                .push_timer(&TaskTimerEntry::new(NanoTimeStamp(time), task))
+                .unwrap();
+        }
+
+        let due = timer.pop_due_timers(&NanoTimeStamp(30), 2);
+
+        assert_eq!(due.iter().map(|t| t.task).collect::<Vec<_>>(), vec![1, 2]);
+
+        let due = timer.pop_due_timers(&NanoTimeStamp(30), 10);
+
+        assert_eq!(due.iter().map(|t| t.task).collect::<Vec<_>>(), vec![3]);
+        assert_eq!(timer.peek_timer().unwrap().task, 4);
+    }
+
+    #[test]
+    fn test_timer_entry_priority() {
+        let entry = TaskTimerEntry::new(NanoTimeStamp(10), 1u64)
+            .with_interval(NanoTimeStamp(5))
+            .with_priority(7);
+
+        let entry_from_bytes = TaskTimerEntry::<u64>::from_bytes(entry.to_bytes());
+
+        assert_eq!(entry_from_bytes.priority, 7);
+        assert_eq!(entry_from_bytes.interval, Some(NanoTimeStamp(5)));
+        assert_eq!(entry_from_bytes.task, 1);
+
+        // Higher priority wins ties on time
+        let low = TaskTimerEntry::new(NanoTimeStamp(10), 1u64);
+
+        assert!(entry < low);
+        assert!(low < TaskTimerEntry::new(NanoTimeStamp(11), 1u64).with_priority(9));
+
+        // One level per aging period
+        assert_eq!(
+            low.effective_priority(&NanoTimeStamp(35), &NanoTimeStamp(10)),
+            2
+        );
+        assert_eq!(
+            low.effective_priority(&NanoTimeStamp(35), &NanoTimeStamp(0)),
+            0
+        );
+        assert_eq!(
+            low.effective_priority(&NanoTimeStamp(5), &NanoTimeStamp(10)),
+            0
+        );
+    }
+
+    #[test]
+    fn test_pop_due_by_priority() {
+        let mut stable_memory = StableMemoryManager::init();
+
+        let mut timer: DefaultTaskTimer<u64> = stable_memory.init_memory("test_timer", 10).unwrap();
+
+        for (time, task, priority) in [(10, 1, 0), (20, 2, 5), (25, 3, 1), (40, 4, 9)] {
+            timer
+                .push_timer(&TaskTimerEntry::new(NanoTimeStamp(time), task).with_priority(priority))
+                .unwrap();
+        }
+
+        timer
+            .push_timer(
+                &TaskTimerEntry::new(NanoTimeStamp(15), 5).with_interval(NanoTimeStamp(100)),
+            )
+            .unwrap();
+
+        let due = timer.pop_due_by_priority(&NanoTimeStamp(30), 10, &NanoTimeStamp(0));
+
+        assert_eq!(
+            due.iter().map(|t| t.task).collect::<Vec<_>>(),
+            vec![2, 3, 1, 5]
+        );
+
+        // Recurring entries are only rescheduled on demand
+        assert_eq!(timer.get_timers().len(), 1);
+
+        timer.reschedule(&due[3]).unwrap();
+
+        assert_eq!(timer.peek_timer().unwrap().time, NanoTimeStamp(40));
+        assert_eq!(timer.peek_timer().unwrap().task, 4);
+
+        for timer_entry in due.iter().take(3) {
+            timer.push_timer(timer_entry).unwrap();
+        }
+
+        // Aging lets the oldest entry overtake a higher priority
+        let due = timer.pop_due_by_priority(&NanoTimeStamp(70), 10, &NanoTimeStamp(10));
+
+        assert_eq!(
+            due.iter().map(|t| t.task).collect::<Vec<_>>(),
+            vec![4, 2, 1, 3]
+        );
+    }
+
+    #[test]
+    fn test_pop_due_by_priority_past_limit() {
+        let mut stable_memory = StableMemoryManager::init();
+
+        let mut timer: DefaultTaskTimer<u64> = stable_memory.init_memory("test_timer", 10).unwrap();
+
+        // More low priority entries are due before the critical one than fit in a round
+        for task in 0..150 {
+            timer
+                .push_timer(&TaskTimerEntry::new(NanoTimeStamp(task), task))
+                .unwrap();
+        }
+
+        timer
+            .push_timer(&TaskTimerEntry::new(NanoTimeStamp(200), 1000).with_priority(9))
+            .unwrap();
+
+        let due = timer.pop_due_by_priority(&NanoTimeStamp(300), 100, &NanoTimeStamp(0));
+
+        assert_eq!(due.len(), 100);
+        assert_eq!(due[0].task, 1000);
+        assert_eq!(
+            due[1..].iter().map(|t| t.task).collect::<Vec<_>>(),
+            (0..99).collect::<Vec<_>>()
+        );
+
+        // The skipped entries stay in the heap
+        assert_eq!(timer.get_timers().len(), 51);
+        assert_eq!(timer.peek_timer().unwrap().task, 99);
+    }
+
+    #[test]
+    fn test_timer_entry_retry_to_and_from_bytes() {
+        let mut entry = TaskTimerEntry::new(NanoTimeStamp(10), 7u64).with_max_attempts(3);
+
+        assert!(entry.retry.fail());
+
+        let entry_from_bytes = TaskTimerEntry::<u64>::from_bytes(entry.to_bytes());
+
+        assert_eq!(entry_from_bytes.task, 7);
+        assert_eq!(
+            entry_from_bytes.retry,
+            TaskRetry {
+                attempts: 1,
+                max_attempts: 3
+            }
+        );
+    }
+
+    #[test]
+    fn test_task_retry() {
+        let mut retry = TaskRetry::default();
+
+        assert!(!retry.fail());
+
+        let mut retry = TaskRetry::new(3);
+
+        assert!(retry.fail());
+        assert!(retry.fail());
+        assert!(!retry.fail());
+        assert_eq!(retry.attempts, 3);
+    }
+
+    #[test]
+    fn test_retry_policy_backoff() {
+        let policy = RetryPolicy::new(10)
+            .base_delay(NanoTimeStamp(100))
+            .max_delay(NanoTimeStamp(1_000));
+
+        assert_eq!(policy.backoff(1, None), NanoTimeStamp(100));
+        assert_eq!(policy.backoff(2, None), NanoTimeStamp(200));
+        assert_eq!(policy.backoff(3, None), NanoTimeStamp(400));
+        assert_eq!(policy.backoff(5, None), NanoTimeStamp(1_000));
+        assert_eq!(policy.backoff(u32::MAX, None), NanoTimeStamp(1_000));
+
+        for random in [0, 1, 99, 12345, u64::MAX] {
+            let delay = policy.backoff(2, Some(random));
+
+            assert!(delay >= NanoTimeStamp(100) && delay <= NanoTimeStamp(200));
+        }
+
+        let policy = policy.jitter(false);
+
+        assert_eq!(policy.backoff(2, Some(12345)), NanoTimeStamp(200));
+    }
+
+    #[test]
+    fn test_dead_letter_queue() {
+        let mut stable_memory = StableMemoryManager::init();
+
+        let mut queue: DeadLetterQueue<u64> =
+            stable_memory.init_memory("test_letters", 11).unwrap();
+
+        let letter = DeadLetter {
+            task: 42,
+            attempts: 3,
+            error: "Outcall failed".to_string(),
+            scheduled_at: NanoTimeStamp(10),
+            failed_at: NanoTimeStamp(20),
+        };
+
+        let first = queue.push(letter.clone());
+        let second = queue.push(letter.clone());
+
+        assert_ne!(first, second);
+        assert_eq!(queue.len(), 2);
+        assert_eq!(queue.get(&first), Some(letter.clone()));
+
+        assert_eq!(queue.remove(&first), Some(letter.clone()));
+        assert_eq!(queue.list().len(), 1);
+
+        queue.clear();
+
+        assert!(queue.is_empty());
+
+        // Ids keep increasing after a clear, an old id can't point to a new letter
+        let third = queue.push(letter.clone());
+
+        assert_ne!(third, first);
+        assert_ne!(third, second);
+        assert_eq!(queue.get(&second), None);
+    }
+
+    #[test]
+    fn test_pop_due_timers_reschedules_cron() {
+        let mut stable_memory = StableMemoryManager::init();
+
+        let mut timer: DefaultTaskTimer<u64> = stable_memory.init_memory("test_timer", 10).unwrap();
+
+        let schedule = CronSchedule::daily(0, 0).unwrap();
+
+        // 2024-01-15T10:30:00Z
+        let now = NanoTimeStamp(1_705_314_600 * NanoTimeStamp::NS_PER_SECOND);
+        let entry = TaskTimerEntry::cron(schedule, 1, &now).unwrap();
+
+        timer.push_timer(&entry).unwrap();
+
+        let day = NanoTimeStamp(NanoTimeStamp::NS_PER_DAY);
+
+        let due = timer.pop_due_timers(&(entry.time.clone() + day.clone()), 10);
+
+        assert_eq!(due.len(), 2);
+        assert_eq!(due[0].time, entry.time);
+        assert_eq!(due[1].time, entry.time.clone() + day.clone());
+        assert_eq!(due[1].cron, Some(schedule));
+
+        let next = timer.peek_timer().unwrap();
+
+        assert_eq!(next.time, entry.time + day.clone() + day);
+        assert_eq!(next.cron, Some(schedule));
+    }
+
+    #[test]
+    fn test_timer_entry_zero_interval() {
+        let entry = TaskTimerEntry::new(NanoTimeStamp(10), 1u64).with_interval(NanoTimeStamp(0));
+
+        let entry_from_bytes = TaskTimerEntry::<u64>::from_bytes(entry.to_bytes());
+
+        assert_eq!(entry_from_bytes.interval, Some(NanoTimeStamp(0)));
+
+        let entry = TaskTimerEntry::new(NanoTimeStamp(10), 1u64);
+
+        let entry_from_bytes = TaskTimerEntry::<u64>::from_bytes(entry.to_bytes());
+
+        assert_eq!(entry_from_bytes.interval, None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Unsupported TaskTimerEntry layout version")]
+    fn test_timer_entry_unknown_version() {
+        let mut bytes = TaskTimerEntry::new(NanoTimeStamp(10), 1u64)
+            .to_bytes()
+            .into_owned();
+
+        bytes[0] = 99;
+
+        TaskTimerEntry::<u64>::from_bytes(bytes.into());
+    }
+
+    #[test]
+    fn test_pop_due_timers_reschedules_interval() {
+        let mut stable_memory = StableMemoryManager::init();
+
+        let mut timer: DefaultTaskTimer<u64> = stable_memory.init_memory("test_timer", 10).unwrap();
+
+        timer
+            .push_timer(&TaskTimerEntry::new(NanoTimeStamp(10), 1).with_interval(NanoTimeStamp(5)))
+            .unwrap();
+
+        let due = timer.pop_due_timers(&NanoTimeStamp(12), 10);
+
+        assert_eq!(due.len(), 1);
+        assert_eq!(timer.peek_timer().unwrap().time, NanoTimeStamp(15));
+        assert_eq!(timer.peek_timer().unwrap().interval, Some(NanoTimeStamp(5)));
+    }
+
+    #[test]
+    fn test_migrate_legacy_heap() {
+        let stable_memory = StableMemoryManager::init();
+
+        let vm = stable_memory.get(12);
+
+        let mut legacy: DefaultStableMinHeap<LegacyTaskTimerEntry<u64>> =
+            DefaultStableMinHeap::init(vm.clone()).unwrap();
+
+        for (time, task) in [(30, 3), (10, 1), (20, 2)] {
+            legacy
+                .push(&LegacyTaskTimerEntry {
+                    time: NanoTimeStamp(time),
+                    task,
+                    interval: None,
+                })
+                .unwrap();
+        }
+
+        drop(legacy);
+
+        let mut timer = DefaultTaskTimer::<u64>::init(vm.clone()).unwrap();
+
+        assert_eq!(timer.get_timers().len(), 3);
+
+        let due = timer.pop_due_timers(&NanoTimeStamp(30), 10);
+
+        assert_eq!(
+            due.iter().map(|t| t.task).collect::<Vec<_>>(),
+            vec![1, 2, 3]
+        );
+        assert!(due.iter().all(|t| t.interval.is_none() && t.cron.is_none()));
+
+        timer
+            .push_timer(&TaskTimerEntry::new(NanoTimeStamp(40), 4))
+            .unwrap();
+
+        drop(timer);
+
+        // Already migrated heaps are loaded as they are
+        let timer = DefaultTaskTimer::<u64>::init(vm).unwrap();
+
+        assert_eq!(timer.peek_timer().unwrap().task, 4);
+    }
+
+    fn arb_timer_entry() -> impl Strategy<Value = TaskTimerEntry<[u8; 32]>> {
+        (
+            any::<u64>(),
+            any::<[u8; 32]>(),
+            proptest::option::of(any::<u64>()),
+            any::<u32>(),
+            any::<u32>(),
+            proptest::option::of(any::<[u8; 20]>()),
+            any::<u8>(),
+        )
+            .prop_map(
+                |(time, task, interval, attempts, max_attempts, cron, priority)| TaskTimerEntry {
+                    time: NanoTimeStamp(time),
+                    task,
+                    interval: interval.map(NanoTimeStamp),
+                    retry: TaskRetry {
+                        attempts,
+                        max_attempts,
+                    },
+                    cron: cron.map(CronSchedule::from_le_bytes),
+                    priority,
+                },
+            )
+    }
+
+    proptest! {
+        #[test]
+        fn proptest_timer_entry_round_trip(entry in arb_timer_entry()) {
+            let bytes = entry.to_bytes();
+
+            prop_assert!(bytes.len() <= TaskTimerEntry::<[u8; 32]>::BOUND.max_size() as usize);
+
+            let entry_from_bytes = TaskTimerEntry::<[u8; 32]>::from_bytes(bytes);
+
+            prop_assert_eq!(entry_from_bytes.time, entry.time);
+            prop_assert_eq!(entry_from_bytes.task, entry.task);
+            prop_assert_eq!(entry_from_bytes.interval, entry.interval);
+            prop_assert_eq!(entry_from_bytes.retry, entry.retry);
+            prop_assert_eq!(entry_from_bytes.cron, entry.cron);
+            prop_assert_eq!(entry_from_bytes.priority, entry.priority);
+        }
+
+        #[test]
+        fn proptest_legacy_timer_entry_migration(
+            time in any::<u64>(),
+            task in any::<[u8; 32]>(),
+        ) {
+            let legacy = LegacyTaskTimerEntry {
+                time: NanoTimeStamp(time),
+                task,
+                interval: None,
+            };
+
+            let entry = TaskTimerEntry::from(LegacyTaskTimerEntry::<[u8; 32]>::from_bytes(legacy.to_bytes()));
+
+            prop_assert_eq!(entry.time, NanoTimeStamp(time));
+            prop_assert_eq!(entry.task, task);
+            prop_assert_eq!(entry.interval, None);
+            prop_assert_eq!(entry.retry, TaskRetry::default());
+        }
+    }
 }
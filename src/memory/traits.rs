@@ -1,10 +1,8 @@
 use super::{
     error::StableMemoryError,
-    timer::DefaultTaskTimer,
-    types::{
-        DefaultStableBTreeMap, DefaultStableCell, DefaultStableLog, DefaultStableMinHeap,
-        DefaultStableVec, DefaultVM,
-    },
+    timer::{DeadLetterQueue, DefaultTaskTimer},
+    ttl::TtlMap,
+    NonceMap,
 };
 
 pub use ic_stable_structures::{
@@ -14,9 +12,9 @@ pub use ic_stable_structures::{
 };
 
 #[rustfmt::skip]
-pub enum InitMemoryArg {
-    Single(DefaultVM),
-    Double(DefaultVM, DefaultVM),
+pub enum InitMemoryArg<M: Memory = DefaultMemoryImpl> {
+    Single(VirtualMemory<M>),
+    Double(VirtualMemory<M>, VirtualMemory<M>),
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -27,19 +25,35 @@ pub enum MemoryType {
     Cell,
     Heap,
     Timer,
+    TtlMap,
+    NonceMap,
 }
 
-pub trait InitMemory<T>: Sized {
+impl MemoryType {
+    /// Returns the partitions of a collection named `name`, the n-th one is created at `id + n`.
+    pub fn partition_names(&self, name: &str) -> Vec<String> {
+        match self {
+            MemoryType::Log => vec![format!("{}_index", name), format!("{}_data", name)],
+            MemoryType::TtlMap => vec![name.to_string(), format!("{}_expiry", name)],
+            MemoryType::NonceMap => vec![name.to_string(), format!("{}_nonce", name)],
+            _ => vec![name.to_string()],
+        }
+    }
+}
+
+pub trait InitMemory<T, M: Memory = DefaultMemoryImpl>: Sized {
     fn memory_type() -> MemoryType;
-    fn init(arg: InitMemoryArg) -> Result<Self, StableMemoryError>;
+    fn init(arg: InitMemoryArg<M>) -> Result<Self, StableMemoryError>;
 }
 
-impl<T: Storable> InitMemory<DefaultStableVec<T>> for DefaultStableVec<T> {
+impl<T: Storable, M: Memory> InitMemory<StableVec<T, VirtualMemory<M>>, M>
+    for StableVec<T, VirtualMemory<M>>
+{
     fn memory_type() -> MemoryType {
         MemoryType::Vec
     }
 
-    fn init(arg: InitMemoryArg) -> Result<Self, StableMemoryError> {
+    fn init(arg: InitMemoryArg<M>) -> Result<Self, StableMemoryError> {
         if let InitMemoryArg::Single(memory) = arg {
             StableVec::init(memory)
                 .map_err(|e| StableMemoryError::UnableToCreateMemory(e.to_string()))
@@ -49,14 +63,15 @@ impl<T: Storable> InitMemory<DefaultStableVec<T>> for DefaultStableVec<T> {
     }
 }
 
-impl<K: Ord + Storable + Clone, V: Storable> InitMemory<DefaultStableBTreeMap<K, V>>
-    for DefaultStableBTreeMap<K, V>
+impl<K: Ord + Storable + Clone, V: Storable, M: Memory>
+    InitMemory<StableBTreeMap<K, V, VirtualMemory<M>>, M>
+    for StableBTreeMap<K, V, VirtualMemory<M>>
 {
     fn memory_type() -> MemoryType {
         MemoryType::Map
     }
 
-    fn init(arg: InitMemoryArg) -> Result<Self, StableMemoryError> {
+    fn init(arg: InitMemoryArg<M>) -> Result<Self, StableMemoryError> {
         if let InitMemoryArg::Single(memory) = arg {
             Ok(StableBTreeMap::init(memory))
         } else {
@@ -65,12 +80,14 @@ impl<K: Ord + Storable + Clone, V: Storable> InitMemory<DefaultStableBTreeMap<K,
     }
 }
 
-impl<T: Storable> InitMemory<DefaultStableLog<T>> for DefaultStableLog<T> {
+impl<T: Storable, M: Memory> InitMemory<StableLog<T, VirtualMemory<M>, VirtualMemory<M>>, M>
+    for StableLog<T, VirtualMemory<M>, VirtualMemory<M>>
+{
     fn memory_type() -> MemoryType {
         MemoryType::Log
     }
 
-    fn init(arg: InitMemoryArg) -> Result<Self, StableMemoryError> {
+    fn init(arg: InitMemoryArg<M>) -> Result<Self, StableMemoryError> {
         if let InitMemoryArg::Double(index_memory, data_memory) = arg {
             StableLog::init(index_memory, data_memory)
                 .map_err(|e| StableMemoryError::UnableToCreateMemory(e.to_string()))
@@ -80,12 +97,14 @@ impl<T: Storable> InitMemory<DefaultStableLog<T>> for DefaultStableLog<T> {
     }
 }
 
-impl<T: Storable + Default> InitMemory<DefaultStableCell<T>> for DefaultStableCell<T> {
+impl<T: Storable + Default, M: Memory> InitMemory<StableCell<T, VirtualMemory<M>>, M>
+    for StableCell<T, VirtualMemory<M>>
+{
     fn memory_type() -> MemoryType {
         MemoryType::Cell
     }
 
-    fn init(arg: InitMemoryArg) -> Result<Self, StableMemoryError> {
+    fn init(arg: InitMemoryArg<M>) -> Result<Self, StableMemoryError> {
         if let InitMemoryArg::Single(memory) = arg {
             StableCell::init(memory, T::default())
                 .map_err(|e| StableMemoryError::UnableToCreateMemory(e.to_string()))
@@ -95,14 +114,16 @@ impl<T: Storable + Default> InitMemory<DefaultStableCell<T>> for DefaultStableCe
     }
 }
 
-impl<T: Ord + Storable> InitMemory<DefaultStableMinHeap<T>> for DefaultStableMinHeap<T> {
+impl<T: Ord + Storable, M: Memory> InitMemory<StableMinHeap<T, VirtualMemory<M>>, M>
+    for StableMinHeap<T, VirtualMemory<M>>
+{
     fn memory_type() -> MemoryType {
         MemoryType::Heap
     }
 
-    fn init(arg: InitMemoryArg) -> Result<Self, StableMemoryError> {
+    fn init(arg: InitMemoryArg<M>) -> Result<Self, StableMemoryError> {
         if let InitMemoryArg::Single(memory) = arg {
-            DefaultStableMinHeap::init(memory)
+            StableMinHeap::init(memory)
                 .map_err(|e| StableMemoryError::UnableToCreateMemory(e.to_string()))
         } else {
             Err(StableMemoryError::WrongInitializationArgument)
@@ -124,3 +145,45 @@ impl<T: Storable + Clone> InitMemory<DefaultTaskTimer<T>> for DefaultTaskTimer<T
         }
     }
 }
+
+impl<T: Storable + Clone> InitMemory<DeadLetterQueue<T>> for DeadLetterQueue<T> {
+    fn memory_type() -> MemoryType {
+        MemoryType::NonceMap
+    }
+
+    fn init(arg: InitMemoryArg) -> Result<Self, StableMemoryError> {
+        if let InitMemoryArg::Double(letters_memory, nonce_memory) = arg {
+            DeadLetterQueue::init(letters_memory, nonce_memory)
+        } else {
+            Err(StableMemoryError::WrongInitializationArgument)
+        }
+    }
+}
+
+impl<K: Storable + Ord + Clone, V: Storable> InitMemory<TtlMap<K, V>> for TtlMap<K, V> {
+    fn memory_type() -> MemoryType {
+        MemoryType::TtlMap
+    }
+
+    fn init(arg: InitMemoryArg) -> Result<Self, StableMemoryError> {
+        if let InitMemoryArg::Double(entries_memory, expiry_memory) = arg {
+            Ok(TtlMap::init(entries_memory, expiry_memory))
+        } else {
+            Err(StableMemoryError::WrongInitializationArgument)
+        }
+    }
+}
+
+impl<T: Storable + Clone> InitMemory<NonceMap<T>> for NonceMap<T> {
+    fn memory_type() -> MemoryType {
+        MemoryType::NonceMap
+    }
+
+    fn init(arg: InitMemoryArg) -> Result<Self, StableMemoryError> {
+        if let InitMemoryArg::Double(items_memory, nonce_memory) = arg {
+            NonceMap::init(items_memory, nonce_memory)
+        } else {
+            Err(StableMemoryError::WrongInitializationArgument)
+        }
+    }
+}
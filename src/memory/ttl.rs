@@ -0,0 +1,264 @@
+use ic_stable_structures::{storable::Bound, Storable};
+use std::borrow::Cow;
+
+use super::types::{DefaultStableBTreeMap, DefaultVM};
+use crate::NanoTimeStamp;
+
+mod test;
+
+/// A value stored in a [`TtlMap`] with its expiry time, `None` never expires.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TtlEntry<V> {
+    pub value: V,
+    pub expires_at: Option<NanoTimeStamp>,
+}
+
+impl<V> TtlEntry<V> {
+    pub fn is_expired(&self, now: &NanoTimeStamp) -> bool {
+        matches!(&self.expires_at, Some(expires_at) if expires_at <= now)
+    }
+}
+
+/// Layout: `[has expiry 1][expires at 8][value ..]`.
+impl<V: Storable> Storable for TtlEntry<V> {
+    fn to_bytes(&self) -> Cow<'_, [u8]> {
+        let value_bytes = self.value.to_bytes();
+
+        let mut bytes = Vec::with_capacity(9 + value_bytes.len());
+        match &self.expires_at {
+            Some(expires_at) => {
+                bytes.push(1);
+                bytes.extend_from_slice(&expires_at.to_le_bytes());
+            }
+            None => bytes.extend_from_slice(&[0; 9]),
+        }
+        bytes.extend_from_slice(&value_bytes);
+        bytes.into()
+    }
+
+    fn from_bytes(bytes: Cow<'_, [u8]>) -> Self {
+        let expires_at = match bytes[0] {
+            0 => None,
+            _ => Some(NanoTimeStamp::from_le_bytes(
+                bytes[1..9].try_into().unwrap(),
+            )),
+        };
+
+        Self {
+            value: V::from_bytes(bytes[9..].to_vec().into()),
+            expires_at,
+        }
+    }
+
+    const BOUND: Bound = match V::BOUND {
+        Bound::Bounded { max_size, .. } => Bound::Bounded {
+            max_size: max_size + 9,
+            is_fixed_size: false,
+        },
+        Bound::Unbounded => Bound::Unbounded,
+    };
+}
+
+/// Key of the expiry index: the expiry time followed by the key bytes.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ExpiryKey {
+    pub expires_at: u64,
+    pub key: Vec<u8>,
+}
+
+impl Storable for ExpiryKey {
+    fn to_bytes(&self) -> Cow<'_, [u8]> {
+        let mut bytes = Vec::with_capacity(8 + self.key.len());
+        bytes.extend_from_slice(&self.expires_at.to_le_bytes());
+        bytes.extend_from_slice(&self.key);
+        bytes.into()
+    }
+
+    fn from_bytes(bytes: Cow<'_, [u8]>) -> Self {
+        Self {
+            expires_at: u64::from_le_bytes(bytes[0..8].try_into().unwrap()),
+            key: bytes[8..].to_vec(),
+        }
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
+
+/// A [`DefaultStableBTreeMap`] whose entries can expire.
+///
+/// Expired entries are never returned. They are removed lazily when read through `get`,
+/// or in bounded batches by `sweep`, which walks an expiry index ordered by time.
+///
+/// The map uses two partitions: `{name}` at `id` and `{name}_expiry` at `id + 1`.
+///
+/// # Example
+/// ```
+/// use b3_utils::memory::{init_stable_mem, ttl::TtlMap};
+/// use b3_utils::NanoTimeStamp;
+///
+/// let mut sessions: TtlMap<u64, u64> = init_stable_mem("sessions", 1).unwrap();
+///
+/// sessions.insert_with_ttl(1, 100, NanoTimeStamp::NS_PER_HOUR);
+/// sessions.insert(2, 200);
+///
+/// assert_eq!(sessions.get(&1), Some(100));
+/// assert!(sessions.expires_at(&2).is_none());
+///
+/// // From a recurring timer task, e.g. scheduled with `TaskTimerExecutor::schedule_interval`
+/// let removed = sessions.sweep(100);
+/// assert_eq!(removed, 0);
+/// ```
+pub struct TtlMap<K: Storable + Ord + Clone, V: Storable> {
+    entries: DefaultStableBTreeMap<K, TtlEntry<V>>,
+    expiry: DefaultStableBTreeMap<ExpiryKey, ()>,
+}
+
+impl<K: Storable + Ord + Clone, V: Storable> TtlMap<K, V> {
+    pub fn init(entries_vm: DefaultVM, expiry_vm: DefaultVM) -> Self {
+        Self {
+            entries: DefaultStableBTreeMap::init(entries_vm),
+            expiry: DefaultStableBTreeMap::init(expiry_vm),
+        }
+    }
+
+    /// Inserts an entry that never expires.
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        self.insert_entry(key, value, None)
+    }
+
+    /// Inserts an entry expiring `ttl` nanoseconds from now.
+    pub fn insert_with_ttl(&mut self, key: K, value: V, ttl: u64) -> Option<V> {
+        let expires_at = NanoTimeStamp(NanoTimeStamp::now().0.saturating_add(ttl));
+
+        self.insert_entry(key, value, Some(expires_at))
+    }
+
+    /// Inserts an entry expiring at the given time.
+    pub fn insert_until(&mut self, key: K, value: V, expires_at: NanoTimeStamp) -> Option<V> {
+        self.insert_entry(key, value, Some(expires_at))
+    }
+
+    /// Returns the value if it has not expired, an expired entry is removed.
+    pub fn get(&mut self, key: &K) -> Option<V> {
+        let entry = self.entries.get(key)?;
+
+        if entry.is_expired(&NanoTimeStamp::now()) {
+            self.remove_entry(key);
+
+            return None;
+        }
+
+        Some(entry.value)
+    }
+
+    /// Returns the value if it has not expired, without removing expired entries.
+    pub fn peek(&self, key: &K) -> Option<V> {
+        self.entries
+            .get(key)
+            .filter(|entry| !entry.is_expired(&NanoTimeStamp::now()))
+            .map(|entry| entry.value)
+    }
+
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.peek(key).is_some()
+    }
+
+    pub fn expires_at(&self, key: &K) -> Option<NanoTimeStamp> {
+        self.entries.get(key).and_then(|entry| entry.expires_at)
+    }
+
+    /// Removes the entry and returns its value if it has not expired.
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        self.remove_entry(key)
+            .filter(|entry| !entry.is_expired(&NanoTimeStamp::now()))
+            .map(|entry| entry.value)
+    }
+
+    /// Returns the earliest expiry time, useful to schedule the next sweep.
+    pub fn next_expiry(&self) -> Option<NanoTimeStamp> {
+        self.expiry
+            .first_key_value()
+            .map(|(key, _)| NanoTimeStamp(key.expires_at))
+    }
+
+    /// Removes up to `limit` expired entries and returns how many were removed.
+    pub fn sweep(&mut self, limit: usize) -> usize {
+        self.sweep_expired(&NanoTimeStamp::now(), limit)
+    }
+
+    /// Removes up to `limit` entries expired at `now`.
+    pub fn sweep_expired(&mut self, now: &NanoTimeStamp, limit: usize) -> usize {
+        let expired: Vec<ExpiryKey> = self
+            .expiry
+            .iter()
+            .take_while(|(key, _)| key.expires_at <= now.0)
+            .take(limit)
+            .map(|(key, _)| key)
+            .collect();
+
+        for expiry_key in expired.iter() {
+            self.expiry.remove(expiry_key);
+            self.entries
+                .remove(&K::from_bytes(Cow::Borrowed(&expiry_key.key)));
+        }
+
+        expired.len()
+    }
+
+    /// Returns the number of stored entries, including expired entries not swept yet.
+    pub fn len(&self) -> u64 {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns the entries that have not expired.
+    pub fn iter(&self) -> Vec<(K, V)> {
+        let now = NanoTimeStamp::now();
+
+        self.entries
+            .iter()
+            .filter(|(_, entry)| !entry.is_expired(&now))
+            .map(|(key, entry)| (key, entry.value))
+            .collect()
+    }
+
+    pub fn clear(&mut self) {
+        while self.entries.pop_last().is_some() {}
+        while self.expiry.pop_last().is_some() {}
+    }
+
+    fn insert_entry(&mut self, key: K, value: V, expires_at: Option<NanoTimeStamp>) -> Option<V> {
+        let old = self.remove_entry(&key);
+
+        if let Some(expires_at) = &expires_at {
+            self.expiry.insert(
+                ExpiryKey {
+                    expires_at: expires_at.0,
+                    key: key.to_bytes().into_owned(),
+                },
+                (),
+            );
+        }
+
+        self.entries.insert(key, TtlEntry { value, expires_at });
+
+        old.filter(|entry| !entry.is_expired(&NanoTimeStamp::now()))
+            .map(|entry| entry.value)
+    }
+
+    fn remove_entry(&mut self, key: &K) -> Option<TtlEntry<V>> {
+        let entry = self.entries.remove(key)?;
+
+        if let Some(expires_at) = &entry.expires_at {
+            self.expiry.remove(&ExpiryKey {
+                expires_at: expires_at.0,
+                key: key.to_bytes().into_owned(),
+            });
+        }
+
+        Some(entry)
+    }
+}
@@ -0,0 +1,107 @@
+#[cfg(test)]
+mod tests {
+    use ic_stable_structures::Storable;
+
+    use crate::{
+        memory::{
+            ttl::{TtlEntry, TtlMap},
+            StableMemoryManager,
+        },
+        NanoTimeStamp,
+    };
+
+    fn init_map(manager: &mut StableMemoryManager) -> TtlMap<u64, u64> {
+        manager.init_memory("test_ttl", 10).unwrap()
+    }
+
+    fn far_future() -> NanoTimeStamp {
+        NanoTimeStamp::now().add_days(1)
+    }
+
+    #[test]
+    fn test_ttl_entry_to_and_from_bytes() {
+        let entry = TtlEntry {
+            value: 7u64,
+            expires_at: Some(NanoTimeStamp(0)),
+        };
+
+        assert_eq!(TtlEntry::<u64>::from_bytes(entry.to_bytes()), entry);
+
+        let entry = TtlEntry {
+            value: 7u64,
+            expires_at: None,
+        };
+
+        assert_eq!(TtlEntry::<u64>::from_bytes(entry.to_bytes()), entry);
+    }
+
+    #[test]
+    fn test_ttl_map_lazy_expiry() {
+        let mut manager = StableMemoryManager::init();
+
+        let mut map = init_map(&mut manager);
+
+        assert_eq!(manager.partition("test_ttl_expiry"), Some(11));
+
+        map.insert(1, 10);
+        map.insert_with_ttl(2, 20, NanoTimeStamp::NS_PER_HOUR);
+        map.insert_until(3, 30, NanoTimeStamp(1));
+
+        assert_eq!(map.len(), 3);
+        assert_eq!(map.get(&1), Some(10));
+        assert_eq!(map.get(&2), Some(20));
+
+        // Expired entries are hidden, and removed when read through `get`
+        assert_eq!(map.peek(&3), None);
+        assert!(!map.contains_key(&3));
+        assert_eq!(map.len(), 3);
+        assert_eq!(map.iter(), vec![(1, 10), (2, 20)]);
+
+        assert_eq!(map.get(&3), None);
+        assert_eq!(map.len(), 2);
+        assert_eq!(map.next_expiry(), map.expires_at(&2));
+
+        // Replacing an entry replaces its expiry
+        assert_eq!(map.insert(2, 21), Some(20));
+        assert_eq!(map.expires_at(&2), None);
+        assert_eq!(map.next_expiry(), None);
+
+        assert_eq!(map.remove(&2), Some(21));
+        assert_eq!(map.remove(&2), None);
+
+        map.clear();
+
+        assert!(map.is_empty());
+    }
+
+    #[test]
+    fn test_ttl_map_sweep() {
+        let mut manager = StableMemoryManager::init();
+
+        let mut map = init_map(&mut manager);
+
+        for key in 0..10 {
+            map.insert_until(key, key, NanoTimeStamp(100 + key));
+        }
+
+        map.insert_until(10, 10, far_future());
+        map.insert(11, 11);
+
+        assert_eq!(map.next_expiry(), Some(NanoTimeStamp(100)));
+
+        // Bounded by the limit, oldest first
+        assert_eq!(map.sweep_expired(&NanoTimeStamp(105), 3), 3);
+        assert_eq!(map.next_expiry(), Some(NanoTimeStamp(103)));
+
+        // Bounded by the time
+        assert_eq!(map.sweep_expired(&NanoTimeStamp(105), 10), 3);
+        assert_eq!(map.next_expiry(), Some(NanoTimeStamp(106)));
+
+        assert_eq!(map.sweep(100), 4);
+        assert_eq!(map.len(), 2);
+        assert_eq!(map.sweep(100), 0);
+
+        assert_eq!(map.get(&10), Some(10));
+        assert_eq!(map.get(&11), Some(11));
+    }
+}
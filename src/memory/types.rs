@@ -1,6 +1,8 @@
 use candid::CandidType;
 use serde::{Deserialize, Serialize};
 
+use super::quota::PartitionQuota;
+
 pub use ic_stable_structures::{
     cell::InitError as ExternalCellInitError, log::InitError as ExternalLogInitError,
     memory_manager::VirtualMemory, storable::Bound, DefaultMemoryImpl, FileMemory, Memory,
@@ -11,7 +13,12 @@ pub use ic_stable_structures::{
 #[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
 pub struct PartitionDetail {
     pub name: String,
+    /// Allocated size in pages.
     pub size: u64,
+    /// Allocated size in bytes, `size` times the page size. It counts whole pages,
+    /// not the bytes taken by the entries.
+    pub bytes: u64,
+    pub quota: Option<PartitionQuota>,
     pub id: u8,
 }
 
@@ -1,4 +1,13 @@
 use candid::Principal;
+use std::{
+    future::Future,
+    pin::pin,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    task::{Context, Poll, Wake, Waker},
+};
 
 pub fn time_mock() -> u64 {
     use std::time::SystemTime;
@@ -21,3 +30,57 @@ pub fn performance_counter_mock(_n: u32) -> u64 {
 pub fn id_mock() -> Principal {
     Principal::management_canister()
 }
+
+thread_local! {
+    static CALLER: std::cell::Cell<Principal> = const { std::cell::Cell::new(Principal::anonymous()) };
+}
+
+//only use for test cases
+pub fn caller_mock() -> Principal {
+    CALLER.with(|caller| caller.get())
+}
+
+//only use for test cases, sets the caller returned by `caller_mock` on this thread
+pub fn set_caller_mock(caller: Principal) {
+    CALLER.with(|c| c.set(caller));
+}
+
+thread_local! {
+    static CONTROLLERS: std::cell::RefCell<Vec<Principal>> = const { std::cell::RefCell::new(Vec::new()) };
+}
+
+//only use for test cases
+pub fn is_controller_mock(principal: &Principal) -> bool {
+    CONTROLLERS.with(|controllers| controllers.borrow().contains(principal))
+}
+
+//only use for test cases, sets the controllers checked by `is_controller_mock` on this thread
+pub fn set_controllers_mock(controllers: Vec<Principal>) {
+    CONTROLLERS.with(|c| *c.borrow_mut() = controllers);
+}
+
+//only use for test cases, runs a future that only waits on mocked calls to completion.
+//panics if the future waits on anything else, like a call through the system API.
+pub fn block_on_mock<F: Future>(future: F) -> F::Output {
+    struct Woken(AtomicBool);
+
+    impl Wake for Woken {
+        fn wake(self: Arc<Self>) {
+            self.0.store(true, Ordering::SeqCst);
+        }
+    }
+
+    let woken = Arc::new(Woken(AtomicBool::new(false)));
+    let waker = Waker::from(woken.clone());
+    let mut context = Context::from_waker(&waker);
+
+    let mut future = pin!(future);
+
+    loop {
+        match future.as_mut().poll(&mut context) {
+            Poll::Ready(output) => return output,
+            Poll::Pending if woken.0.swap(false, Ordering::SeqCst) => continue,
+            Poll::Pending => panic!("The future is waiting on something other than a mocked call"),
+        }
+    }
+}
@@ -4,12 +4,17 @@ mod cost;
 pub use cost::*;
 
 use ic_cdk::api::management_canister::http_request::{
-    http_request, http_request_with_closure, CanisterHttpRequestArgument, HttpHeader, HttpMethod,
+    http_request_with_closure, CanisterHttpRequestArgument, HttpHeader, HttpMethod,
     TransformContext,
 };
 
 pub use ic_cdk::api::management_canister::http_request::HttpResponse as HttpOutcallResponse;
 
+use crate::{
+    api::{record_call, CallCycles, Management},
+    constants::MANAGMENT_CANISTER_ID,
+};
+
 /// Used to build a request to the Management Canister's `http_request` method.
 pub struct HttpOutcall(pub CanisterHttpRequestArgument);
 
@@ -135,14 +140,16 @@ impl HttpOutcall {
         HttpsOutcallCost::total(&self.0)
     }
 
-    /// Wraps around `http_request` to issue a request to the `http_request` endpoint.
+    /// Issues the request to the `http_request` endpoint, through the call transport.
     pub async fn send(self) -> Result<HttpOutcallResponse, String> {
         let cycle_cost = self.calculate_cycle_cost();
 
-        http_request(self.0, cycle_cost)
+        Management::call("http_request", self.0, CallCycles::Pay128(cycle_cost))
             .await
-            .map(|(response,)| response)
-            .map_err(|(_rejection_code, message)| message)
+            .map_err(|e| match e.rejection() {
+                Some(rejection) => rejection.message.clone(),
+                None => e.to_string(),
+            })
     }
 
     /// Wraps around `http_request_with_closure` to issue a request to the `http_request` endpoint with a transform closure.
@@ -152,9 +159,11 @@ impl HttpOutcall {
     ) -> Result<HttpOutcallResponse, String> {
         let cycle_cost = self.calculate_cycle_cost();
 
-        http_request_with_closure(self.0, cycle_cost, transform_func)
-            .await
-            .map(|(response,)| response)
+        let res = http_request_with_closure(self.0, cycle_cost, transform_func).await;
+
+        record_call(MANAGMENT_CANISTER_ID, "http_request", cycle_cost);
+
+        res.map(|(response,)| response)
             .map_err(|(_rejection_code, message)| message)
     }
 }
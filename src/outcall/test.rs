@@ -4,7 +4,15 @@ use crate::outcall::{
     HttpOutcall, HttpsOutcallCost, HTTPS_OUTCALL_BASE_COST, HTTPS_OUTCALL_REQ_COST_PER_BYTE,
     HTTPS_OUTCALL_RESP_COST_PER_BYTE,
 };
-use ic_cdk::api::management_canister::http_request::{CanisterHttpRequestArgument, HttpMethod};
+use crate::{
+    api::MockTransport, constants::MANAGMENT_CANISTER_ID, mocks::block_on_mock,
+    outcall::HttpOutcallResponse,
+};
+use candid::Nat;
+use ic_cdk::api::{
+    call::RejectionCode,
+    management_canister::http_request::{CanisterHttpRequestArgument, HttpMethod},
+};
 
 #[test]
 fn test_new_http_request() {
@@ -98,3 +106,41 @@ fn test_calculate_cycle_cost() {
 
     assert_eq!(expected_cost, actual_cost);
 }
+
+#[test]
+fn test_http_request_send() {
+    let response = HttpOutcallResponse {
+        status: Nat::from(200u64),
+        headers: vec![],
+        body: b"ok".to_vec(),
+    };
+
+    let mock =
+        MockTransport::default().reply(MANAGMENT_CANISTER_ID, "http_request", response.clone());
+
+    mock.install();
+
+    let request = HttpOutcall::new("https://example.com").get(Some(1024));
+    let cycle_cost = request.calculate_cycle_cost();
+    let arg = candid::encode_one(&request.0).unwrap();
+
+    assert_eq!(block_on_mock(request.send()).unwrap(), response);
+
+    let call = &mock.calls()[0];
+
+    assert_eq!(call.cycles, cycle_cost);
+    assert_eq!(call.args, arg);
+
+    MockTransport::default()
+        .reject(
+            MANAGMENT_CANISTER_ID,
+            "http_request",
+            RejectionCode::SysTransient,
+            "Timeout",
+        )
+        .install();
+
+    let result = block_on_mock(HttpOutcall::new("https://example.com").send());
+
+    assert_eq!(result, Err("Timeout".to_string()));
+}
@@ -3,52 +3,177 @@ use std::cell::RefCell;
 use candid::Principal;
 
 use crate::{
-    memory::{error::StableMemoryError, types::DefaultStableCell, with_stable_mem_mut},
+    memory::{
+        error::StableMemoryError,
+        types::{DefaultStableCell, DefaultVM, Memory},
+        with_stable_mem_mut,
+    },
     principal::StoredPrincipal,
 };
 
-fn init_owner(
-    name: &str,
-    id: u8,
-) -> Result<RefCell<DefaultStableCell<StoredPrincipal>>, StableMemoryError> {
-    let memory = with_stable_mem_mut(|pm| pm.create(name, id))?;
+#[cfg(test)]
+use crate::mocks::{caller_mock as ic_cdk_caller, is_controller_mock as ic_cdk_is_controller};
+#[cfg(not(test))]
+use ic_cdk::{api::is_controller as ic_cdk_is_controller, caller as ic_cdk_caller};
 
-    let owner_id = StoredPrincipal::from(ic_cdk::caller());
+mod audit;
+mod error;
+mod roles;
+mod test;
+mod transfer;
 
-    let cell = DefaultStableCell::init(memory, owner_id)
-        .map_err(|e| StableMemoryError::UnableToCreateMemory(e.to_string()))?;
+pub use audit::*;
+pub use error::*;
+pub use roles::*;
+pub use transfer::*;
 
-    Ok(RefCell::new(cell))
-}
+use audit::record;
+
+/// Default partition of the owner, see [`init_owner_partition`].
+pub const OWNER_PARTITION_NAME: &str = "owner";
+pub const OWNER_PARTITION_ID: u8 = 251;
 
 thread_local! {
-    static OWNER: RefCell<DefaultStableCell<StoredPrincipal>> = init_owner("owner", 251).unwrap();
+    static OWNER_PARTITION: RefCell<(String, u8)> = RefCell::new((OWNER_PARTITION_NAME.to_string(), OWNER_PARTITION_ID));
+    static OWNER: RefCell<Option<DefaultStableCell<StoredPrincipal>>> = const { RefCell::new(None) };
 }
 
-pub fn get_owner() -> Principal {
-    OWNER.with(|states| {
-        let state = states.borrow();
+fn owner_memory() -> Result<DefaultVM, StableMemoryError> {
+    let (name, id) = OWNER_PARTITION.with(|partition| partition.borrow().clone());
+
+    with_stable_mem_mut(|pm| pm.create(&name, id))
+}
+
+/// Loads the owner from its partition, unless it was never initialized.
+fn load_owner() {
+    OWNER.with(|owner| {
+        if owner.borrow().is_some() {
+            return;
+        }
+
+        let memory = owner_memory().expect("Unable to create owner memory");
+
+        if memory.size() == 0 {
+            return;
+        }
+
+        let cell = DefaultStableCell::init(memory, Principal::anonymous().into())
+            .expect("Unable to load owner");
+
+        *owner.borrow_mut() = Some(cell);
+    })
+}
+
+/// Replaces the owner, initializing it if needed, and returns the previous one.
+fn replace_owner(new_owner: Principal) -> Option<Principal> {
+    load_owner();
+
+    OWNER.with(|owner| {
+        let mut owner = owner.borrow_mut();
+
+        match owner.as_mut() {
+            Some(cell) => {
+                let old_owner = cell.set(new_owner.into()).expect("Unable to set owner");
 
-        state.get().into()
+                Some(old_owner.into())
+            }
+            None => {
+                let memory = owner_memory().expect("Unable to create owner memory");
+                let cell = DefaultStableCell::init(memory, new_owner.into())
+                    .expect("Unable to initialize owner");
+
+                *owner = Some(cell);
+
+                None
+            }
+        }
     })
 }
 
+/// Moves the owner to another partition than `owner` at id 251.
+///
+/// Must be called in `#[init]` and `#[post_upgrade]` before the owner is used.
+pub fn init_owner_partition(name: &str, id: u8) -> Result<(), StableMemoryError> {
+    if OWNER.with(|owner| owner.borrow().is_some()) {
+        return Err(StableMemoryError::PartitionExists);
+    }
+
+    OWNER_PARTITION.with(|partition| *partition.borrow_mut() = (name.to_string(), id));
+
+    owner_memory().map(|_| ())
+}
+
+/// Sets the owner, meant to be called from `#[init]` with a principal from the init
+/// arguments or the deployer. Until then the owner is unset and only controllers pass
+/// [`caller_is_owner_or_controller`].
+///
+/// Fails with `OwnerExists` if an owner is already stored, so calling it again from
+/// `#[post_upgrade]` can't take over the canister. Use [`set_owner`] or
+/// [`propose_owner`] to change it.
+pub fn init_owner_with(owner: Principal) -> Result<(), OwnerError> {
+    if let Some(existing) = try_get_owner() {
+        return Err(OwnerError::OwnerExists(existing));
+    }
+
+    record(OwnerAction::OwnerInitialized { owner })?;
+
+    replace_owner(owner);
+
+    Ok(())
+}
+
+/// Returns the owner, `None` until it is initialized.
+pub fn try_get_owner() -> Option<Principal> {
+    load_owner();
+
+    OWNER.with(|owner| owner.borrow().as_ref().map(|cell| cell.get().into()))
+}
+
+/// Returns the owner.
+///
+/// # Panics
+/// If the owner was never initialized, see [`init_owner_with`].
+pub fn get_owner() -> Principal {
+    try_get_owner().expect("Owner not initialized")
+}
+
+/// Replaces the owner right away, prefer [`propose_owner`] so a wrong principal can't
+/// lock the canister.
+/// Fails without changing anything if the owner was never initialized.
 pub fn set_owner(new_owner: Principal) -> Result<Principal, String> {
-    OWNER.with(|states| {
-        let mut state = states.borrow_mut();
-        let old_owner = state.set(new_owner.into()).expect("Unable to set owner");
+    let old_owner = try_get_owner().ok_or("Owner not initialized".to_string())?;
 
-        Ok(old_owner.into())
+    record(OwnerAction::OwnerChanged {
+        old_owner,
+        new_owner,
     })
+    .map_err(|err| err.to_string())?;
+
+    replace_owner(new_owner);
+
+    Ok(old_owner)
 }
 
 pub fn caller_is_owner() -> Result<(), String> {
-    let caller_id = ic_cdk::caller();
-    let owner_id = get_owner();
+    let caller_id = ic_cdk_caller();
+
+    match try_get_owner() {
+        Some(owner_id) if caller_id == owner_id => Ok(()),
+        _ => Err(format!("Caller is not the owner. Caller: {}", caller_id)),
+    }
+}
+
+/// Guard that accepts the owner and the controllers of the canister, so controllers
+/// can recover a canister whose owner is lost or not initialized.
+pub fn caller_is_owner_or_controller() -> Result<(), String> {
+    let caller_id = ic_cdk_caller();
 
-    if caller_id == owner_id {
+    if caller_is_owner().is_ok() || ic_cdk_is_controller(&caller_id) {
         Ok(())
     } else {
-        Err(format!("Caller is not the owner. Caller: {}", caller_id))
+        Err(format!(
+            "Caller is neither the owner nor a controller. Caller: {}",
+            caller_id
+        ))
     }
 }
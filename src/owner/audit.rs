@@ -0,0 +1,125 @@
+use candid::{CandidType, Decode, Encode, Principal};
+use serde::{Deserialize, Serialize};
+use std::{borrow::Cow, cell::RefCell};
+
+use crate::{
+    memory::{
+        error::StableMemoryError,
+        init_stable_mem,
+        types::{Bound, DefaultStableLog, Storable},
+    },
+    types::RoleId,
+    NanoTimeStamp,
+};
+
+use super::{error::OwnerError, ic_cdk_caller};
+
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub enum OwnerAction {
+    RoleCreated {
+        role: RoleId,
+        name: String,
+    },
+    RoleDeleted {
+        role: RoleId,
+        name: String,
+    },
+    RoleGranted {
+        role: RoleId,
+        name: String,
+        principal: Principal,
+    },
+    RoleRevoked {
+        role: RoleId,
+        name: String,
+        principal: Principal,
+    },
+    OwnershipProposed {
+        new_owner: Principal,
+        expires_at: NanoTimeStamp,
+    },
+    TransferCancelled {
+        new_owner: Principal,
+    },
+    OwnerInitialized {
+        owner: Principal,
+    },
+    OwnerChanged {
+        old_owner: Principal,
+        new_owner: Principal,
+    },
+}
+
+/// A change of the access control, with the caller who made it.
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct OwnerAuditEntry {
+    pub timestamp: NanoTimeStamp,
+    pub caller: Principal,
+    pub action: OwnerAction,
+}
+
+impl Storable for OwnerAuditEntry {
+    const BOUND: Bound = Bound::Unbounded;
+
+    fn from_bytes(bytes: Cow<'_, [u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).unwrap()
+    }
+
+    fn to_bytes(&self) -> Cow<'_, [u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
+}
+
+thread_local! {
+    static AUDIT_LOG: RefCell<Option<DefaultStableLog<OwnerAuditEntry>>> = const { RefCell::new(None) };
+}
+
+/// Enables the audit log in the partitions `{name}_index` at `id` and `{name}_data` at
+/// `id + 1`. Nothing is recorded until it is called.
+///
+/// Must be called in `#[init]` and `#[post_upgrade]` before anything is recorded.
+pub fn init_audit_partition(name: &str, id: u8) -> Result<(), StableMemoryError> {
+    AUDIT_LOG.with(|log| {
+        if log.borrow().is_some() {
+            return Err(StableMemoryError::PartitionExists);
+        }
+
+        *log.borrow_mut() = Some(init_stable_mem(name, id)?);
+
+        Ok(())
+    })
+}
+
+/// Records an action if the audit log is enabled, callers record before making the
+/// change so a failed append leaves nothing changed.
+pub(crate) fn record(action: OwnerAction) -> Result<(), OwnerError> {
+    AUDIT_LOG.with(|log| match log.borrow().as_ref() {
+        Some(log) => {
+            let entry = OwnerAuditEntry {
+                timestamp: NanoTimeStamp::now(),
+                caller: ic_cdk_caller(),
+                action,
+            };
+
+            log.append(&entry)
+                .map(|_| ())
+                .map_err(|err| OwnerError::AuditFailed(format!("{:?}", err)))
+        }
+        None => Ok(()),
+    })
+}
+
+/// Returns at most `limit` audit entries starting at `from`, oldest first.
+/// Empty if the audit log is not enabled.
+pub fn audit_log(from: u64, limit: u64) -> Vec<OwnerAuditEntry> {
+    AUDIT_LOG.with(|log| match log.borrow().as_ref() {
+        Some(log) => (from..from.saturating_add(limit).min(log.len()))
+            .filter_map(|index| log.get(index))
+            .collect(),
+        None => vec![],
+    })
+}
+
+pub fn audit_log_len() -> u64 {
+    AUDIT_LOG.with(|log| log.borrow().as_ref().map_or(0, |log| log.len()))
+}
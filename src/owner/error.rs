@@ -0,0 +1,42 @@
+use candid::{CandidType, Principal};
+use serde::{Deserialize, Serialize};
+
+use crate::NanoTimeStamp;
+
+#[derive(CandidType, Debug, Clone, Deserialize, Serialize, PartialEq)]
+pub enum OwnerError {
+    RoleExists(String),
+    RoleNotFound(String),
+    NoOwner,
+    OwnerExists(Principal),
+    AlreadyOwner(Principal),
+    InvalidExpiry(NanoTimeStamp),
+    NoPendingTransfer,
+    NotPendingOwner(Principal),
+    TransferExpired(NanoTimeStamp),
+    RolesNotInitialized,
+    TransferNotInitialized,
+    TransferFailed(String),
+    AuditFailed(String),
+}
+
+#[rustfmt::skip]
+impl std::fmt::Display for OwnerError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            OwnerError::RoleExists(name) => write!(f, "Role {} already exists", name),
+            OwnerError::RoleNotFound(name) => write!(f, "Role {} not found", name),
+            OwnerError::NoOwner => write!(f, "Owner not initialized"),
+            OwnerError::OwnerExists(owner) => write!(f, "Owner already initialized: {}", owner),
+            OwnerError::AlreadyOwner(principal) => write!(f, "{} is already the owner", principal),
+            OwnerError::InvalidExpiry(expires_at) => write!(f, "Expiry {} is in the past", expires_at),
+            OwnerError::NoPendingTransfer => write!(f, "No pending ownership transfer"),
+            OwnerError::NotPendingOwner(caller) => write!(f, "Caller is not the proposed owner. Caller: {}", caller),
+            OwnerError::TransferExpired(expires_at) => write!(f, "Ownership transfer expired at {}", expires_at),
+            OwnerError::RolesNotInitialized => write!(f, "Roles not initialized, see init_roles_partition"),
+            OwnerError::TransferNotInitialized => write!(f, "Ownership transfers not initialized, see init_transfer_partition"),
+            OwnerError::TransferFailed(err) => write!(f, "Unable to store the ownership transfer: {}", err),
+            OwnerError::AuditFailed(err) => write!(f, "Unable to record the audit entry: {}", err),
+        }
+    }
+}
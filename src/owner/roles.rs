@@ -0,0 +1,295 @@
+use candid::{CandidType, Principal};
+use serde::{Deserialize, Serialize};
+use std::{borrow::Cow, cell::RefCell};
+
+use crate::{
+    memory::{
+        error::StableMemoryError,
+        init_stable_mem,
+        types::{Bound, DefaultStableBTreeMap, Storable},
+    },
+    nonce::Nonce,
+    principal::StoredPrincipal,
+    types::RoleId,
+};
+
+use super::{
+    audit::{record, OwnerAction},
+    error::OwnerError,
+    ic_cdk_caller,
+};
+
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct Role {
+    pub id: RoleId,
+    pub name: String,
+}
+
+/// Key of a principal assigned to a role.
+///
+/// Layout: `[role id 8 big endian][principal ..]`, so the members of a role are contiguous.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct RoleMember {
+    pub role: RoleId,
+    pub principal: StoredPrincipal,
+}
+
+impl Storable for RoleMember {
+    fn to_bytes(&self) -> Cow<'_, [u8]> {
+        let mut bytes = Vec::with_capacity(8 + 29);
+        bytes.extend_from_slice(&self.role.get().to_be_bytes());
+        bytes.extend_from_slice(self.principal.as_slice());
+        bytes.into()
+    }
+
+    fn from_bytes(bytes: Cow<'_, [u8]>) -> Self {
+        Self {
+            role: Nonce(u64::from_be_bytes(bytes[0..8].try_into().unwrap())),
+            principal: StoredPrincipal::from_slice(&bytes[8..]),
+        }
+    }
+
+    const BOUND: Bound = Bound::Bounded {
+        max_size: 8 + 29,
+        is_fixed_size: false,
+    };
+}
+
+struct RoleStore {
+    roles: DefaultStableBTreeMap<String, RoleId>,
+    members: DefaultStableBTreeMap<RoleMember, ()>,
+}
+
+thread_local! {
+    static ROLE_STORE: RefCell<Option<RoleStore>> = const { RefCell::new(None) };
+}
+
+fn with_role_store<F, R>(f: F) -> Result<R, OwnerError>
+where
+    F: FnOnce(&mut RoleStore) -> R,
+{
+    ROLE_STORE.with(|store| match store.borrow_mut().as_mut() {
+        Some(store) => Ok(f(store)),
+        None => Err(OwnerError::RolesNotInitialized),
+    })
+}
+
+/// Enables the roles in the partitions `{name}` at `id` and `{name}_members` at `id + 1`.
+/// Until then no role exists and creating one fails with `RolesNotInitialized`.
+///
+/// Must be called in `#[init]` and `#[post_upgrade]` before the roles are used.
+pub fn init_roles_partition(name: &str, id: u8) -> Result<(), StableMemoryError> {
+    ROLE_STORE.with(|store| {
+        if store.borrow().is_some() {
+            return Err(StableMemoryError::PartitionExists);
+        }
+
+        *store.borrow_mut() = Some(RoleStore {
+            roles: init_stable_mem(name, id)?,
+            members: init_stable_mem(&format!("{}_members", name), id + 1)?,
+        });
+
+        Ok(())
+    })
+}
+
+fn role_id(name: &str) -> Result<RoleId, OwnerError> {
+    with_role_store(|store| store.roles.get(&name.to_string()))?
+        .ok_or(OwnerError::RoleNotFound(name.to_string()))
+}
+
+fn first_member(role: RoleId) -> RoleMember {
+    RoleMember {
+        role,
+        principal: StoredPrincipal::from_slice(&[]),
+    }
+}
+
+/// Creates a role, ids are never reused while the role with the highest id exists.
+pub fn create_role(name: &str) -> Result<RoleId, OwnerError> {
+    let role = with_role_store(|store| {
+        if store.roles.contains_key(&name.to_string()) {
+            return Err(OwnerError::RoleExists(name.to_string()));
+        }
+
+        Ok(store
+            .roles
+            .iter()
+            .map(|(_, id)| id.add_64(1))
+            .max()
+            .unwrap_or_default())
+    })??;
+
+    record(OwnerAction::RoleCreated {
+        role,
+        name: name.to_string(),
+    })?;
+
+    with_role_store(|store| store.roles.insert(name.to_string(), role))?;
+
+    Ok(role)
+}
+
+/// Deletes a role and revokes it from all its members.
+pub fn delete_role(name: &str) -> Result<RoleId, OwnerError> {
+    let role = role_id(name)?;
+
+    record(OwnerAction::RoleDeleted {
+        role,
+        name: name.to_string(),
+    })?;
+
+    with_role_store(|store| {
+        let keys: Vec<RoleMember> = store
+            .members
+            .range(first_member(role)..)
+            .take_while(|(member, _)| member.role == role)
+            .map(|(member, _)| member)
+            .collect();
+
+        for key in keys {
+            store.members.remove(&key);
+        }
+
+        store.roles.remove(&name.to_string());
+    })?;
+
+    Ok(role)
+}
+
+/// Returns the id of a role, `None` if it doesn't exist or the roles are not initialized.
+pub fn get_role(name: &str) -> Option<RoleId> {
+    role_id(name).ok()
+}
+
+pub fn get_roles() -> Vec<Role> {
+    with_role_store(|store| {
+        store
+            .roles
+            .iter()
+            .map(|(name, id)| Role { id, name })
+            .collect()
+    })
+    .unwrap_or_default()
+}
+
+/// Assigns a role to a principal, returns false if it already had it.
+pub fn grant_role(name: &str, principal: Principal) -> Result<bool, OwnerError> {
+    let role = role_id(name)?;
+
+    let member = RoleMember {
+        role,
+        principal: principal.into(),
+    };
+
+    if with_role_store(|store| store.members.contains_key(&member))? {
+        return Ok(false);
+    }
+
+    record(OwnerAction::RoleGranted {
+        role,
+        name: name.to_string(),
+        principal,
+    })?;
+
+    with_role_store(|store| store.members.insert(member, ()))?;
+
+    Ok(true)
+}
+
+/// Removes a role from a principal, returns false if it didn't have it.
+pub fn revoke_role(name: &str, principal: Principal) -> Result<bool, OwnerError> {
+    let role = role_id(name)?;
+
+    let member = RoleMember {
+        role,
+        principal: principal.into(),
+    };
+
+    if !with_role_store(|store| store.members.contains_key(&member))? {
+        return Ok(false);
+    }
+
+    record(OwnerAction::RoleRevoked {
+        role,
+        name: name.to_string(),
+        principal,
+    })?;
+
+    with_role_store(|store| store.members.remove(&member))?;
+
+    Ok(true)
+}
+
+pub fn has_role(principal: Principal, name: &str) -> bool {
+    let role = match get_role(name) {
+        Some(role) => role,
+        None => return false,
+    };
+
+    let member = RoleMember {
+        role,
+        principal: principal.into(),
+    };
+
+    with_role_store(|store| store.members.contains_key(&member)).unwrap_or(false)
+}
+
+pub fn get_role_members(name: &str) -> Result<Vec<Principal>, OwnerError> {
+    let role = role_id(name)?;
+
+    with_role_store(|store| {
+        store
+            .members
+            .range(first_member(role)..)
+            .take_while(|(member, _)| member.role == role)
+            .map(|(member, _)| member.principal.into())
+            .collect()
+    })
+}
+
+pub fn get_principal_roles(principal: Principal) -> Vec<Role> {
+    get_roles()
+        .into_iter()
+        .filter(|role| has_role(principal, &role.name))
+        .collect()
+}
+
+/// Guard that accepts callers assigned to the role `name`.
+///
+/// Guards take no arguments, so wrap it for each role:
+/// ```
+/// use b3_utils::owner::caller_has_role;
+///
+/// // Used as `#[update(guard = "caller_is_admin")]`
+/// fn caller_is_admin() -> Result<(), String> {
+///     caller_has_role("admin")
+/// }
+/// ```
+pub fn caller_has_role(name: &str) -> Result<(), String> {
+    let caller_id = ic_cdk_caller();
+
+    if has_role(caller_id, name) {
+        Ok(())
+    } else {
+        Err(format!(
+            "Caller does not have the role {}. Caller: {}",
+            name, caller_id
+        ))
+    }
+}
+
+/// Guard that accepts callers assigned to at least one of `names`.
+pub fn caller_has_any_role(names: &[&str]) -> Result<(), String> {
+    let caller_id = ic_cdk_caller();
+
+    if names.iter().any(|name| has_role(caller_id, name)) {
+        Ok(())
+    } else {
+        Err(format!(
+            "Caller does not have any of the roles {}. Caller: {}",
+            names.join(", "),
+            caller_id
+        ))
+    }
+}
@@ -0,0 +1,367 @@
+#[cfg(test)]
+mod tests {
+    use candid::Principal;
+
+    use crate::{
+        memory::with_stable_mem,
+        mocks::{set_caller_mock, set_controllers_mock},
+        nonce::Nonce,
+        owner::{
+            accept_ownership, audit_log, audit_log_len, caller_has_any_role, caller_has_role,
+            caller_is_owner, caller_is_owner_or_controller, cancel_transfer, create_role,
+            delete_role, get_owner, get_pending_transfer, get_principal_roles, get_role,
+            get_role_members, get_roles, grant_role, has_role, init_audit_partition,
+            init_owner_partition, init_owner_with, init_roles_partition, init_transfer_partition,
+            propose_owner, revoke_role, set_owner, try_get_owner, OwnerAction, OwnerError,
+            OWNER_PARTITION_ID,
+        },
+        NanoTimeStamp,
+    };
+
+    fn principal(id: u8) -> Principal {
+        Principal::from_slice(&[id; 29])
+    }
+
+    #[test]
+    fn test_roles() {
+        init_roles_partition("roles", 1).unwrap();
+
+        assert_eq!(create_role("admin").unwrap(), Nonce(0));
+        assert_eq!(create_role("operator").unwrap(), Nonce(1));
+        assert_eq!(
+            create_role("admin"),
+            Err(OwnerError::RoleExists("admin".to_string()))
+        );
+
+        assert_eq!(get_role("operator"), Some(Nonce(1)));
+        assert_eq!(get_roles().len(), 2);
+
+        assert!(grant_role("admin", principal(1)).unwrap());
+        assert!(!grant_role("admin", principal(1)).unwrap());
+        assert!(grant_role("admin", principal(2)).unwrap());
+        assert!(grant_role("operator", principal(1)).unwrap());
+        assert_eq!(
+            grant_role("viewer", principal(1)),
+            Err(OwnerError::RoleNotFound("viewer".to_string()))
+        );
+
+        assert!(has_role(principal(1), "admin"));
+        assert!(!has_role(principal(3), "admin"));
+        assert!(!has_role(principal(1), "viewer"));
+
+        assert_eq!(
+            get_role_members("admin").unwrap(),
+            vec![principal(1), principal(2)]
+        );
+        assert_eq!(get_principal_roles(principal(1)).len(), 2);
+
+        assert!(revoke_role("admin", principal(2)).unwrap());
+        assert!(!revoke_role("admin", principal(2)).unwrap());
+        assert_eq!(get_role_members("admin").unwrap(), vec![principal(1)]);
+
+        // Deleting a role revokes it from everyone
+        assert_eq!(delete_role("admin").unwrap(), Nonce(0));
+        assert!(!has_role(principal(1), "admin"));
+        assert_eq!(get_principal_roles(principal(1)).len(), 1);
+        assert_eq!(get_role_members("operator").unwrap(), vec![principal(1)]);
+    }
+
+    #[test]
+    fn test_caller_has_role() {
+        init_roles_partition("roles", 1).unwrap();
+
+        create_role("admin").unwrap();
+        create_role("operator").unwrap();
+
+        grant_role("operator", principal(1)).unwrap();
+
+        set_caller_mock(principal(1));
+
+        assert!(caller_has_role("operator").is_ok());
+        assert!(caller_has_role("admin").is_err());
+        assert!(caller_has_any_role(&["admin", "operator"]).is_ok());
+
+        set_caller_mock(principal(2));
+
+        assert!(caller_has_role("operator").is_err());
+        assert!(caller_has_any_role(&["admin", "operator"]).is_err());
+    }
+
+    #[test]
+    fn test_audit_log() {
+        init_roles_partition("roles", 1).unwrap();
+        init_audit_partition("audit", 3).unwrap();
+
+        set_caller_mock(principal(9));
+
+        create_role("admin").unwrap();
+        grant_role("admin", principal(1)).unwrap();
+        grant_role("admin", principal(1)).unwrap();
+        revoke_role("admin", principal(1)).unwrap();
+        delete_role("admin").unwrap();
+
+        // Calls that change nothing are not logged
+        assert_eq!(audit_log_len(), 4);
+
+        let entries = audit_log(0, 10);
+
+        assert!(entries.iter().all(|entry| entry.caller == principal(9)));
+        assert_eq!(
+            entries[1].action,
+            OwnerAction::RoleGranted {
+                role: Nonce(0),
+                name: "admin".to_string(),
+                principal: principal(1),
+            }
+        );
+        assert!(matches!(entries[3].action, OwnerAction::RoleDeleted { .. }));
+
+        assert_eq!(audit_log(3, 10).len(), 1);
+        assert!(audit_log(4, 10).is_empty());
+    }
+
+    #[test]
+    fn test_ownership_transfer() {
+        init_audit_partition("audit", 3).unwrap();
+        init_transfer_partition("transfer", 5).unwrap();
+
+        set_caller_mock(principal(1));
+
+        assert_eq!(
+            propose_owner(principal(2), NanoTimeStamp::now().add_hours(1)),
+            Err(OwnerError::NoOwner)
+        );
+
+        init_owner_with(principal(1)).unwrap();
+
+        assert_eq!(
+            propose_owner(principal(1), NanoTimeStamp::now().add_hours(1)),
+            Err(OwnerError::AlreadyOwner(principal(1)))
+        );
+        assert!(matches!(
+            propose_owner(principal(2), NanoTimeStamp(1)),
+            Err(OwnerError::InvalidExpiry(_))
+        ));
+        assert_eq!(accept_ownership(), Err(OwnerError::NoPendingTransfer));
+
+        let transfer = propose_owner(principal(2), NanoTimeStamp::now().add_hours(1)).unwrap();
+
+        assert_eq!(transfer.proposed_by, principal(1));
+        assert_eq!(get_pending_transfer(), Some(transfer));
+
+        // Only the proposed owner can accept
+        set_caller_mock(principal(3));
+
+        assert_eq!(
+            accept_ownership(),
+            Err(OwnerError::NotPendingOwner(principal(3)))
+        );
+
+        set_caller_mock(principal(2));
+
+        assert!(caller_is_owner().is_err());
+        assert_eq!(accept_ownership().unwrap(), principal(1));
+        assert!(caller_is_owner().is_ok());
+        assert_eq!(get_owner(), principal(2));
+        assert_eq!(get_pending_transfer(), None);
+
+        assert_eq!(
+            audit_log(0, 10).last().unwrap().action,
+            OwnerAction::OwnerChanged {
+                old_owner: principal(1),
+                new_owner: principal(2),
+            }
+        );
+    }
+
+    #[test]
+    fn test_ownership_transfer_cancel_and_expiry() {
+        init_audit_partition("audit", 3).unwrap();
+        init_transfer_partition("transfer", 5).unwrap();
+
+        set_caller_mock(principal(1));
+
+        init_owner_with(principal(1)).unwrap();
+
+        propose_owner(principal(2), NanoTimeStamp::now().add_hours(1)).unwrap();
+
+        assert_eq!(cancel_transfer().unwrap().new_owner, principal(2));
+        assert_eq!(cancel_transfer(), Err(OwnerError::NoPendingTransfer));
+
+        set_caller_mock(principal(2));
+
+        assert_eq!(accept_ownership(), Err(OwnerError::NoPendingTransfer));
+
+        // A proposal can't be accepted once expired
+        set_caller_mock(principal(1));
+
+        let expires_at = NanoTimeStamp(NanoTimeStamp::now().0 + NanoTimeStamp::NS_PER_MILLISECOND);
+
+        propose_owner(principal(2), expires_at.clone()).unwrap();
+
+        std::thread::sleep(std::time::Duration::from_millis(2));
+
+        set_caller_mock(principal(2));
+
+        assert_eq!(
+            accept_ownership(),
+            Err(OwnerError::TransferExpired(expires_at))
+        );
+        assert_eq!(get_owner(), principal(1));
+
+        let actions: Vec<OwnerAction> = audit_log(0, 10).into_iter().map(|e| e.action).collect();
+
+        assert!(matches!(actions[0], OwnerAction::OwnerInitialized { .. }));
+        assert!(matches!(actions[1], OwnerAction::OwnershipProposed { .. }));
+        assert!(matches!(actions[2], OwnerAction::TransferCancelled { .. }));
+        assert_eq!(actions.len(), 4);
+    }
+
+    #[test]
+    fn test_transfer_partition() {
+        init_transfer_partition("custom_transfer", 200).unwrap();
+
+        set_caller_mock(principal(1));
+
+        init_owner_with(principal(1)).unwrap();
+
+        propose_owner(principal(2), NanoTimeStamp::now().add_hours(1)).unwrap();
+
+        assert_eq!(
+            with_stable_mem(|pm| pm.partition("custom_transfer")),
+            Some(200)
+        );
+
+        assert!(init_transfer_partition("other_transfer", 201).is_err());
+    }
+
+    #[test]
+    fn test_owner_is_not_initialized_by_first_caller() {
+        set_caller_mock(principal(1));
+
+        assert_eq!(try_get_owner(), None);
+        assert!(caller_is_owner().is_err());
+        assert!(set_owner(principal(1)).is_err());
+
+        // A failed call must not write the owner
+        assert_eq!(try_get_owner(), None);
+        assert_eq!(audit_log_len(), 0);
+
+        init_owner_with(principal(2)).unwrap();
+
+        assert_eq!(get_owner(), principal(2));
+
+        // An existing owner can't be overwritten, e.g. from `#[post_upgrade]`
+        assert_eq!(
+            init_owner_with(principal(1)),
+            Err(OwnerError::OwnerExists(principal(2)))
+        );
+        assert_eq!(get_owner(), principal(2));
+        assert!(caller_is_owner().is_err());
+
+        assert_eq!(set_owner(principal(1)).unwrap(), principal(2));
+        assert!(caller_is_owner().is_ok());
+
+        assert_eq!(
+            with_stable_mem(|pm| pm.partition("owner")),
+            Some(OWNER_PARTITION_ID)
+        );
+    }
+
+    #[test]
+    fn test_owner_partition() {
+        init_owner_partition("custom_owner", 200).unwrap();
+        init_owner_with(principal(1)).unwrap();
+
+        assert_eq!(get_owner(), principal(1));
+        assert_eq!(
+            with_stable_mem(|pm| pm.partition("custom_owner")),
+            Some(200)
+        );
+        assert_eq!(with_stable_mem(|pm| pm.partition("owner")), None);
+
+        // The partition can't move once the owner is loaded
+        assert!(init_owner_partition("other_owner", 201).is_err());
+    }
+
+    #[test]
+    fn test_roles_and_audit_partitions() {
+        init_roles_partition("custom_roles", 200).unwrap();
+        init_audit_partition("custom_audit", 210).unwrap();
+
+        set_caller_mock(principal(1));
+
+        create_role("admin").unwrap();
+
+        assert_eq!(
+            with_stable_mem(|pm| pm.partition("custom_roles")),
+            Some(200)
+        );
+        assert_eq!(
+            with_stable_mem(|pm| pm.partition("custom_roles_members")),
+            Some(201)
+        );
+        assert_eq!(
+            with_stable_mem(|pm| pm.partition("custom_audit_data")),
+            Some(211)
+        );
+        assert_eq!(with_stable_mem(|pm| pm.partition("owner_roles")), None);
+        assert_eq!(audit_log_len(), 1);
+
+        // The partitions can't move once they are loaded
+        assert!(init_roles_partition("other_roles", 220).is_err());
+        assert!(init_audit_partition("other_audit", 222).is_err());
+    }
+
+    #[test]
+    fn test_roles_audit_and_transfer_are_opt_in() {
+        set_caller_mock(principal(1));
+
+        init_owner_with(principal(1)).unwrap();
+        set_owner(principal(2)).unwrap();
+
+        assert_eq!(create_role("admin"), Err(OwnerError::RolesNotInitialized));
+        assert_eq!(
+            grant_role("admin", principal(1)),
+            Err(OwnerError::RolesNotInitialized)
+        );
+        assert_eq!(get_role("admin"), None);
+        assert!(get_roles().is_empty());
+        assert!(!has_role(principal(1), "admin"));
+
+        assert_eq!(
+            propose_owner(principal(3), NanoTimeStamp::now().add_hours(1)),
+            Err(OwnerError::TransferNotInitialized)
+        );
+        assert_eq!(get_pending_transfer(), None);
+        assert_eq!(cancel_transfer(), Err(OwnerError::NoPendingTransfer));
+
+        // Nothing is recorded and no partition is claimed
+        assert_eq!(audit_log_len(), 0);
+        assert!(audit_log(0, 10).is_empty());
+        assert_eq!(with_stable_mem(|pm| pm.partitions().len()), 1);
+    }
+
+    #[test]
+    fn test_caller_is_owner_or_controller() {
+        set_controllers_mock(vec![principal(9)]);
+
+        set_caller_mock(principal(9));
+
+        // Controllers pass before the owner is initialized
+        assert!(caller_is_owner().is_err());
+        assert!(caller_is_owner_or_controller().is_ok());
+
+        init_owner_with(principal(1)).unwrap();
+
+        assert!(caller_is_owner_or_controller().is_ok());
+
+        set_caller_mock(principal(1));
+
+        assert!(caller_is_owner_or_controller().is_ok());
+
+        set_caller_mock(principal(2));
+
+        assert!(caller_is_owner_or_controller().is_err());
+    }
+}
@@ -0,0 +1,171 @@
+use candid::{CandidType, Decode, Encode, Principal};
+use serde::{Deserialize, Serialize};
+use std::{borrow::Cow, cell::RefCell};
+
+use crate::{
+    memory::{
+        error::StableMemoryError,
+        init_stable_mem,
+        types::{Bound, DefaultStableCell, Storable},
+    },
+    NanoTimeStamp,
+};
+
+use super::{
+    audit::{record, OwnerAction},
+    error::OwnerError,
+    ic_cdk_caller, replace_owner, try_get_owner,
+};
+
+/// An ownership transfer waiting for the new owner to accept it.
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct OwnershipTransfer {
+    pub new_owner: Principal,
+    pub proposed_by: Principal,
+    pub proposed_at: NanoTimeStamp,
+    pub expires_at: NanoTimeStamp,
+}
+
+impl OwnershipTransfer {
+    pub fn is_expired(&self) -> bool {
+        self.expires_at.has_passed()
+    }
+}
+
+impl Storable for OwnershipTransfer {
+    const BOUND: Bound = Bound::Unbounded;
+
+    fn from_bytes(bytes: Cow<'_, [u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).unwrap()
+    }
+
+    fn to_bytes(&self) -> Cow<'_, [u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
+}
+
+thread_local! {
+    static PENDING_TRANSFER: RefCell<Option<DefaultStableCell<Option<OwnershipTransfer>>>> = const { RefCell::new(None) };
+}
+
+fn with_pending_transfer<F, R>(f: F) -> Result<R, OwnerError>
+where
+    F: FnOnce(&mut DefaultStableCell<Option<OwnershipTransfer>>) -> R,
+{
+    PENDING_TRANSFER.with(|pending| match pending.borrow_mut().as_mut() {
+        Some(pending) => Ok(f(pending)),
+        None => Err(OwnerError::TransferNotInitialized),
+    })
+}
+
+/// Enables two-step ownership transfers, keeping the pending transfer in the partition
+/// `name` at `id`. Until then [`propose_owner`] fails with `TransferNotInitialized`.
+///
+/// Must be called in `#[init]` and `#[post_upgrade]` before a transfer is proposed.
+pub fn init_transfer_partition(name: &str, id: u8) -> Result<(), StableMemoryError> {
+    PENDING_TRANSFER.with(|pending| {
+        if pending.borrow().is_some() {
+            return Err(StableMemoryError::PartitionExists);
+        }
+
+        *pending.borrow_mut() = Some(init_stable_mem(name, id)?);
+
+        Ok(())
+    })
+}
+
+fn set_pending_transfer(transfer: Option<OwnershipTransfer>) -> Result<(), OwnerError> {
+    with_pending_transfer(|pending| {
+        pending
+            .set(transfer)
+            .map(|_| ())
+            .map_err(|err| OwnerError::TransferFailed(format!("{:?}", err)))
+    })?
+}
+
+/// Returns the pending transfer, even if it expired.
+pub fn get_pending_transfer() -> Option<OwnershipTransfer> {
+    with_pending_transfer(|pending| pending.get().clone())
+        .ok()
+        .flatten()
+}
+
+/// Proposes `new_owner` as the owner until `expires_at`, replacing any pending proposal.
+/// The owner only changes once `new_owner` calls [`accept_ownership`].
+///
+/// Doesn't check the caller, guard the method calling it with `caller_is_owner`.
+pub fn propose_owner(
+    new_owner: Principal,
+    expires_at: NanoTimeStamp,
+) -> Result<OwnershipTransfer, OwnerError> {
+    // Fail early if transfers are not enabled
+    with_pending_transfer(|_| ())?;
+
+    if new_owner == try_get_owner().ok_or(OwnerError::NoOwner)? {
+        return Err(OwnerError::AlreadyOwner(new_owner));
+    }
+
+    if expires_at.has_passed() {
+        return Err(OwnerError::InvalidExpiry(expires_at));
+    }
+
+    let transfer = OwnershipTransfer {
+        new_owner,
+        proposed_by: ic_cdk_caller(),
+        proposed_at: NanoTimeStamp::now(),
+        expires_at: expires_at.clone(),
+    };
+
+    record(OwnerAction::OwnershipProposed {
+        new_owner,
+        expires_at,
+    })?;
+
+    set_pending_transfer(Some(transfer.clone()))?;
+
+    Ok(transfer)
+}
+
+/// Makes the caller the owner if it is the proposed owner and the proposal didn't expire,
+/// returns the previous owner.
+pub fn accept_ownership() -> Result<Principal, OwnerError> {
+    let transfer = get_pending_transfer().ok_or(OwnerError::NoPendingTransfer)?;
+
+    let caller_id = ic_cdk_caller();
+
+    if caller_id != transfer.new_owner {
+        return Err(OwnerError::NotPendingOwner(caller_id));
+    }
+
+    if transfer.is_expired() {
+        return Err(OwnerError::TransferExpired(transfer.expires_at));
+    }
+
+    let old_owner = try_get_owner().ok_or(OwnerError::NoOwner)?;
+
+    record(OwnerAction::OwnerChanged {
+        old_owner,
+        new_owner: transfer.new_owner,
+    })?;
+
+    set_pending_transfer(None)?;
+
+    replace_owner(transfer.new_owner);
+
+    Ok(old_owner)
+}
+
+/// Cancels the pending transfer, expired or not.
+///
+/// Doesn't check the caller, guard the method calling it with `caller_is_owner`.
+pub fn cancel_transfer() -> Result<OwnershipTransfer, OwnerError> {
+    let transfer = get_pending_transfer().ok_or(OwnerError::NoPendingTransfer)?;
+
+    record(OwnerAction::TransferCancelled {
+        new_owner: transfer.new_owner,
+    })?;
+
+    set_pending_transfer(None)?;
+
+    Ok(transfer)
+}
@@ -0,0 +1,295 @@
+use candid::Principal;
+use std::{
+    cell::RefCell,
+    collections::{HashMap, VecDeque},
+};
+
+use crate::{error::HelperError, NanoTimeStamp};
+
+#[cfg(test)]
+use crate::mocks::caller_mock as ic_cdk_caller;
+#[cfg(not(test))]
+use ic_cdk::caller as ic_cdk_caller;
+
+mod test;
+
+/// How many calls a caller can make to a method.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RateLimit {
+    /// Up to `capacity` calls in a burst, then one more every `refill_interval` nanoseconds.
+    TokenBucket { capacity: u64, refill_interval: u64 },
+    /// Up to `max_calls` calls in any `window` nanoseconds.
+    SlidingWindow { max_calls: u64, window: u64 },
+}
+
+impl RateLimit {
+    pub fn token_bucket(capacity: u64, refill_interval: u64) -> Self {
+        RateLimit::TokenBucket {
+            capacity,
+            refill_interval,
+        }
+    }
+
+    pub fn sliding_window(max_calls: u64, window: u64) -> Self {
+        RateLimit::SlidingWindow { max_calls, window }
+    }
+
+    /// One call every `interval` nanoseconds, e.g. `SYSTEM_RATE_LIMIT` of the ledger module.
+    pub fn min_interval(interval: u64) -> Self {
+        Self::token_bucket(1, interval)
+    }
+
+    pub fn per_second(calls: u64) -> Self {
+        Self::sliding_window(calls, NanoTimeStamp::NS_PER_SECOND)
+    }
+
+    pub fn per_minute(calls: u64) -> Self {
+        Self::sliding_window(calls, NanoTimeStamp::NS_PER_MINUTE)
+    }
+
+    fn new_state(&self, now: u64) -> LimiterState {
+        match self {
+            RateLimit::TokenBucket { capacity, .. } => LimiterState::Bucket {
+                tokens: *capacity,
+                last_refill: now,
+            },
+            RateLimit::SlidingWindow { .. } => LimiterState::Window {
+                calls: VecDeque::new(),
+            },
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+enum LimiterState {
+    Bucket { tokens: u64, last_refill: u64 },
+    Window { calls: VecDeque<u64> },
+}
+
+impl LimiterState {
+    /// Brings the state up to `now` and returns the calls left.
+    fn refresh(&mut self, limit: &RateLimit, now: u64) -> u64 {
+        match (self, limit) {
+            (
+                LimiterState::Bucket {
+                    tokens,
+                    last_refill,
+                },
+                RateLimit::TokenBucket {
+                    capacity,
+                    refill_interval,
+                },
+            ) => {
+                let refilled = now.saturating_sub(*last_refill) / (*refill_interval).max(1);
+
+                if refilled > 0 {
+                    *tokens = tokens.saturating_add(refilled).min(*capacity);
+                    *last_refill += refilled * refill_interval;
+                }
+
+                if *tokens == *capacity {
+                    *last_refill = now;
+                }
+
+                *tokens
+            }
+            (LimiterState::Window { calls }, RateLimit::SlidingWindow { max_calls, window }) => {
+                while matches!(calls.front(), Some(call) if call + window <= now) {
+                    calls.pop_front();
+                }
+
+                max_calls.saturating_sub(calls.len() as u64)
+            }
+            (state, limit) => {
+                // The limit of the method changed kind.
+                *state = limit.new_state(now);
+
+                state.refresh(limit, now)
+            }
+        }
+    }
+
+    fn consume(&mut self, now: u64) {
+        match self {
+            LimiterState::Bucket { tokens, .. } => *tokens -= 1,
+            LimiterState::Window { calls } => calls.push_back(now),
+        }
+    }
+}
+
+/// Limits the calls of each caller, per method.
+///
+/// Methods without their own limit use the default one, if any. The state lives on
+/// the heap, so limits start over after an upgrade.
+///
+/// # Example
+/// ```
+/// use b3_utils::ratelimit::{RateLimit, RateLimiter};
+/// use b3_utils::NanoTimeStamp;
+/// use candid::Principal;
+///
+/// let mut limiter = RateLimiter::default()
+///     .default_limit(RateLimit::per_second(10))
+///     .method("transfer", RateLimit::min_interval(NanoTimeStamp::NS_PER_MINUTE));
+///
+/// let caller = Principal::anonymous();
+/// let now = NanoTimeStamp::now();
+///
+/// assert!(limiter.check("transfer", caller, &now).is_ok());
+/// assert!(limiter.check("transfer", caller, &now).is_err());
+/// assert!(limiter.check("balance", caller, &now).is_ok());
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct RateLimiter {
+    default_limit: Option<RateLimit>,
+    limits: HashMap<String, RateLimit>,
+    states: HashMap<(String, Principal), LimiterState>,
+}
+
+impl RateLimiter {
+    pub fn default_limit(mut self, limit: RateLimit) -> Self {
+        self.default_limit = Some(limit);
+        self
+    }
+
+    pub fn method(mut self, method: &str, limit: RateLimit) -> Self {
+        self.limits.insert(method.to_string(), limit);
+        self
+    }
+
+    pub fn set_default_limit(&mut self, limit: Option<RateLimit>) {
+        self.default_limit = limit;
+    }
+
+    pub fn set_limit(&mut self, method: &str, limit: RateLimit) {
+        self.limits.insert(method.to_string(), limit);
+    }
+
+    pub fn remove_limit(&mut self, method: &str) -> Option<RateLimit> {
+        self.limits.remove(method)
+    }
+
+    /// Returns the limit applied to `method`.
+    pub fn limit(&self, method: &str) -> Option<RateLimit> {
+        self.limits.get(method).copied().or(self.default_limit)
+    }
+
+    /// Counts a call of `caller` to `method`, or rejects it if the limit is reached.
+    pub fn check(
+        &mut self,
+        method: &str,
+        caller: Principal,
+        now: &NanoTimeStamp,
+    ) -> Result<(), HelperError> {
+        let limit = match self.limit(method) {
+            Some(limit) => limit,
+            None => return Ok(()),
+        };
+
+        let state = self
+            .states
+            .entry((method.to_string(), caller))
+            .or_insert_with(|| limit.new_state(now.0));
+
+        if state.refresh(&limit, now.0) == 0 {
+            return Err(HelperError::RateLimitExceeded);
+        }
+
+        state.consume(now.0);
+
+        Ok(())
+    }
+
+    /// Returns the calls `caller` can still make to `method` at `now`.
+    pub fn remaining(&self, method: &str, caller: Principal, now: &NanoTimeStamp) -> u64 {
+        let limit = match self.limit(method) {
+            Some(limit) => limit,
+            None => return u64::MAX,
+        };
+
+        let mut state = self
+            .states
+            .get(&(method.to_string(), caller))
+            .cloned()
+            .unwrap_or_else(|| limit.new_state(now.0));
+
+        state.refresh(&limit, now.0)
+    }
+
+    /// Forgets the calls of `caller` to every method.
+    pub fn reset(&mut self, caller: Principal) {
+        self.states.retain(|(_, principal), _| *principal != caller);
+    }
+
+    /// Drops the state of callers back to their full limit, to bound the memory used.
+    pub fn prune(&mut self, now: &NanoTimeStamp) -> usize {
+        let before = self.states.len();
+
+        let limits = &self.limits;
+        let default_limit = self.default_limit;
+
+        self.states.retain(|(method, _), state| {
+            match limits.get(method).copied().or(default_limit) {
+                Some(limit) => {
+                    let full = match limit {
+                        RateLimit::TokenBucket { capacity, .. } => capacity,
+                        RateLimit::SlidingWindow { max_calls, .. } => max_calls,
+                    };
+
+                    state.refresh(&limit, now.0) < full
+                }
+                None => false,
+            }
+        });
+
+        before - self.states.len()
+    }
+
+    pub fn len(&self) -> usize {
+        self.states.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.states.is_empty()
+    }
+}
+
+thread_local! {
+    static RATE_LIMITER: RefCell<RateLimiter> = RefCell::new(RateLimiter::default());
+}
+
+pub fn with_rate_limiter<F, R>(f: F) -> R
+where
+    F: FnOnce(&RateLimiter) -> R,
+{
+    RATE_LIMITER.with(|limiter| f(&limiter.borrow()))
+}
+
+pub fn with_rate_limiter_mut<F, R>(f: F) -> R
+where
+    F: FnOnce(&mut RateLimiter) -> R,
+{
+    RATE_LIMITER.with(|limiter| f(&mut limiter.borrow_mut()))
+}
+
+/// Counts a call of the caller to `method` in the global limiter.
+pub fn check_rate_limit(method: &str) -> Result<(), HelperError> {
+    let caller = ic_cdk_caller();
+
+    with_rate_limiter_mut(|limiter| limiter.check(method, caller, &NanoTimeStamp::now()))
+}
+
+/// Guard that rejects the caller once it reached the limit of `method`.
+///
+/// Guards take no arguments, so wrap it for each method:
+/// ```
+/// use b3_utils::ratelimit::caller_within_rate_limit;
+///
+/// // Used as `#[update(guard = "transfer_rate_limit")]`
+/// fn transfer_rate_limit() -> Result<(), String> {
+///     caller_within_rate_limit("transfer")
+/// }
+/// ```
+pub fn caller_within_rate_limit(method: &str) -> Result<(), String> {
+    check_rate_limit(method).map_err(|e| e.to_string())
+}
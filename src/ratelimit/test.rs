@@ -0,0 +1,122 @@
+#[cfg(test)]
+mod tests {
+    use candid::Principal;
+
+    use crate::{
+        error::HelperError,
+        mocks::set_caller_mock,
+        ratelimit::{caller_within_rate_limit, with_rate_limiter_mut, RateLimit, RateLimiter},
+        NanoTimeStamp,
+    };
+
+    fn caller(id: u8) -> Principal {
+        Principal::from_slice(&[id; 29])
+    }
+
+    #[test]
+    fn test_token_bucket() {
+        let mut limiter = RateLimiter::default().method(
+            "transfer",
+            RateLimit::token_bucket(2, NanoTimeStamp::NS_PER_SECOND),
+        );
+
+        let now = NanoTimeStamp(NanoTimeStamp::NS_PER_HOUR);
+
+        assert!(limiter.check("transfer", caller(1), &now).is_ok());
+        assert!(limiter.check("transfer", caller(1), &now).is_ok());
+        assert_eq!(
+            limiter.check("transfer", caller(1), &now),
+            Err(HelperError::RateLimitExceeded)
+        );
+        assert_eq!(limiter.remaining("transfer", caller(1), &now), 0);
+
+        // Other callers and methods aren't affected
+        assert!(limiter.check("transfer", caller(2), &now).is_ok());
+        assert!(limiter.check("balance", caller(1), &now).is_ok());
+
+        let later = now.add_secs(1);
+
+        assert_eq!(limiter.remaining("transfer", caller(1), &later), 1);
+        assert!(limiter.check("transfer", caller(1), &later).is_ok());
+        assert!(limiter.check("transfer", caller(1), &later).is_err());
+
+        // Never refills above the capacity
+        assert_eq!(
+            limiter.remaining("transfer", caller(1), &later.add_mins(1)),
+            2
+        );
+    }
+
+    #[test]
+    fn test_sliding_window() {
+        let mut limiter = RateLimiter::default().default_limit(RateLimit::per_second(2));
+
+        let now = NanoTimeStamp(NanoTimeStamp::NS_PER_HOUR);
+        let half_second = NanoTimeStamp(now.0 + NanoTimeStamp::NS_PER_MILLISECOND * 500);
+
+        assert!(limiter.check("transfer", caller(1), &now).is_ok());
+        assert!(limiter.check("transfer", caller(1), &half_second).is_ok());
+        assert!(limiter.check("transfer", caller(1), &half_second).is_err());
+
+        // Only the first call left the window
+        let second = now.add_secs(1);
+
+        assert_eq!(limiter.remaining("transfer", caller(1), &second), 1);
+        assert!(limiter.check("transfer", caller(1), &second).is_ok());
+        assert!(limiter.check("transfer", caller(1), &second).is_err());
+    }
+
+    #[test]
+    fn test_reset_and_prune() {
+        let mut limiter = RateLimiter::default()
+            .default_limit(RateLimit::min_interval(NanoTimeStamp::NS_PER_SECOND));
+
+        let now = NanoTimeStamp(NanoTimeStamp::NS_PER_HOUR);
+
+        limiter.check("a", caller(1), &now).unwrap();
+        limiter.check("b", caller(1), &now).unwrap();
+        limiter.check("a", caller(2), &now).unwrap();
+
+        assert_eq!(limiter.len(), 3);
+
+        limiter.reset(caller(1));
+
+        assert_eq!(limiter.len(), 1);
+        assert!(limiter.check("a", caller(1), &now).is_ok());
+
+        assert_eq!(limiter.prune(&now), 0);
+        assert_eq!(limiter.prune(&now.add_secs(1)), 2);
+        assert!(limiter.is_empty());
+
+        // Changing the limit of a method applies to the next call
+        limiter.check("a", caller(1), &now).unwrap();
+        limiter.set_limit("a", RateLimit::per_second(2));
+
+        assert_eq!(limiter.remaining("a", caller(1), &now), 2);
+        assert!(limiter.check("a", caller(1), &now).is_ok());
+    }
+
+    #[test]
+    fn test_caller_within_rate_limit() {
+        assert!(caller_within_rate_limit("transfer").is_ok());
+
+        with_rate_limiter_mut(|limiter| {
+            limiter.set_limit(
+                "transfer",
+                RateLimit::min_interval(NanoTimeStamp::NS_PER_HOUR),
+            )
+        });
+
+        set_caller_mock(caller(1));
+
+        assert!(caller_within_rate_limit("transfer").is_ok());
+        assert_eq!(
+            caller_within_rate_limit("transfer"),
+            Err(HelperError::RateLimitExceeded.to_string())
+        );
+
+        set_caller_mock(caller(2));
+
+        assert!(caller_within_rate_limit("transfer").is_ok());
+    }
+}
@@ -11,6 +11,9 @@ use ic_cdk::api::time as ic_timestamp;
 mod test;
 mod traits;
 
+mod cron;
+pub use cron::*;
+
 #[derive(
     Default, CandidType, Deserialize, Serialize, PartialEq, Eq, PartialOrd, Ord, Clone, Debug,
 )]
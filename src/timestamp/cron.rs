@@ -0,0 +1,299 @@
+use candid::CandidType;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use super::NanoTimeStamp;
+
+/// A calendar schedule in UTC, parsed from a 5-field cron expression:
+/// `minute hour day-of-month month day-of-week`.
+///
+/// Each field accepts `*`, numbers, ranges (`1-5`), lists (`1,15`) and steps (`*/15`, `0-30/10`).
+/// Day-of-week goes from 0 (Sunday) to 6, 7 is also accepted for Sunday.
+/// When both day-of-month and day-of-week are restricted, a day matching either of them fires.
+///
+/// # Example
+/// ```
+/// use b3_utils::{CronSchedule, NanoTimeStamp};
+///
+/// let schedule = CronSchedule::parse("0 0 1 * *").unwrap();
+///
+/// // 2024-01-15T10:30:00Z
+/// let time = NanoTimeStamp(1_705_314_600 * NanoTimeStamp::NS_PER_SECOND);
+///
+/// // 2024-02-01T00:00:00Z
+/// let next = schedule.next_after(&time).unwrap();
+/// assert_eq!(next, NanoTimeStamp(1_706_745_600 * NanoTimeStamp::NS_PER_SECOND));
+/// ```
+#[derive(CandidType, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CronSchedule {
+    minutes: u64,
+    hours: u32,
+    days_of_month: u32,
+    months: u16,
+    days_of_week: u8,
+    flags: u8,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CronError {
+    InvalidFieldCount(usize),
+    InvalidValue(String),
+    OutOfRange(String),
+}
+
+#[rustfmt::skip]
+impl fmt::Display for CronError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CronError::InvalidFieldCount(count) => write!(f, "Invalid cron expression: expected 5 fields, got {}", count),
+            CronError::InvalidValue(field) => write!(f, "Invalid cron field: {}", field),
+            CronError::OutOfRange(field) => write!(f, "Cron field out of range: {}", field),
+        }
+    }
+}
+
+impl CronSchedule {
+    const DAY_OF_MONTH_ANY: u8 = 1;
+    const DAY_OF_WEEK_ANY: u8 = 2;
+
+    /// Searching stops after this many days without a match (e.g. `0 0 30 2 *`).
+    const MAX_SEARCH_DAYS: u64 = 366 * 5;
+
+    pub fn parse(expression: &str) -> Result<Self, CronError> {
+        let expression = match expression.trim() {
+            "@yearly" | "@annually" => "0 0 1 1 *",
+            "@monthly" => "0 0 1 * *",
+            "@weekly" => "0 0 * * 0",
+            "@daily" | "@midnight" => "0 0 * * *",
+            "@hourly" => "0 * * * *",
+            expression => expression,
+        };
+
+        let fields: Vec<&str> = expression.split_whitespace().collect();
+
+        if fields.len() != 5 {
+            return Err(CronError::InvalidFieldCount(fields.len()));
+        }
+
+        let minutes = parse_field(fields[0], 0, 59)?;
+        let hours = parse_field(fields[1], 0, 23)?;
+        let days_of_month = parse_field(fields[2], 1, 31)?;
+        let months = parse_field(fields[3], 1, 12)?;
+        let mut days_of_week = parse_field(fields[4], 0, 7)?;
+
+        // 7 is an alias for Sunday.
+        if days_of_week & (1 << 7) != 0 {
+            days_of_week = (days_of_week | 1) & !(1 << 7);
+        }
+
+        let mut flags = 0;
+        if fields[2] == "*" {
+            flags |= Self::DAY_OF_MONTH_ANY;
+        }
+        if fields[4] == "*" {
+            flags |= Self::DAY_OF_WEEK_ANY;
+        }
+
+        Ok(Self {
+            minutes,
+            hours: hours as u32,
+            days_of_month: days_of_month as u32,
+            months: months as u16,
+            days_of_week: days_of_week as u8,
+            flags,
+        })
+    }
+
+    /// Every day at the given UTC time.
+    pub fn daily(hour: u8, minute: u8) -> Result<Self, CronError> {
+        Self::parse(&format!("{} {} * * *", minute, hour))
+    }
+
+    /// Every week on the given day (0 is Sunday) at the given UTC time.
+    pub fn weekly(day_of_week: u8, hour: u8, minute: u8) -> Result<Self, CronError> {
+        Self::parse(&format!("{} {} * * {}", minute, hour, day_of_week))
+    }
+
+    /// Every month on the given day at the given UTC time.
+    pub fn monthly(day_of_month: u8, hour: u8, minute: u8) -> Result<Self, CronError> {
+        Self::parse(&format!("{} {} {} * *", minute, hour, day_of_month))
+    }
+
+    /// Returns the first time strictly after `time` matching the schedule.
+    pub fn next_after(&self, time: &NanoTimeStamp) -> Option<NanoTimeStamp> {
+        let mut minutes = time.0 / NanoTimeStamp::NS_PER_MINUTE + 1;
+        let last_day = minutes / MINUTES_PER_DAY + Self::MAX_SEARCH_DAYS;
+
+        while minutes / MINUTES_PER_DAY <= last_day {
+            let days = minutes / MINUTES_PER_DAY;
+            let (_, month, day) = civil_from_days(days);
+
+            if self.months & (1 << month) == 0 {
+                minutes = first_day_of_next_month(days) * MINUTES_PER_DAY;
+                continue;
+            }
+
+            if !self.matches_day(days, day) {
+                minutes = (days + 1) * MINUTES_PER_DAY;
+                continue;
+            }
+
+            let hour = (minutes % MINUTES_PER_DAY) / 60;
+
+            if self.hours & (1 << hour) == 0 {
+                minutes = (minutes / 60 + 1) * 60;
+                continue;
+            }
+
+            if self.minutes & (1 << (minutes % 60)) == 0 {
+                minutes += 1;
+                continue;
+            }
+
+            return minutes
+                .checked_mul(NanoTimeStamp::NS_PER_MINUTE)
+                .map(NanoTimeStamp);
+        }
+
+        None
+    }
+
+    fn matches_day(&self, days: u64, day: u32) -> bool {
+        // 1970-01-01 was a Thursday.
+        let weekday = (days + 4) % 7;
+
+        let day_of_month = self.days_of_month & (1 << day) != 0;
+        let day_of_week = self.days_of_week & (1 << weekday) != 0;
+
+        match (
+            self.flags & Self::DAY_OF_MONTH_ANY != 0,
+            self.flags & Self::DAY_OF_WEEK_ANY != 0,
+        ) {
+            (true, true) => true,
+            (true, false) => day_of_week,
+            (false, true) => day_of_month,
+            (false, false) => day_of_month || day_of_week,
+        }
+    }
+
+    pub fn to_le_bytes(&self) -> [u8; 20] {
+        let mut bytes = [0u8; 20];
+        bytes[0..8].copy_from_slice(&self.minutes.to_le_bytes());
+        bytes[8..12].copy_from_slice(&self.hours.to_le_bytes());
+        bytes[12..16].copy_from_slice(&self.days_of_month.to_le_bytes());
+        bytes[16..18].copy_from_slice(&self.months.to_le_bytes());
+        bytes[18] = self.days_of_week;
+        bytes[19] = self.flags;
+        bytes
+    }
+
+    pub fn from_le_bytes(bytes: [u8; 20]) -> Self {
+        Self {
+            minutes: u64::from_le_bytes(bytes[0..8].try_into().unwrap()),
+            hours: u32::from_le_bytes(bytes[8..12].try_into().unwrap()),
+            days_of_month: u32::from_le_bytes(bytes[12..16].try_into().unwrap()),
+            months: u16::from_le_bytes(bytes[16..18].try_into().unwrap()),
+            days_of_week: bytes[18],
+            flags: bytes[19],
+        }
+    }
+}
+
+impl std::str::FromStr for CronSchedule {
+    type Err = CronError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+const MINUTES_PER_DAY: u64 = 24 * 60;
+
+fn parse_field(field: &str, min: u64, max: u64) -> Result<u64, CronError> {
+    let mut bits = 0u64;
+
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => {
+                let step = parse_number(step, field)?;
+                if step == 0 {
+                    return Err(CronError::InvalidValue(field.to_string()));
+                }
+                (range, step)
+            }
+            None => (part, 1),
+        };
+
+        let (start, end) = if range == "*" {
+            (min, max)
+        } else if let Some((start, end)) = range.split_once('-') {
+            (parse_number(start, field)?, parse_number(end, field)?)
+        } else {
+            let value = parse_number(range, field)?;
+            // `5/15` means from 5 to the end of the range.
+            if part.contains('/') {
+                (value, max)
+            } else {
+                (value, value)
+            }
+        };
+
+        if start < min || end > max || start > end {
+            return Err(CronError::OutOfRange(field.to_string()));
+        }
+
+        // A step past the end of the range stops the loop instead of overflowing
+        let mut value = Some(start);
+        while let Some(current) = value.filter(|value| *value <= end) {
+            bits |= 1 << current;
+            value = current.checked_add(step);
+        }
+    }
+
+    Ok(bits)
+}
+
+fn parse_number(value: &str, field: &str) -> Result<u64, CronError> {
+    value
+        .parse()
+        .map_err(|_| CronError::InvalidValue(field.to_string()))
+}
+
+/// Converts days since the UNIX epoch to a `(year, month, day)` date.
+/// See <http://howardhinnant.github.io/date_algorithms.html#civil_from_days>.
+fn civil_from_days(days: u64) -> (u64, u32, u32) {
+    let z = days + 719_468;
+    let era = z / 146_097;
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = (if mp < 10 { mp + 3 } else { mp - 9 }) as u32;
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    (year, month, day)
+}
+
+/// Converts a `(year, month, day)` date to days since the UNIX epoch.
+fn days_from_civil(year: u64, month: u32, day: u32) -> u64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year / 400;
+    let yoe = year - era * 400;
+    let month = month as u64;
+    let mp = if month > 2 { month - 3 } else { month + 9 };
+    let doy = (153 * mp + 2) / 5 + day as u64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+
+    era * 146_097 + doe - 719_468
+}
+
+fn first_day_of_next_month(days: u64) -> u64 {
+    let (year, month, _) = civil_from_days(days);
+
+    if month == 12 {
+        days_from_civil(year + 1, 1, 1)
+    } else {
+        days_from_civil(year, month + 1, 1)
+    }
+}
@@ -1,6 +1,6 @@
 #[cfg(test)]
 mod tests {
-    use crate::NanoTimeStamp;
+    use crate::{CronError, CronSchedule, NanoTimeStamp};
 
     #[test]
     fn test_time_conversions() {
@@ -51,4 +51,137 @@ mod tests {
         let time_since = now.time_since();
         assert!(time_since <= 10 * NanoTimeStamp::NS_PER_SECOND);
     }
+
+    // 2024-01-15T10:30:00Z, a Monday
+    const MONDAY: u64 = 1_705_314_600;
+
+    fn secs(secs: u64) -> NanoTimeStamp {
+        NanoTimeStamp(secs * NanoTimeStamp::NS_PER_SECOND)
+    }
+
+    #[test]
+    fn test_cron_daily() {
+        let schedule = CronSchedule::daily(0, 0).unwrap();
+
+        // 2024-01-16T00:00:00Z
+        assert_eq!(
+            schedule.next_after(&secs(MONDAY)),
+            Some(secs(1_705_363_200))
+        );
+
+        // Strictly after the given time
+        assert_eq!(
+            schedule.next_after(&secs(1_705_363_200)),
+            Some(secs(1_705_449_600))
+        );
+    }
+
+    #[test]
+    fn test_cron_monthly_and_yearly() {
+        let schedule = CronSchedule::parse("@monthly").unwrap();
+
+        // 2024-02-01T00:00:00Z
+        assert_eq!(
+            schedule.next_after(&secs(MONDAY)),
+            Some(secs(1_706_745_600))
+        );
+
+        let schedule = CronSchedule::parse("0 12 29 2 *").unwrap();
+
+        // 2024-02-29T12:00:00Z, leap day
+        assert_eq!(
+            schedule.next_after(&secs(MONDAY)),
+            Some(secs(1_709_208_000))
+        );
+
+        let schedule = CronSchedule::parse("@yearly").unwrap();
+
+        // 2025-01-01T00:00:00Z
+        assert_eq!(
+            schedule.next_after(&secs(MONDAY)),
+            Some(secs(1_735_689_600))
+        );
+    }
+
+    #[test]
+    fn test_cron_steps_and_weekdays() {
+        let schedule = CronSchedule::parse("*/15 * * * *").unwrap();
+
+        // 2024-01-15T10:45:00Z
+        assert_eq!(
+            schedule.next_after(&secs(MONDAY)),
+            Some(secs(MONDAY + 15 * 60))
+        );
+
+        // Fridays at 09:00
+        let schedule = CronSchedule::weekly(5, 9, 0).unwrap();
+
+        // 2024-01-19T09:00:00Z
+        assert_eq!(
+            schedule.next_after(&secs(MONDAY)),
+            Some(secs(1_705_654_800))
+        );
+
+        // 7 is Sunday as well
+        assert_eq!(
+            CronSchedule::parse("0 0 * * 7").unwrap(),
+            CronSchedule::parse("0 0 * * 0").unwrap()
+        );
+
+        // Day of month or Sunday
+        let schedule = CronSchedule::parse("0 0 20 * 0").unwrap();
+
+        // 2024-01-20T00:00:00Z, the Saturday before the first Sunday
+        assert_eq!(
+            schedule.next_after(&secs(MONDAY)),
+            Some(secs(1_705_708_800))
+        );
+
+        // 2024-01-21T00:00:00Z, the following Sunday
+        assert_eq!(
+            schedule.next_after(&secs(1_705_708_800)),
+            Some(secs(1_705_795_200))
+        );
+    }
+
+    #[test]
+    fn test_cron_invalid() {
+        assert_eq!(
+            CronSchedule::parse("0 0 * *"),
+            Err(CronError::InvalidFieldCount(4))
+        );
+        assert!(matches!(
+            CronSchedule::parse("60 0 * * *"),
+            Err(CronError::OutOfRange(_))
+        ));
+        assert!(matches!(
+            CronSchedule::parse("*/0 0 * * *"),
+            Err(CronError::InvalidValue(_))
+        ));
+        assert!(matches!(
+            CronSchedule::parse("a 0 * * *"),
+            Err(CronError::InvalidValue(_))
+        ));
+
+        // A huge step only keeps the start of the range
+        assert_eq!(
+            CronSchedule::parse("1-5/18446744073709551615 0 * * *").unwrap(),
+            CronSchedule::parse("1 0 * * *").unwrap()
+        );
+
+        // February 30th never happens
+        let schedule = CronSchedule::parse("0 0 30 2 *").unwrap();
+
+        assert_eq!(schedule.next_after(&secs(MONDAY)), None);
+    }
+
+    #[test]
+    fn test_cron_to_and_from_bytes() {
+        let schedule = CronSchedule::parse("5,35 */2 1-15 1,6 1-5").unwrap();
+
+        assert_eq!(
+            CronSchedule::from_le_bytes(schedule.to_le_bytes()),
+            schedule
+        );
+    }
 }
@@ -39,4 +39,4 @@ pub fn deserialize_g2(bytes: &[u8]) -> Result<G2Affine, PairingError> {
     } else {
         Err(PairingError::InvalidCurve)
     }
-}
\ No newline at end of file
+}